@@ -0,0 +1,185 @@
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Read},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+use tracing::{info, warn};
+
+use crate::{
+    cmd::{Command, CommandExecutor},
+    RespDecode, RespEncode, RespError, RespFrame,
+};
+
+use super::Backend;
+
+pub(super) type AofSender = mpsc::UnboundedSender<RespFrame>;
+
+/// Replay every command stored in the append-only file into the backend.
+///
+/// A partially written command at the end of the file (e.g. after a crash) is dropped by
+/// truncating the file to the last complete frame. Returns the number of replayed commands.
+pub(super) fn replay(path: &Path, backend: &Backend) -> Result<usize> {
+    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    let total = data.len();
+
+    let mut buf = BytesMut::from(&data[..]);
+    let mut count = 0;
+    loop {
+        if buf.is_empty() {
+            break;
+        }
+        match RespFrame::decode(&mut buf) {
+            Ok(frame) => {
+                let cmd = Command::try_from(frame)?;
+                cmd.execute(backend);
+                count += 1;
+            }
+            Err(RespError::NotComplete) => {
+                let valid = total - buf.len();
+                warn!(
+                    "aof: truncating {} trailing bytes of an incomplete command",
+                    buf.len()
+                );
+                file.set_len(valid as u64)?;
+                break;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "aof: corrupted at byte {}: {}",
+                    total - buf.len(),
+                    e
+                ))
+            }
+        }
+    }
+    info!("aof: replayed {} commands from {}", count, path.display());
+    Ok(count)
+}
+
+/// Spawn the task appending write commands to the file, so that command execution never
+/// waits on disk io. Must be called within a tokio runtime.
+pub(super) fn spawn_writer(path: &Path) -> Result<AofSender> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut file = tokio::fs::File::from_std(file);
+    let (tx, mut rx) = mpsc::unbounded_channel::<RespFrame>();
+    tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            let data = frame.encode();
+            if let Err(e) = file.write_all(&data).await {
+                warn!("aof: failed to append command: {:?}", e);
+                continue;
+            }
+            if let Err(e) = file.flush().await {
+                warn!("aof: failed to flush: {:?}", e);
+            }
+        }
+    });
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn set_frame(key: &str, value: &str) -> RespFrame {
+        RespArray::new([
+            BulkString::from("set").into(),
+            BulkString::from(key).into(),
+            BulkString::from(value).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_replay_truncates_partial_command() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+        let mut data = set_frame("hello", "world").encode();
+        let complete = data.len();
+        data.extend_from_slice(b"*3\r\n$3\r\nset\r\n$3\r\nfoo");
+        std::fs::write(&path, &data)?;
+
+        let backend = Backend::new();
+        assert_eq!(replay(&path, &backend)?, 1);
+        assert_eq!(backend.get("hello"), Some(BulkString::new("world").into()));
+        assert_eq!(backend.get("foo"), None);
+        assert_eq!(std::fs::metadata(&path)?.len() as usize, complete);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_keeps_absolute_ttl() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let pexpireat = |key: &str, timestamp: i64| -> RespFrame {
+            RespArray::new([
+                BulkString::from("pexpireat").into(),
+                BulkString::from(key).into(),
+                BulkString::from(timestamp.to_string()).into(),
+            ])
+            .into()
+        };
+        let mut data = set_frame("expired", "value").encode();
+        data.extend(pexpireat("expired", now - 1_000).encode());
+        data.extend(set_frame("live", "value").encode());
+        data.extend(pexpireat("live", now + 100_000).encode());
+        std::fs::write(&path, &data)?;
+
+        let backend = Backend::new();
+        assert_eq!(replay(&path, &backend)?, 4);
+        assert_eq!(backend.get("expired"), None);
+        let ttl = backend.ttl("live").unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(90) && ttl <= Duration::from_secs(100));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aof_with_max_keys() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+        let mut data = set_frame("a", "1").encode();
+        data.extend(set_frame("b", "2").encode());
+        data.extend(set_frame("c", "3").encode());
+        std::fs::write(&path, &data)?;
+
+        let backend = Backend::with_max_keys(2).with_aof(&path)?;
+        assert!(backend.aof_enabled());
+        assert_eq!(backend.key_count(), 2);
+        assert!(backend.clone().with_aof(&path).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aof_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+
+        let backend = Backend::new_with_aof(&path)?;
+        backend.set("hello", BulkString::new("world").into());
+        backend.append_aof(set_frame("hello", "world"));
+        let expected = set_frame("hello", "world").encode().len() as u64;
+        for _ in 0..100 {
+            if std::fs::metadata(&path)?.len() == expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let restored = Backend::new_with_aof(&path)?;
+        assert_eq!(restored.get("hello"), Some(BulkString::new("world").into()));
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use super::Backend;
+
+/// Metadata of a connected client, as reported by `CLIENT LIST`.
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub id: u64,
+    pub addr: String,
+    pub name: Option<String>,
+    pub connected_at: Instant,
+    pub db: usize,
+}
+
+/// Registry of the connected clients by id.
+#[derive(Debug, Default)]
+pub(super) struct Clients(DashMap<u64, ClientInfo>);
+
+/// Keeps a client registered until dropped, so the registry is cleaned up however the
+/// connection ends (clean close, error or task cancellation).
+#[derive(Debug)]
+pub struct ClientGuard {
+    backend: Backend,
+    id: u64,
+}
+
+impl ClientInfo {
+    pub fn age(&self) -> Duration {
+        self.connected_at.elapsed()
+    }
+}
+
+impl ClientGuard {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.backend.clients.0.remove(&self.id);
+        self.backend.client_disconnected();
+    }
+}
+
+impl Backend {
+    /// Register a new client connected from `addr` under a fresh id, it stays registered
+    /// until the returned guard is dropped.
+    pub fn register_client(&self, addr: impl Into<String>) -> ClientGuard {
+        let id = self.next_client_id();
+        let info = ClientInfo {
+            id,
+            addr: addr.into(),
+            name: None,
+            connected_at: Instant::now(),
+            db: 0,
+        };
+        self.clients.0.insert(id, info);
+        self.client_connected();
+        ClientGuard {
+            backend: self.clone(),
+            id,
+        }
+    }
+
+    pub fn set_client_name(&self, id: u64, name: Option<String>) {
+        if let Some(mut info) = self.clients.0.get_mut(&id) {
+            info.name = name;
+        }
+    }
+
+    pub fn client(&self, id: u64) -> Option<ClientInfo> {
+        self.clients.0.get(&id).map(|info| info.value().clone())
+    }
+
+    /// connected clients ordered by id
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<_> = self.clients.0.iter().map(|v| v.value().clone()).collect();
+        clients.sort_by_key(|info| info.id);
+        clients
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_registry() {
+        let backend = Backend::new();
+        let first = backend.register_client("127.0.0.1:4000");
+        let second = backend.register_client("127.0.0.1:4001");
+        backend.set_client_name(second.id(), Some("worker".into()));
+
+        let clients = backend.clients();
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].id, first.id());
+        assert_eq!(clients[0].addr, "127.0.0.1:4000");
+        assert_eq!(clients[1].name.as_deref(), Some("worker"));
+        assert_eq!(backend.connected_clients(), 2);
+
+        drop(first);
+        let clients = backend.clients();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].id, second.id());
+        assert_eq!(backend.connected_clients(), 1);
+    }
+}
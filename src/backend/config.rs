@@ -0,0 +1,224 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+
+use super::Backend;
+
+// smallest accepted `proto-max-bulk-len`, like redis: a lower limit would reject the commands
+// needed to raise it again
+const MIN_PROTO_MAX_BULK_LEN: u64 = 1024 * 1024;
+// smallest accepted `client-query-buffer-limit`, like redis
+const MIN_CLIENT_QUERY_BUFFER_LIMIT: u64 = 1024 * 1024;
+// the queue of every subscriber is allocated up to this many messages
+const MAX_PUBSUB_BUFFER_LIMIT: u64 = 1024 * 1024;
+
+/// What happens when the message queue of a subscriber is full, `pubsub-overflow-policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// the subscriber is disconnected, like redis does past its output buffer limit
+    Disconnect,
+    /// the new message is dropped for this subscriber only, the messages already queued
+    /// are kept and delivered first
+    Drop,
+}
+
+/// Runtime tunables, changed with `CONFIG SET`.
+#[derive(Debug)]
+pub(super) struct Config {
+    // idle seconds before a client connection is closed, 0 disables it
+    timeout: AtomicU64,
+    proto_max_bulk_len: AtomicU64,
+    // bytes buffered for a client without a complete request
+    client_query_buffer_limit: AtomicU64,
+    // protocol errors a connection survives, the next one closes it
+    protocol_error_budget: AtomicU64,
+    // messages queued per subscriber, applies to the connections subscribing afterwards
+    pubsub_buffer_limit: AtomicU64,
+    pubsub_drop_on_overflow: AtomicBool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            timeout: AtomicU64::new(0),
+            proto_max_bulk_len: AtomicU64::new(512 * 1024 * 1024),
+            client_query_buffer_limit: AtomicU64::new(512 * 1024 * 1024),
+            // like redis, the first protocol error closes the connection
+            protocol_error_budget: AtomicU64::new(0),
+            pubsub_buffer_limit: AtomicU64::new(10_000),
+            pubsub_drop_on_overflow: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Backend {
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        match self.config.timeout.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// maximum length of a bulk string sent by a client
+    pub fn proto_max_bulk_len(&self) -> u64 {
+        self.config.proto_max_bulk_len.load(Ordering::Relaxed)
+    }
+
+    /// maximum number of bytes read from a client while its request is incomplete
+    pub fn client_query_buffer_limit(&self) -> u64 {
+        self.config
+            .client_query_buffer_limit
+            .load(Ordering::Relaxed)
+    }
+
+    /// number of malformed requests a connection may send, each getting an error reply,
+    /// before the next one closes it
+    pub fn protocol_error_budget(&self) -> u64 {
+        self.config.protocol_error_budget.load(Ordering::Relaxed)
+    }
+
+    /// number of messages queued for a subscriber before `pubsub_overflow_policy` applies
+    pub fn pubsub_buffer_limit(&self) -> usize {
+        self.config.pubsub_buffer_limit.load(Ordering::Relaxed) as usize
+    }
+
+    pub fn pubsub_overflow_policy(&self) -> OverflowPolicy {
+        if self.config.pubsub_drop_on_overflow.load(Ordering::Relaxed) {
+            OverflowPolicy::Drop
+        } else {
+            OverflowPolicy::Disconnect
+        }
+    }
+
+    /// every parameter reported by `CONFIG GET` with its current value
+    pub fn config_params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "appendonly",
+                if self.aof_enabled() { "yes" } else { "no" }.into(),
+            ),
+            (
+                "client-query-buffer-limit",
+                self.client_query_buffer_limit().to_string(),
+            ),
+            ("dbfilename", self.snapshot_path().display().to_string()),
+            ("maxkeys", self.max_keys.unwrap_or(0).to_string()),
+            ("maxmemory", "0".into()),
+            ("maxmemory-policy", self.eviction_policy().name().into()),
+            ("proto-max-bulk-len", self.proto_max_bulk_len().to_string()),
+            (
+                "protocol-error-budget",
+                self.protocol_error_budget().to_string(),
+            ),
+            (
+                "pubsub-buffer-limit",
+                self.pubsub_buffer_limit().to_string(),
+            ),
+            (
+                "pubsub-overflow-policy",
+                match self.pubsub_overflow_policy() {
+                    OverflowPolicy::Disconnect => "disconnect",
+                    OverflowPolicy::Drop => "drop",
+                }
+                .into(),
+            ),
+            ("save", "".into()),
+            (
+                "timeout",
+                self.config.timeout.load(Ordering::Relaxed).to_string(),
+            ),
+        ]
+    }
+
+    /// change a parameter at runtime, only the tunables in `Config` are accepted
+    pub fn config_set(&self, name: &str, value: &str) -> Result<()> {
+        let invalid = || anyhow!("Invalid argument '{}' for CONFIG SET '{}'", value, name);
+        if name == "pubsub-overflow-policy" {
+            let drop = match value.to_ascii_lowercase().as_str() {
+                "disconnect" => false,
+                "drop" => true,
+                _ => return Err(invalid()),
+            };
+            self.config
+                .pubsub_drop_on_overflow
+                .store(drop, Ordering::Relaxed);
+            return Ok(());
+        }
+        let (target, range) = match name {
+            "timeout" => (&self.config.timeout, 0..=u64::MAX),
+            "proto-max-bulk-len" => (
+                &self.config.proto_max_bulk_len,
+                MIN_PROTO_MAX_BULK_LEN..=u64::MAX,
+            ),
+            "client-query-buffer-limit" => (
+                &self.config.client_query_buffer_limit,
+                MIN_CLIENT_QUERY_BUFFER_LIMIT..=u64::MAX,
+            ),
+            "protocol-error-budget" => (&self.config.protocol_error_budget, 0..=u64::MAX),
+            // a subscriber must be able to hold at least one message
+            "pubsub-buffer-limit" => (
+                &self.config.pubsub_buffer_limit,
+                1..=MAX_PUBSUB_BUFFER_LIMIT,
+            ),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown option or number of arguments for CONFIG SET - '{}'",
+                    name
+                ))
+            }
+        };
+        let value: u64 = value
+            .parse()
+            .ok()
+            .filter(|v| range.contains(v))
+            .ok_or_else(invalid)?;
+        target.store(value, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_set() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(backend.idle_timeout(), None);
+        backend.config_set("timeout", "10")?;
+        assert_eq!(backend.idle_timeout(), Some(Duration::from_secs(10)));
+        assert!(backend.config_set("timeout", "abc").is_err());
+        assert!(backend.config_set("maxmemory", "10").is_err());
+
+        assert!(backend.config_set("proto-max-bulk-len", "0").is_err());
+        backend.config_set("proto-max-bulk-len", "1048576")?;
+        assert_eq!(backend.proto_max_bulk_len(), 1048576);
+
+        assert!(backend
+            .config_set("client-query-buffer-limit", "1024")
+            .is_err());
+        backend.config_set("client-query-buffer-limit", "2097152")?;
+        assert_eq!(backend.client_query_buffer_limit(), 2097152);
+
+        assert_eq!(backend.protocol_error_budget(), 0);
+        backend.config_set("protocol-error-budget", "3")?;
+        assert_eq!(backend.protocol_error_budget(), 3);
+
+        assert!(backend.config_set("pubsub-buffer-limit", "0").is_err());
+        assert!(backend
+            .config_set("pubsub-buffer-limit", &u64::MAX.to_string())
+            .is_err());
+        backend.config_set("pubsub-buffer-limit", "100")?;
+        assert_eq!(backend.pubsub_buffer_limit(), 100);
+        assert_eq!(backend.pubsub_overflow_policy(), OverflowPolicy::Disconnect);
+        backend.config_set("pubsub-overflow-policy", "DROP")?;
+        assert_eq!(backend.pubsub_overflow_policy(), OverflowPolicy::Drop);
+        assert!(backend
+            .config_set("pubsub-overflow-policy", "oldest")
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use bytes::BytesMut;
+
+use crate::{RespDecode, RespEncode, RespFrame};
+
+use super::{
+    snapshot::{decode_entry, encode_entry},
+    Backend,
+};
+
+// DUMP payload layout: MAGIC, VERSION, the little endian FNV-1a checksum of the body as 8
+// bytes, then the body `<kind><frame>` encoded like a snapshot entry.
+const MAGIC: &[u8] = b"SRDUMP";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+
+impl Backend {
+    /// Serialize the value of the key for `restore`, `None` if the key doesn't exist. The
+    /// time to live isn't part of the payload.
+    pub fn dump(&self, key: &str) -> Option<Vec<u8>> {
+        let (kind, value) = self.entry_frame(key)?;
+        let mut body = vec![kind];
+        body.extend(value.encode());
+        let mut payload = Vec::with_capacity(HEADER_LEN + body.len());
+        payload.extend_from_slice(MAGIC);
+        payload.push(VERSION);
+        payload.extend_from_slice(&checksum(&body).to_le_bytes());
+        payload.append(&mut body);
+        Some(payload)
+    }
+
+    /// length of the frame the value of the key is serialized as, `None` if the key doesn't
+    /// exist
+    pub fn serialized_len(&self, key: &str) -> Option<usize> {
+        self.entry_frame(key).map(|(_, value)| value.encoded_len())
+    }
+
+    // the kind of the value of the key and the frame it is serialized as, like a snapshot
+    // entry
+    fn entry_frame(&self, key: &str) -> Option<(u8, RespFrame)> {
+        self.expire_if_needed(key);
+        self.keys.get(key).map(|value| encode_entry(value.value()))
+    }
+
+    /// Store the value serialized by `dump` under the key with an optional time to live,
+    /// replacing any existing value. The key is left untouched if the payload is invalid.
+    pub fn restore(&self, key: &str, payload: &[u8], ttl: Option<Duration>) -> Result<()> {
+        if payload.len() < HEADER_LEN || !payload.starts_with(MAGIC) {
+            return Err(anyhow!("dump: invalid header"));
+        }
+        if payload[MAGIC.len()] != VERSION {
+            return Err(anyhow!("dump: unsupported version"));
+        }
+        let (sum, body) = payload[MAGIC.len() + 1..].split_at(8);
+        if body.is_empty() || sum != checksum(body).to_le_bytes() {
+            return Err(anyhow!("dump: checksum mismatch"));
+        }
+
+        let kind = body[0];
+        let mut buf = BytesMut::from(&body[1..]);
+        let value = RespFrame::decode(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(anyhow!("dump: trailing data"));
+        }
+        let value = decode_entry(kind, value)?;
+
+        self.insert_entry(key, value);
+        if let Some(ttl) = ttl {
+            self.expire(key, ttl);
+        }
+        Ok(())
+    }
+}
+
+// 64 bit FNV-1a, enough to catch truncated or corrupted payloads
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::snapshot::KIND_HASH, BulkString, RespArray, SimpleString};
+
+    #[test]
+    fn test_dump_restore_round_trip() -> Result<()> {
+        let backend = Backend::new();
+        assert!(backend.dump("missing").is_none());
+
+        backend.set("hello", BulkString::new("world").into());
+        backend.hset("user", "name", SimpleString::new("alice").into());
+        let string = backend.dump("hello").unwrap();
+        let hash = backend.dump("user").unwrap();
+
+        // restoring replaces a value of another type
+        backend.restore("user", &string, None)?;
+        assert_eq!(backend.get("user"), Some(BulkString::new("world").into()));
+        assert_eq!(backend.key_type("user"), Some("string"));
+
+        backend.restore("copy", &hash, Some(Duration::from_secs(100)))?;
+        assert_eq!(
+            backend.hget("copy", "name"),
+            Some(SimpleString::new("alice").into())
+        );
+        assert!(backend.ttl("copy").unwrap().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_rejects_corrupted_payload() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        let payload = backend.dump("hello").unwrap();
+
+        let mut corrupted = payload.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(backend.restore("hello", &corrupted, None).is_err());
+        assert!(backend
+            .restore("hello", &payload[..HEADER_LEN], None)
+            .is_err());
+        assert!(backend.restore("hello", b"garbage", None).is_err());
+
+        // a valid checksum over a hash with a field that isn't utf-8
+        let mut body = vec![KIND_HASH];
+        body.extend(
+            RespFrame::from(RespArray::new([
+                BulkString::new(vec![0xff, 0xfe]).into(),
+                BulkString::new("value").into(),
+            ]))
+            .encode(),
+        );
+        let mut invalid = MAGIC.to_vec();
+        invalid.push(VERSION);
+        invalid.extend_from_slice(&checksum(&body).to_le_bytes());
+        invalid.extend(body);
+        assert!(backend.restore("hello", &invalid, None).is_err());
+
+        let mut newer = payload.clone();
+        newer[MAGIC.len()] = VERSION + 1;
+        assert!(backend.restore("hello", &newer, None).is_err());
+        // the key is left untouched
+        assert_eq!(backend.get("hello"), Some(BulkString::new("world").into()));
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    str::FromStr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Error};
+
+use super::{random::random_index, Backend, BackendInner};
+
+// like redis: the counter of a new key, so it isn't evicted before it can be accessed again
+const LFU_INIT_VAL: u8 = 5;
+// the higher, the more accesses it takes to increment a high counter
+const LFU_LOG_FACTOR: usize = 10;
+const LFU_DECAY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Which key is evicted when inserting a new key would exceed the maximum number of keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// the least recently used key
+    #[default]
+    AllKeysLru,
+    /// the least frequently used key, see `OBJECT FREQ`
+    AllKeysLfu,
+}
+
+impl EvictionPolicy {
+    /// name of the policy, as the redis `maxmemory-policy`
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvictionPolicy::AllKeysLru => "allkeys-lru",
+            EvictionPolicy::AllKeysLfu => "allkeys-lfu",
+        }
+    }
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "allkeys-lru" => Ok(EvictionPolicy::AllKeysLru),
+            "allkeys-lfu" => Ok(EvictionPolicy::AllKeysLfu),
+            _ => Err(anyhow!("unknown eviction policy '{}'", s)),
+        }
+    }
+}
+
+/// per-key bookkeeping for eviction, `OBJECT IDLETIME` and `OBJECT FREQ`
+#[derive(Debug)]
+pub(super) struct KeyMeta {
+    // logical clock value of the last access, higher is more recent
+    last_access: u64,
+    accessed_at: Instant,
+    // logarithmic access counter, as of `accessed_at`
+    frequency: u8,
+}
+
+impl KeyMeta {
+    // like redis, the counter is decremented once per decay period without access, so keys
+    // popular a while ago don't stay forever
+    fn decayed_frequency(&self) -> u8 {
+        let periods = self.accessed_at.elapsed().as_secs() / LFU_DECAY_PERIOD.as_secs();
+        self.frequency
+            .saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+}
+
+// the counter after one more access: the higher it is the less likely it's incremented, so
+// 255 stands for about a million accesses
+fn increment_frequency(frequency: u8) -> u8 {
+    if frequency == u8::MAX {
+        return frequency;
+    }
+    let base = frequency.saturating_sub(LFU_INIT_VAL) as usize;
+    if random_index(base * LFU_LOG_FACTOR + 1) == 0 {
+        frequency + 1
+    } else {
+        frequency
+    }
+}
+
+impl Backend {
+    /// Create a backend holding at most `max_keys` keys. When inserting a new key would
+    /// exceed the limit, the least recently used key is evicted (`allkeys-lru`).
+    pub fn with_max_keys(max_keys: usize) -> Self {
+        Self::with_eviction(max_keys, EvictionPolicy::AllKeysLru)
+    }
+
+    /// Like `with_max_keys`, evicting the keys chosen by `policy`.
+    pub fn with_eviction(max_keys: usize, policy: EvictionPolicy) -> Self {
+        Self(Arc::new(BackendInner {
+            max_keys: Some(max_keys),
+            eviction_policy: policy,
+            ..Default::default()
+        }))
+    }
+
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.eviction_policy
+    }
+
+    /// mark the key as just accessed
+    pub(super) fn touch(&self, key: &str) {
+        let last_access = self.clock.fetch_add(1, Ordering::Relaxed);
+        match self.meta.get_mut(key) {
+            Some(mut meta) => {
+                meta.frequency = increment_frequency(meta.decayed_frequency());
+                meta.last_access = last_access;
+                meta.accessed_at = Instant::now();
+            }
+            None => {
+                let meta = KeyMeta {
+                    last_access,
+                    accessed_at: Instant::now(),
+                    frequency: LFU_INIT_VAL,
+                };
+                self.meta.insert(key.to_string(), meta);
+            }
+        }
+    }
+
+    /// mark the key as just accessed if it exists, as `TOUCH` does, returns whether it exists
+    pub fn touch_key(&self, key: &str) -> bool {
+        let exists = self.key_type(key).is_some();
+        if exists {
+            self.touch(key);
+        }
+        exists
+    }
+
+    /// Time since the key was last read or written, `None` if it doesn't exist. Doesn't
+    /// count as an access of the key.
+    pub fn idle_time(&self, key: &str) -> Option<Duration> {
+        self.key_type(key)?;
+        Some(
+            self.meta
+                .get(key)
+                .map(|meta| meta.accessed_at.elapsed())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Logarithmic access counter of the key, `None` if it doesn't exist. Doesn't count as
+    /// an access of the key.
+    pub fn access_frequency(&self, key: &str) -> Option<u8> {
+        self.key_type(key)?;
+        Some(
+            self.meta
+                .get(key)
+                .map(|meta| meta.decayed_frequency())
+                .unwrap_or(LFU_INIT_VAL),
+        )
+    }
+
+    /// evict keys until a new `key` fits in the configured limit. Finding the victim scans
+    /// all keys, which is fine for the key counts this server targets.
+    pub(super) fn make_room_for(&self, key: &str) {
+        let Some(max_keys) = self.max_keys else {
+            return;
+        };
+        if self.key_type(key).is_some() {
+            return;
+        }
+        while self.key_count() >= max_keys {
+            if !self.evict() {
+                break;
+            }
+        }
+    }
+
+    fn evict(&self) -> bool {
+        let victim = match self.eviction_policy {
+            EvictionPolicy::AllKeysLru => self.meta.iter().min_by_key(|v| v.value().last_access),
+            // the least recently used of the least frequently used keys
+            EvictionPolicy::AllKeysLfu => self
+                .meta
+                .iter()
+                .min_by_key(|v| (v.value().decayed_frequency(), v.value().last_access)),
+        }
+        .map(|v| v.key().clone());
+        match victim {
+            Some(key) => self.remove(&key),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_idle_time() {
+        let backend = Backend::new();
+        assert!(backend.idle_time("a").is_none());
+        backend.set("a", BulkString::new("1").into());
+        std::thread::sleep(Duration::from_millis(20));
+        let idle = backend.idle_time("a").unwrap();
+        assert!(idle >= Duration::from_millis(20));
+        // reading the idle time isn't an access
+        assert!(backend.idle_time("a").unwrap() >= idle);
+        backend.get("a");
+        assert!(backend.idle_time("a").unwrap() < idle);
+    }
+
+    #[test]
+    fn test_touch_key_protects_from_eviction() {
+        let backend = Backend::with_max_keys(2);
+        backend.set("a", BulkString::new("1").into());
+        backend.set("b", BulkString::new("2").into());
+        assert!(backend.touch_key("a"));
+        assert!(!backend.touch_key("missing"));
+        backend.set("c", BulkString::new("3").into());
+
+        assert!(backend.get("a").is_some());
+        assert!(backend.get("b").is_none());
+        // touching a missing key doesn't create it
+        assert!(backend.idle_time("missing").is_none());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let backend = Backend::with_max_keys(2);
+        backend.set("a", BulkString::new("1").into());
+        backend.set("b", BulkString::new("2").into());
+        // access a so b becomes the least recently used key
+        assert!(backend.get("a").is_some());
+        backend.hset("c", "field", BulkString::new("3").into());
+
+        assert_eq!(backend.key_count(), 2);
+        assert!(backend.get("a").is_some());
+        assert!(backend.get("b").is_none());
+        assert!(backend.hget("c", "field").is_some());
+
+        // overwriting an existing key never evicts
+        backend.set("a", BulkString::new("4").into());
+        assert_eq!(backend.key_count(), 2);
+        assert!(backend.hget("c", "field").is_some());
+    }
+
+    #[test]
+    fn test_lfu_eviction() {
+        let backend = Backend::with_eviction(2, EvictionPolicy::AllKeysLfu);
+        backend.set("hot", BulkString::new("1").into());
+        for _ in 0..100 {
+            assert!(backend.get("hot").is_some());
+        }
+        // the most recently used key, but accessed only once
+        backend.set("cold", BulkString::new("2").into());
+        assert!(backend.access_frequency("hot") > backend.access_frequency("cold"));
+        assert_eq!(backend.access_frequency("cold"), Some(LFU_INIT_VAL));
+        assert_eq!(backend.access_frequency("missing"), None);
+
+        backend.set("new", BulkString::new("3").into());
+        assert!(backend.get("hot").is_some());
+        assert!(backend.get("cold").is_none());
+        assert!(backend.get("new").is_some());
+    }
+
+    #[test]
+    fn test_frequency_increments_less_when_high() {
+        assert_eq!(increment_frequency(LFU_INIT_VAL), LFU_INIT_VAL + 1);
+        assert_eq!(increment_frequency(u8::MAX), u8::MAX);
+        let mut frequency = LFU_INIT_VAL;
+        for _ in 0..1000 {
+            frequency = increment_frequency(frequency);
+        }
+        // a linear counter would have reached 255
+        assert!(frequency > LFU_INIT_VAL + 1 && frequency < 100);
+    }
+}
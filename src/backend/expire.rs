@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::Backend;
+
+impl Backend {
+    /// Set a time to live on an existing key, returns false if the key doesn't exist.
+    pub fn expire(&self, key: &str, ttl: Duration) -> bool {
+        self.expire_at(key, Instant::now() + ttl)
+    }
+
+    /// Set the instant an existing key expires at, returns false if the key doesn't exist.
+    pub fn expire_at(&self, key: &str, when: Instant) -> bool {
+        if self.key_type(key).is_none() {
+            return false;
+        }
+        self.expires.insert(key.to_string(), when);
+        true
+    }
+
+    /// Remove the expiry of the key, returns false if the key doesn't exist or has no expiry.
+    pub fn persist(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.expires.remove(key).is_some()
+    }
+
+    /// Remaining time to live of the key: `None` if the key doesn't exist,
+    /// `Some(None)` if it exists without an expiry.
+    pub fn ttl(&self, key: &str) -> Option<Option<Duration>> {
+        self.key_type(key)?;
+        Some(
+            self.expires
+                .get(key)
+                .map(|when| when.saturating_duration_since(Instant::now())),
+        )
+    }
+
+    /// number of keys with a time to live
+    pub fn expires_count(&self) -> usize {
+        self.expires.len()
+    }
+
+    /// Lazily remove the key if its time to live has passed, returns whether it was removed.
+    pub(super) fn expire_if_needed(&self, key: &str) -> bool {
+        let expired = self
+            .expires
+            .get(key)
+            .map(|when| *when <= Instant::now())
+            .unwrap_or(false);
+        if expired {
+            self.remove(key);
+        }
+        expired
+    }
+
+    /// Remove up to `batch` expired keys, returns the number of removed keys.
+    pub fn remove_expired(&self, batch: usize) -> usize {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .expires
+            .iter()
+            .filter(|v| *v.value() <= now)
+            .take(batch)
+            .map(|v| v.key().clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+        }
+        expired.len()
+    }
+
+    /// Spawn the active expiration task: every `interval` it removes expired keys in batches
+    /// of `batch`, so keys that are never read again don't stay in memory. Like redis, it
+    /// keeps going while full batches are found. Must be called within a tokio runtime.
+    pub fn spawn_expiration_reaper(&self, interval: Duration, batch: usize) -> JoinHandle<()> {
+        // an empty batch would never end a cycle, and a zero interval makes `interval` panic
+        let interval = interval.max(Duration::from_millis(1));
+        let batch = batch.max(1);
+        let backend = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                loop {
+                    let n = backend.remove_expired(batch);
+                    if n > 0 {
+                        debug!("removed {} expired keys", n);
+                    }
+                    if n < batch {
+                        break;
+                    }
+                    tokio::task::yield_now().await;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_lazy_expiration() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        assert!(backend.expire("hello", Duration::from_secs(100)));
+        assert!(backend.ttl("hello").unwrap().unwrap() > Duration::from_secs(99));
+        assert!(!backend.expire("missing", Duration::from_secs(100)));
+
+        assert!(backend.persist("hello"));
+        assert_eq!(backend.ttl("hello"), Some(None));
+        assert!(!backend.persist("hello"));
+
+        backend.expire_at("hello", Instant::now());
+        assert_eq!(backend.get("hello"), None);
+        assert_eq!(backend.ttl("hello"), None);
+    }
+
+    #[tokio::test]
+    async fn test_expiration_reaper() {
+        let backend = Backend::new();
+        for i in 0..10 {
+            let key = format!("key{}", i);
+            backend.set(&key, BulkString::new("value").into());
+            backend.expire_at(&key, Instant::now());
+        }
+        backend.set("keep", BulkString::new("value").into());
+        assert_eq!(backend.key_count(), 11);
+
+        let handle = backend.spawn_expiration_reaper(Duration::from_millis(10), 3);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.abort();
+        assert_eq!(backend.key_count(), 1);
+        assert_eq!(backend.ttl("keep"), Some(None));
+    }
+
+    #[tokio::test]
+    async fn test_expiration_reaper_with_zero_settings() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        backend.expire_at("hello", Instant::now());
+
+        let handle = backend.spawn_expiration_reaper(Duration::ZERO, 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        handle.abort();
+        assert_eq!(backend.key_count(), 0);
+    }
+}
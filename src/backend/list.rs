@@ -0,0 +1,444 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use tokio::sync::Notify;
+
+use crate::{cmd::index_range, BulkString, RespArray, RespFrame};
+
+use super::{Backend, Value};
+
+/// End of a list elements are pushed to or popped from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+/// Wakers of the clients blocked in `blocking_pop`, registered under each key they wait on.
+pub(super) type Waiters = DashMap<String, Vec<Arc<Notify>>>;
+
+impl Backend {
+    /// Push the values one after the other to the given end of the list, creating it if the
+    /// key doesn't exist, and return the new length. Clients blocked on the key are woken.
+    pub fn push(
+        &self,
+        key: &str,
+        end: ListEnd,
+        values: impl IntoIterator<Item = RespFrame>,
+    ) -> usize {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let len = {
+            let new = || Value::List(Default::default());
+            let Some(mut list) = self.get_or_insert_as(key, new, Value::as_list_mut) else {
+                return 0;
+            };
+            for value in values {
+                match end {
+                    ListEnd::Left => list.push_front(value),
+                    ListEnd::Right => list.push_back(value),
+                }
+            }
+            list.len()
+        };
+        self.touch(key);
+        if let Some(waiters) = self.list_waiters.get(key) {
+            for waiter in waiters.iter() {
+                waiter.notify_one();
+            }
+        }
+        len
+    }
+
+    /// Pop an element from the given end of the list, the key is removed with its last
+    /// element. `None` if the key doesn't exist.
+    pub fn pop(&self, key: &str, end: ListEnd) -> Option<RespFrame> {
+        self.expire_if_needed(key);
+        let value = {
+            let mut list = self.get_mut_as(key, Value::as_list_mut)?;
+            match end {
+                ListEnd::Left => list.pop_front(),
+                ListEnd::Right => list.pop_back(),
+            }
+        };
+        self.remove_if_empty(key);
+        value
+    }
+
+    /// Replace the element at `index`, negative indices counting from the end. `None` if
+    /// the key doesn't exist, `Some(false)` if the index is out of range.
+    pub fn lset(&self, key: &str, index: i64, value: RespFrame) -> Option<bool> {
+        self.expire_if_needed(key);
+        let set = {
+            let mut list = self.get_mut_as(key, Value::as_list_mut)?;
+            match list_index(list.len(), index).and_then(|i| list.get_mut(i)) {
+                Some(element) => {
+                    *element = value;
+                    true
+                }
+                None => false,
+            }
+        };
+        self.touch(key);
+        Some(set)
+    }
+
+    /// Insert the value before or after the first element equal to `pivot` and return the
+    /// new length. `None` if the key doesn't exist, `Some(-1)` if no element is `pivot`.
+    pub fn linsert(
+        &self,
+        key: &str,
+        before: bool,
+        pivot: &RespFrame,
+        value: RespFrame,
+    ) -> Option<i64> {
+        self.expire_if_needed(key);
+        let len = {
+            let mut list = self.get_mut_as(key, Value::as_list_mut)?;
+            match list.iter().position(|element| element == pivot) {
+                Some(i) => {
+                    list.insert(if before { i } else { i + 1 }, value);
+                    list.len() as i64
+                }
+                None => -1,
+            }
+        };
+        self.touch(key);
+        Some(len)
+    }
+
+    /// Remove up to `count` elements equal to `value`, from the head, or from the tail if
+    /// `count` is negative, or all of them if it's 0. Returns the number removed, the key is
+    /// removed with its last element.
+    pub fn lrem(&self, key: &str, count: i64, value: &RespFrame) -> usize {
+        self.expire_if_needed(key);
+        let removed = {
+            let Some(mut list) = self.get_mut_as(key, Value::as_list_mut) else {
+                return 0;
+            };
+            let limit = match count.unsigned_abs() {
+                0 => usize::MAX,
+                n => usize::try_from(n).unwrap_or(usize::MAX),
+            };
+            let mut positions = list
+                .iter()
+                .enumerate()
+                .filter(|(_, element)| *element == value)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            if count < 0 {
+                positions.reverse();
+            }
+            positions.truncate(limit);
+            // remove from the back so the positions left stay valid
+            positions.sort_unstable_by(|a, b| b.cmp(a));
+            for &i in &positions {
+                list.remove(i);
+            }
+            positions.len()
+        };
+        self.remove_if_empty(key);
+        removed
+    }
+
+    /// Positions of the elements equal to `value`, skipping the first `rank - 1` matches,
+    /// searching from the tail if `rank` is negative. At most `count` positions are returned,
+    /// all of them if it's 0.
+    pub fn lpos(&self, key: &str, value: &RespFrame, rank: i64, count: usize) -> Vec<usize> {
+        let Some(list) = self.get_live(key, Value::as_list) else {
+            return vec![];
+        };
+        let matches = list
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| *element == value)
+            .map(|(i, _)| i);
+        let skip = usize::try_from(rank.unsigned_abs() - 1).unwrap_or(usize::MAX);
+        let limit = if count == 0 { usize::MAX } else { count };
+        let positions = if rank < 0 {
+            matches.rev().skip(skip).take(limit).collect()
+        } else {
+            matches.skip(skip).take(limit).collect()
+        };
+        drop(list);
+        self.touch(key);
+        positions
+    }
+
+    /// Keep only the elements between the inclusive `start` and `stop` indices, negative
+    /// indices counting from the end. The key is removed if no element is left.
+    pub fn ltrim(&self, key: &str, start: i64, stop: i64) {
+        self.expire_if_needed(key);
+        {
+            let Some(mut list) = self.get_mut_as(key, Value::as_list_mut) else {
+                return;
+            };
+            let range = index_range(list.len(), start, stop);
+            list.truncate(range.end);
+            list.drain(..range.start);
+        }
+        self.remove_if_empty(key);
+    }
+
+    // remove the key once its list has no element left, otherwise it's an access
+    fn remove_if_empty(&self, key: &str) {
+        if self
+            .keys
+            .remove_if(key, |_, v| v.as_list().is_some_and(|list| list.is_empty()))
+            .is_some()
+        {
+            self.remove(key);
+        } else {
+            self.touch(key);
+        }
+    }
+
+    /// elements of the list from left to right. `None` if the key doesn't exist.
+    pub fn list_elements(&self, key: &str) -> Option<Vec<RespFrame>> {
+        let elements = self
+            .get_live(key, Value::as_list)
+            .map(|list| list.iter().cloned().collect());
+        if elements.is_some() {
+            self.touch(key);
+        }
+        elements
+    }
+
+    /// Pop an element from the first non-empty list of `keys`, waiting up to `timeout` (or
+    /// forever if `None`) for a push to any of them. Returns the key with the element, or
+    /// `None` on timeout. The pop is appended to the aof as `LPOP`/`RPOP` while holding the
+    /// write locks, but the wait doesn't hold them. Dropping the future, e.g. when the client
+    /// disconnects, unregisters it without popping anything.
+    pub async fn blocking_pop(
+        &self,
+        keys: &[String],
+        end: ListEnd,
+        timeout: Option<Duration>,
+    ) -> Option<(String, RespFrame)> {
+        let waiter = Registration::new(&self.list_waiters, keys);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            {
+                let _writes = self.lock_writes().await;
+                let _aof_order = self.lock_aof().await;
+                for key in keys {
+                    if let Some(value) = self.pop(key, end) {
+                        self.append_aof(pop_frame(key, end));
+                        self.signal_modified(Some(std::slice::from_ref(key)));
+                        return Some((key.clone(), value));
+                    }
+                }
+            }
+            // a push between the attempt above and this wait leaves a permit, so it isn't
+            // missed
+            match deadline {
+                Some(deadline) => {
+                    let notified = waiter.notify.notified();
+                    if tokio::time::timeout_at(deadline.into(), notified)
+                        .await
+                        .is_err()
+                    {
+                        return None;
+                    }
+                }
+                None => waiter.notify.notified().await,
+            }
+        }
+    }
+}
+
+// position of `index` in a list of `len` elements, negative indices counting from the end
+fn list_index(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    usize::try_from(index).ok().filter(|&i| i < len)
+}
+
+// `LPOP key` or `RPOP key`, the form a blocking pop is appended to the aof in
+fn pop_frame(key: &str, end: ListEnd) -> RespFrame {
+    let name = match end {
+        ListEnd::Left => "lpop",
+        ListEnd::Right => "rpop",
+    };
+    RespArray::new([BulkString::from(name).into(), BulkString::from(key).into()]).into()
+}
+
+// the waker of a blocked client under each of its keys, unregistered when dropped
+struct Registration<'a> {
+    waiters: &'a Waiters,
+    keys: &'a [String],
+    notify: Arc<Notify>,
+}
+
+impl<'a> Registration<'a> {
+    fn new(waiters: &'a Waiters, keys: &'a [String]) -> Self {
+        let notify = Arc::new(Notify::new());
+        for key in keys {
+            waiters.entry(key.clone()).or_default().push(notify.clone());
+        }
+        Registration {
+            waiters,
+            keys,
+            notify,
+        }
+    }
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        for key in self.keys {
+            self.waiters.remove_if_mut(key, |_, waiters| {
+                waiters.retain(|waiter| !Arc::ptr_eq(waiter, &self.notify));
+                waiters.is_empty()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let backend = Backend::new();
+        assert_eq!(backend.push("l", ListEnd::Right, [bulk("b"), bulk("c")]), 2);
+        assert_eq!(backend.push("l", ListEnd::Left, [bulk("a")]), 3);
+        assert_eq!(backend.key_type("l"), Some("list"));
+        assert_eq!(
+            backend.list_elements("l"),
+            Some(vec![bulk("a"), bulk("b"), bulk("c")])
+        );
+
+        assert_eq!(backend.pop("l", ListEnd::Right), Some(bulk("c")));
+        assert_eq!(backend.pop("l", ListEnd::Left), Some(bulk("a")));
+        assert_eq!(backend.pop("l", ListEnd::Left), Some(bulk("b")));
+        // the key is removed with its last element
+        assert_eq!(backend.key_type("l"), None);
+        assert_eq!(backend.pop("l", ListEnd::Left), None);
+    }
+
+    #[test]
+    fn test_lset_linsert_and_lrem() {
+        let backend = Backend::new();
+        backend.push("l", ListEnd::Right, ["a", "b", "a", "c", "a"].map(bulk));
+
+        assert_eq!(backend.lset("l", -1, bulk("z")), Some(true));
+        assert_eq!(backend.lset("l", 5, bulk("z")), Some(false));
+        assert_eq!(backend.lset("l", -6, bulk("z")), Some(false));
+        assert_eq!(backend.lset("missing", 0, bulk("z")), None);
+
+        assert_eq!(backend.linsert("l", true, &bulk("b"), bulk("x")), Some(6));
+        assert_eq!(backend.linsert("l", false, &bulk("z"), bulk("y")), Some(7));
+        assert_eq!(backend.linsert("l", true, &bulk("q"), bulk("x")), Some(-1));
+        assert_eq!(
+            backend.linsert("missing", true, &bulk("a"), bulk("x")),
+            None
+        );
+        let elements = ["a", "x", "b", "a", "c", "z", "y"].map(bulk);
+        assert_eq!(backend.list_elements("l"), Some(elements.to_vec()));
+
+        backend.push("l", ListEnd::Right, [bulk("a")]);
+        // from the tail, then all of them
+        assert_eq!(backend.lrem("l", -1, &bulk("a")), 1);
+        let elements = ["a", "x", "b", "a", "c", "z", "y"].map(bulk);
+        assert_eq!(backend.list_elements("l"), Some(elements.to_vec()));
+        assert_eq!(backend.lrem("l", 1, &bulk("a")), 1);
+        assert_eq!(backend.list_elements("l").unwrap()[0], bulk("x"));
+        assert_eq!(backend.lrem("l", 0, &bulk("a")), 1);
+        assert_eq!(backend.lrem("l", 0, &bulk("q")), 0);
+        assert_eq!(backend.lrem("missing", 0, &bulk("a")), 0);
+
+        for value in ["x", "b", "c", "z", "y"] {
+            assert_eq!(backend.lrem("l", 0, &bulk(value)), 1);
+        }
+        assert_eq!(backend.key_type("l"), None);
+    }
+
+    #[test]
+    fn test_lpos() {
+        let backend = Backend::new();
+        let values = ["a", "b", "a", "c", "a"].map(bulk);
+        backend.push("l", ListEnd::Right, values);
+        assert_eq!(backend.lpos("l", &bulk("a"), 1, 1), [0]);
+        assert_eq!(backend.lpos("l", &bulk("a"), 2, 0), [2, 4]);
+        assert_eq!(backend.lpos("l", &bulk("a"), -1, 2), [4, 2]);
+        assert_eq!(backend.lpos("l", &bulk("a"), -4, 0), []);
+        assert_eq!(backend.lpos("missing", &bulk("a"), 1, 0), []);
+    }
+
+    #[test]
+    fn test_ltrim() {
+        let backend = Backend::new();
+        backend.push("l", ListEnd::Right, ["a", "b", "c", "d", "e"].map(bulk));
+        backend.ltrim("l", 1, -2);
+        let elements = ["b", "c", "d"].map(bulk);
+        assert_eq!(backend.list_elements("l"), Some(elements.to_vec()));
+        backend.ltrim("l", -2, 10);
+        let elements = ["c", "d"].map(bulk);
+        assert_eq!(backend.list_elements("l"), Some(elements.to_vec()));
+
+        backend.ltrim("l", 1, 0);
+        assert_eq!(backend.key_type("l"), None);
+        backend.ltrim("missing", 0, -1);
+        assert_eq!(backend.key_type("missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_returns_available_element() {
+        let backend = Backend::new();
+        backend.push("b", ListEnd::Right, [bulk("x")]);
+        let keys = ["a".to_string(), "b".to_string()];
+        let popped = backend
+            .blocking_pop(&keys, ListEnd::Left, Some(Duration::from_millis(10)))
+            .await;
+        assert_eq!(popped, Some(("b".to_string(), bulk("x"))));
+
+        let popped = backend
+            .blocking_pop(&keys, ListEnd::Left, Some(Duration::from_millis(10)))
+            .await;
+        assert_eq!(popped, None);
+        assert!(backend.list_waiters.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_blocking_pop_is_woken_by_push() {
+        let backend = Backend::new();
+        let waiter = {
+            let backend = backend.clone();
+            tokio::spawn(async move {
+                let keys = ["queue".to_string()];
+                backend.blocking_pop(&keys, ListEnd::Right, None).await
+            })
+        };
+        while backend.list_waiters.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        backend.push("queue", ListEnd::Left, [bulk("job")]);
+        let popped = waiter.await.unwrap();
+        assert_eq!(popped, Some(("queue".to_string(), bulk("job"))));
+        assert_eq!(backend.key_type("queue"), None);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_blocking_pop_takes_nothing() {
+        let backend = Backend::new();
+        let keys = ["queue".to_string()];
+        let timeout = tokio::time::timeout(
+            Duration::from_millis(10),
+            backend.blocking_pop(&keys, ListEnd::Left, None),
+        )
+        .await;
+        assert!(timeout.is_err());
+        assert!(backend.list_waiters.is_empty());
+
+        backend.push("queue", ListEnd::Left, [bulk("job")]);
+        assert_eq!(backend.pop("queue", ListEnd::Left), Some(bulk("job")));
+    }
+}
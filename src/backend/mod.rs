@@ -1,16 +1,90 @@
-use std::{ops::Deref, sync::Arc};
+mod aof;
+mod clients;
+mod config;
+mod dump;
+mod eviction;
+mod expire;
+mod list;
+mod monitor;
+mod pubsub;
+mod random;
+mod set;
+mod shutdown;
+mod snapshot;
+mod store;
+mod string;
+mod value;
+mod watch;
+mod zset;
 
-use dashmap::DashMap;
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::RespFrame;
+use anyhow::{anyhow, Result};
+use dashmap::{
+    mapref::{
+        entry::Entry,
+        one::{MappedRef, MappedRefMut},
+    },
+    DashMap,
+};
+
+use crate::{BulkString, RespArray, RespFrame, RespMap, RespSet};
+
+pub use eviction::EvictionPolicy;
+pub use list::ListEnd;
+pub use pubsub::MessageSender;
+pub use store::Store;
+pub(crate) use string::string_bytes;
+pub use string::{SetCondition, SetFlags};
+use value::Value;
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackendInner>);
 
 #[derive(Debug)]
 pub struct BackendInner {
-    map: DashMap<String, RespFrame>,
-    hmap: DashMap<String, DashMap<String, RespFrame>>,
+    // the values of every type, a key holds exactly one
+    keys: DashMap<String, Value>,
+    // clients blocked in BLPOP/BRPOP, by the keys they wait on
+    list_waiters: list::Waiters,
+    stats: Stats,
+    aof: Option<aof::AofSender>,
+    // serializes write commands while aof is enabled, see `lock_aof`
+    aof_order: tokio::sync::Mutex<()>,
+    // shared by write commands, exclusive for transactions, see `lock_writes`
+    writes: tokio::sync::RwLock<()>,
+    // clients watching keys for their transaction
+    watchers: watch::Watchers,
+    snapshot_path: RwLock<PathBuf>,
+    // access tracking used by eviction
+    meta: DashMap<String, eviction::KeyMeta>,
+    clock: AtomicU64,
+    max_keys: Option<usize>,
+    eviction_policy: eviction::EvictionPolicy,
+    expires: DashMap<String, Instant>,
+    config: config::Config,
+    pubsub: pubsub::PubSub,
+    clients: clients::Clients,
+    // connections in MONITOR
+    monitors: monitor::Monitors,
+    shutdown: shutdown::ShutdownSignal,
+}
+
+#[derive(Debug)]
+struct Stats {
+    started_at: Instant,
+    total_commands: AtomicU64,
+    connected_clients: AtomicU64,
+    command_calls: DashMap<&'static str, AtomicU64>,
+    next_client_id: AtomicU64,
 }
 
 impl Deref for Backend {
@@ -23,10 +97,55 @@ impl Deref for Backend {
 
 impl Default for Backend {
     fn default() -> Self {
-        Self(Arc::new(BackendInner {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
-        }))
+        Self(Arc::new(BackendInner::default()))
+    }
+}
+
+impl Default for BackendInner {
+    fn default() -> Self {
+        BackendInner {
+            keys: DashMap::new(),
+            list_waiters: list::Waiters::default(),
+            stats: Stats {
+                started_at: Instant::now(),
+                total_commands: AtomicU64::new(0),
+                connected_clients: AtomicU64::new(0),
+                command_calls: DashMap::new(),
+                next_client_id: AtomicU64::new(1),
+            },
+            aof: None,
+            aof_order: tokio::sync::Mutex::new(()),
+            writes: tokio::sync::RwLock::new(()),
+            watchers: watch::Watchers::default(),
+            snapshot_path: RwLock::new(PathBuf::from("dump.rdb")),
+            meta: DashMap::new(),
+            clock: AtomicU64::new(0),
+            max_keys: None,
+            eviction_policy: eviction::EvictionPolicy::default(),
+            expires: DashMap::new(),
+            config: config::Config::default(),
+            pubsub: pubsub::PubSub::default(),
+            clients: clients::Clients::default(),
+            monitors: monitor::Monitors::default(),
+            shutdown: shutdown::ShutdownSignal::default(),
+        }
+    }
+}
+
+// same default as dashmap: four shards per cpu, rounded to a power of two
+fn default_shard_amount() -> usize {
+    let cpus = std::thread::available_parallelism().map_or(1, usize::from);
+    (cpus * 4).next_power_of_two()
+}
+
+// insert the copied value unless the destination exists and must not be replaced
+fn insert_copy(keys: &DashMap<String, Value>, key: &str, value: Value, replace: bool) -> bool {
+    match keys.entry(key.to_string()) {
+        Entry::Occupied(_) if !replace => false,
+        entry => {
+            entry.insert(value);
+            true
+        }
     }
 }
 
@@ -35,28 +154,606 @@ impl Backend {
         Self::default()
     }
 
+    /// Create a backend whose keyspace is pre-allocated for `capacity` keys, avoiding the
+    /// repeated resizing of the maps when bulk loading many keys. The memory for
+    /// `capacity` entries is reserved upfront even if they are never used.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_shard_amount(capacity, default_shard_amount())
+    }
+
+    /// Like `with_capacity`, also setting the number of shards of every keyspace map
+    /// (values, expiries and access tracking). More shards
+    /// reduce lock contention between connections at the cost of a higher fixed memory
+    /// overhead. `shard_amount` must be a power of two greater than one.
+    pub fn with_capacity_and_shard_amount(capacity: usize, shard_amount: usize) -> Self {
+        Self(Arc::new(BackendInner {
+            keys: DashMap::with_capacity_and_shard_amount(capacity, shard_amount),
+            meta: DashMap::with_capacity_and_shard_amount(capacity, shard_amount),
+            expires: DashMap::with_shard_amount(shard_amount),
+            ..Default::default()
+        }))
+    }
+
+    /// Create a backend persisted to an append-only file: existing commands in the file are
+    /// replayed to rebuild the data, and subsequent write commands are appended to it by a
+    /// background task. Must be called within a tokio runtime.
+    pub fn new_with_aof(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new().with_aof(path)
+    }
+
+    /// Like `new_with_aof` for a backend created with other options, e.g. `with_max_keys`.
+    /// Fails if the backend has already been cloned.
+    pub fn with_aof(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let inner = Arc::get_mut(&mut self.0)
+            .ok_or_else(|| anyhow!("aof must be enabled before the backend is shared"))?;
+        if inner.aof.is_some() {
+            return Err(anyhow!("aof is already enabled"));
+        }
+        aof::replay(path, &self)?;
+        let sender = aof::spawn_writer(path)?;
+        Arc::get_mut(&mut self.0)
+            .expect("replaying doesn't share the backend")
+            .aof = Some(sender);
+        Ok(self)
+    }
+
+    /// file used by `SAVE`, defaults to `dump.rdb` in the working directory
+    pub fn snapshot_path(&self) -> PathBuf {
+        self.snapshot_path.read().unwrap().clone()
+    }
+
+    pub fn set_snapshot_path(&self, path: impl Into<PathBuf>) {
+        *self.snapshot_path.write().unwrap() = path.into();
+    }
+
+    pub fn aof_enabled(&self) -> bool {
+        self.aof.is_some()
+    }
+
+    /// Lock to hold while executing a write command and appending it to the aof, so the
+    /// aof has the commands in the order they were executed and replays to the same data.
+    /// `None` when aof is disabled, write commands then run concurrently.
+    pub async fn lock_aof(&self) -> Option<tokio::sync::MutexGuard<'_, ()>> {
+        match self.aof {
+            Some(_) => Some(self.aof_order.lock().await),
+            None => None,
+        }
+    }
+
+    /// queue a write command to be appended to the aof, a no-op when aof is disabled
+    pub fn append_aof(&self, frame: RespFrame) {
+        if let Some(aof) = &self.aof {
+            // the writer task only stops when the runtime shuts down
+            let _ = aof.send(frame);
+        }
+    }
+
+    /// Look the value of the key up once its expiry has been checked, an expired key being
+    /// removed first, viewed as one type by `as_type`, e.g. `Value::as_hash`. `None` if the
+    /// key doesn't exist or holds another type. Every read of a value goes through here so
+    /// none can return stale data.
+    fn get_live<T>(
+        &self,
+        key: &str,
+        as_type: impl FnOnce(&Value) -> Option<&T>,
+    ) -> Option<MappedRef<'_, String, Value, T>> {
+        self.expire_if_needed(key);
+        self.keys.get(key)?.try_map(as_type).ok()
+    }
+
+    /// The value of the key to modify in place, viewed as one type like `get_live`. The
+    /// caller checks the expiry.
+    fn get_mut_as<T>(
+        &self,
+        key: &str,
+        as_type: impl FnOnce(&mut Value) -> Option<&mut T>,
+    ) -> Option<MappedRefMut<'_, String, Value, T>> {
+        self.keys.get_mut(key)?.try_map(as_type).ok()
+    }
+
+    /// The value of the key to write to, created by `new` if the key doesn't exist. `None`
+    /// if the key holds another type: commands check the type first, so that only happens
+    /// when a write of another type won a race, and the key is left as it wrote it.
+    fn get_or_insert_as<T>(
+        &self,
+        key: &str,
+        new: impl FnOnce() -> Value,
+        as_type: impl FnOnce(&mut Value) -> Option<&mut T>,
+    ) -> Option<MappedRefMut<'_, String, Value, T>> {
+        self.keys
+            .entry(key.to_string())
+            .or_insert_with(new)
+            .try_map(as_type)
+            .ok()
+    }
+
     pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+        let ret = self.get_live(key, Value::as_string).map(|v| v.clone());
+        if ret.is_some() {
+            self.touch(key);
+        }
+        ret
     }
 
     pub fn set(&self, key: &str, value: RespFrame) {
-        self.map.insert(key.to_string(), value);
+        self.make_room_for(key);
+        // like redis, SET replaces a value of any type
+        self.keys.insert(key.to_string(), Value::String(value));
+        // like redis, SET discards any previous time to live
+        self.expires.remove(key);
+        self.touch(key);
     }
 
     pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+        let ret = self
+            .get_live(key, Value::as_hash)
+            .and_then(|v| v.get(field).map(|v| v.value().clone()));
+        if ret.is_some() {
+            self.touch(key);
+        }
+        ret
     }
 
     pub fn hset(&self, key: &str, field: &str, value: RespFrame) {
-        self.hmap
-            .entry(key.to_string())
-            .or_default()
-            .insert(field.to_string(), value);
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        if let Some(hash) =
+            self.get_or_insert_as(key, || Value::Hash(DashMap::new()), Value::as_hash_mut)
+        {
+            hash.insert(field.to_string(), value);
+        }
+        self.touch(key);
+    }
+
+    /// Set the field only if the hash doesn't have it yet, creating the hash if needed.
+    /// Returns whether the field was set.
+    pub fn hset_nx(&self, key: &str, field: &str, value: RespFrame) -> bool {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let set = self
+            .get_or_insert_as(key, || Value::Hash(DashMap::new()), Value::as_hash_mut)
+            .is_some_and(|hash| match hash.entry(field.to_string()) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                    true
+                }
+            });
+        if set {
+            self.touch(key);
+        }
+        set
     }
 
     pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.clone())
+        let ret = self.get_live(key, Value::as_hash).map(|v| v.clone());
+        if ret.is_some() {
+            self.touch(key);
+        }
+        ret
+    }
+
+    /// Random fields of the hash with their values, like redis `HRANDFIELD`: `count`
+    /// distinct fields, or if `count` is negative `-count` fields that may repeat. Empty if
+    /// the key doesn't exist. The hash is copied to sample it, so it costs O(N).
+    pub fn hrandfield(&self, key: &str, count: i64) -> Vec<(String, RespFrame)> {
+        let Some(hash) = self.get_live(key, Value::as_hash) else {
+            return vec![];
+        };
+        let fields = hash
+            .iter()
+            .map(|v| (v.key().clone(), v.value().clone()))
+            .collect::<Vec<_>>();
+        drop(hash);
+        self.touch(key);
+        let n = usize::try_from(count.unsigned_abs()).unwrap_or(usize::MAX);
+        if count < 0 {
+            random::sample_with_repeats(&fields, n)
+        } else {
+            random::sample(fields, n)
+        }
+    }
+
+    /// remove the key whatever the type of its value, returns whether it existed
+    pub fn remove(&self, key: &str) -> bool {
+        let removed = self.keys.remove(key).is_some();
+        self.meta.remove(key);
+        self.expires.remove(key);
+        removed
+    }
+
+    /// Remove the keys like `remove`, returns how many existed. From a tokio runtime the
+    /// values are freed by a blocking task, so a large value doesn't stall the caller.
+    pub fn unlink(&self, keys: &[String]) -> usize {
+        let values = keys
+            .iter()
+            .filter_map(|key| {
+                // an expired key doesn't count
+                self.expire_if_needed(key);
+                let value = self.keys.remove(key).map(|(_, value)| value);
+                self.meta.remove(key);
+                self.expires.remove(key);
+                value
+            })
+            .collect::<Vec<_>>();
+        let count = values.len();
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || drop(values));
+            }
+            Err(_) => drop(values),
+        }
+        count
+    }
+
+    /// Remove every key of the database `index`. Like a redis cluster node there is only
+    /// database 0, the others hold no key.
+    pub fn clear_db(&self, index: usize) {
+        if index == 0 {
+            self.clear_all();
+        }
+    }
+
+    /// Remove every key of every database. Every watched key counts as modified.
+    pub fn clear_all(&self) {
+        self.keys.clear();
+        self.meta.clear();
+        self.expires.clear();
+        self.signal_modified(None);
+    }
+
+    /// Copy the value and time to live of `src` to `dst`. Returns false if `src` doesn't
+    /// exist, if `src` and `dst` are the same key, or if `dst` exists and `replace` is false.
+    /// `dst` is checked and written under its entry lock, so two concurrent copies can't
+    /// both claim the same destination. Values of other types at `dst` are only removed
+    /// when `replace` is true.
+    pub fn copy(&self, src: &str, dst: &str, replace: bool) -> bool {
+        if src == dst {
+            return false;
+        }
+        self.expire_if_needed(src);
+        self.expire_if_needed(dst);
+        let ttl = self.expires.get(src).map(|v| *v);
+        let Some(value) = self.keys.get(src).map(|v| v.value().clone()) else {
+            return false;
+        };
+        if !replace && self.key_type(dst).is_some() {
+            return false;
+        }
+        self.make_room_for(dst);
+
+        let copied = insert_copy(&self.keys, dst, value, replace);
+        if copied {
+            match ttl {
+                Some(when) => self.expires.insert(dst.to_string(), when),
+                None => self.expires.remove(dst).map(|(_, when)| when),
+            };
+            self.touch(dst);
+        }
+        copied
+    }
+
+    /// Iterate over all live key/value pairs, hashes being yielded as `RespMap` frames, sets
+    /// as `RespSet` frames, sorted sets as `RespMap` frames of the member scores and lists
+    /// as `RespArray` frames. Expired
+    /// keys not yet removed are skipped. The iterator holds read locks on the
+    /// shards it walks through, so don't write to the backend while holding it.
+    pub fn iter(&self) -> impl Iterator<Item = (String, RespFrame)> + '_ {
+        let now = Instant::now();
+        let live = move |key: &String| self.expires.get(key).is_none_or(|when| *when > now);
+        self.keys.iter().filter(move |v| live(v.key())).map(|v| {
+            let value = match v.value() {
+                Value::String(value) => value.clone(),
+                Value::Hash(hash) => {
+                    let mut map = RespMap::new();
+                    for field in hash.iter() {
+                        map.insert(field.key().clone(), field.value().clone());
+                    }
+                    map.into()
+                }
+                Value::Set(set) => {
+                    let members = set
+                        .iter()
+                        .map(|member| BulkString::from(member.as_str()).into())
+                        .collect::<Vec<RespFrame>>();
+                    RespSet::new(members).into()
+                }
+                Value::ZSet(zset) => {
+                    let mut map = RespMap::new();
+                    for (member, score) in zset.iter() {
+                        map.insert(member.to_string(), score.into());
+                    }
+                    map.into()
+                }
+                Value::List(list) => {
+                    RespArray::new(list.iter().cloned().collect::<Vec<_>>()).into()
+                }
+            };
+            (v.key().clone(), value)
+        })
+    }
+
+    /// type of the value stored at key, as reported by redis `TYPE`
+    pub fn key_type(&self, key: &str) -> Option<&'static str> {
+        self.expire_if_needed(key);
+        self.keys.get(key).map(|v| v.type_name())
+    }
+
+    /// internal encoding redis would use for the value, as reported by `OBJECT ENCODING`.
+    /// Doesn't count as an access of the key.
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        if let Some(value) = self.get_live(key, Value::as_string) {
+            let data: &[u8] = match &*value {
+                RespFrame::BulkString(s) => s.as_ref(),
+                RespFrame::SimpleString(s) => s.as_bytes(),
+                RespFrame::Integer(_) => return Some("int"),
+                _ => return Some("raw"),
+            };
+            let is_int = data.len() <= 20
+                && std::str::from_utf8(data)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .is_some();
+            return Some(match (is_int, data.len()) {
+                (true, _) => "int",
+                (false, n) if n <= 44 => "embstr",
+                _ => "raw",
+            });
+        }
+        if let Some(set) = self.get_live(key, Value::as_set) {
+            let ints = set
+                .iter()
+                .all(|v| v.len() <= 20 && v.parse::<i64>().is_ok());
+            let small = set.iter().all(|v| v.len() <= 64);
+            return Some(match set.len() {
+                n if ints && n <= 512 => "intset",
+                n if small && n <= 128 => "listpack",
+                _ => "hashtable",
+            });
+        }
+        if let Some(list) = self.get_live(key, Value::as_list) {
+            let small = list.len() <= 128
+                && list.iter().all(|v| match v {
+                    RespFrame::BulkString(s) => s.len() <= 64,
+                    _ => true,
+                });
+            return Some(if small { "listpack" } else { "quicklist" });
+        }
+        if let Some(zset) = self.get_live(key, Value::as_zset) {
+            let small = zset.len() <= 128 && zset.iter().all(|(member, _)| member.len() <= 64);
+            return Some(if small { "listpack" } else { "skiplist" });
+        }
+        self.get_live(key, Value::as_hash).map(|hash| {
+            let small = hash.len() <= 128
+                && hash.iter().all(|v| {
+                    v.key().len() <= 64
+                        && match v.value() {
+                            RespFrame::BulkString(s) => s.len() <= 64,
+                            _ => true,
+                        }
+                });
+            if small {
+                "listpack"
+            } else {
+                "hashtable"
+            }
+        })
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.stats.started_at.elapsed()
+    }
+
+    /// record one executed command, both in the total and the per-command counter
+    pub fn record_command(&self, name: &'static str) {
+        self.stats.total_commands.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .command_calls
+            .entry(name)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// per-command call counts, sorted by command name
+    pub fn command_stats(&self) -> Vec<(&'static str, u64)> {
+        let mut ret: Vec<_> = self
+            .stats
+            .command_calls
+            .iter()
+            .map(|v| (*v.key(), v.value().load(Ordering::Relaxed)))
+            .collect();
+        ret.sort_by(|a, b| a.0.cmp(b.0));
+        ret
+    }
+
+    pub fn commands_processed(&self) -> u64 {
+        self.stats.total_commands.load(Ordering::Relaxed)
+    }
+
+    pub fn client_connected(&self) {
+        self.stats.connected_clients.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn client_disconnected(&self) {
+        self.stats.connected_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// allocate a unique id for a new client connection
+    pub fn next_client_id(&self) -> u64 {
+        self.stats.next_client_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn connected_clients(&self) -> u64 {
+        self.stats.connected_clients.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_with_capacity() {
+        let backend = Backend::with_capacity(10_000);
+        assert!(backend.keys.capacity() >= 10_000);
+
+        let backend = Backend::with_capacity_and_shard_amount(100, 8);
+        assert!(backend.keys.capacity() >= 100);
+        backend.set("hello", BulkString::new("world").into());
+        assert_eq!(backend.get("hello"), Some(BulkString::new("world").into()));
+    }
+
+    #[test]
+    fn test_expired_key_is_absent_from_every_read() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        backend.hset("user", "name", BulkString::new("alice").into());
+        backend.expire("hello", Duration::ZERO);
+        backend.expire("user", Duration::ZERO);
+
+        assert_eq!(backend.get("hello"), None);
+        assert_eq!(backend.hget("user", "name"), None);
+        assert!(backend.hgetall("user").is_none());
+        for key in ["hello", "user"] {
+            assert_eq!(backend.key_type(key), None);
+            assert_eq!(backend.object_encoding(key), None);
+            assert_eq!(backend.idle_time(key), None);
+        }
+        assert_eq!(backend.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_set_replaces_value_of_any_type() {
+        let backend = Backend::new();
+        backend.hset("hash", "field", BulkString::new("value").into());
+        backend.sadd("set", ["member".into()]);
+        backend.zadd("zset", [(1.0, "member".into())]);
+        for key in ["hash", "set", "zset"] {
+            backend.set(key, BulkString::new("string").into());
+            assert_eq!(backend.key_type(key), Some("string"));
+        }
+        assert_eq!(backend.key_count(), 3);
+        assert_eq!(backend.hget("hash", "field"), None);
+        assert_eq!(backend.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_key_holds_one_type() {
+        let backend = Backend::new();
+        backend.set("key", BulkString::new("string").into());
+        // writes of another type leave the value alone, commands reply WRONGTYPE first
+        backend.hset("key", "field", BulkString::new("value").into());
+        assert_eq!(backend.sadd("key", ["member".into()]), 0);
+        assert_eq!(backend.zadd("key", [(1.0, "member".into())]), 0);
+        assert_eq!(backend.push("key", ListEnd::Left, [1.into()]), 0);
+        assert_eq!(backend.key_type("key"), Some("string"));
+        assert_eq!(backend.hget("key", "field"), None);
+        assert!(!backend.sismember("key", "member"));
+        assert_eq!(backend.list_elements("key"), None);
+        assert_eq!(backend.key_count(), 1);
+
+        backend.hset("hash", "field", BulkString::new("value").into());
+        assert_eq!(backend.get("hash"), None);
+        assert!(!backend.setbit("hash", 0, true));
+        assert_eq!(backend.key_type("hash"), Some("hash"));
+        assert!(backend.remove("hash"));
+        assert_eq!(backend.key_type("hash"), None);
+    }
+
+    #[tokio::test]
+    async fn test_unlink() {
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        backend.hset("h", "field", BulkString::new("v").into());
+        backend.set("expired", BulkString::new("1").into());
+        backend.expire("expired", Duration::ZERO);
+
+        let keys = ["a", "h", "a", "missing", "expired"].map(String::from);
+        assert_eq!(backend.unlink(&keys), 2);
+        assert_eq!(backend.key_count(), 0);
+        assert_eq!(backend.unlink(&keys), 0);
+    }
+
+    #[test]
+    fn test_clear_db() {
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        backend.sadd("s", ["member".into()]);
+        backend.expire("a", Duration::from_secs(100));
+
+        // database 1 holds no key, database 0 is left intact
+        backend.clear_db(1);
+        assert_eq!(backend.key_count(), 2);
+        assert!(backend.get("a").is_some());
+
+        backend.clear_db(0);
+        assert_eq!(backend.key_count(), 0);
+        assert_eq!(backend.ttl("a"), None);
+
+        backend.set("a", BulkString::new("1").into());
+        backend.clear_all();
+        assert_eq!(backend.key_count(), 0);
+    }
+
+    #[test]
+    fn test_hrandfield() {
+        let backend = Backend::new();
+        for field in ["a", "b", "c"] {
+            backend.hset("h", field, BulkString::new(field).into());
+        }
+        let mut fields = backend.hrandfield("h", 5);
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        let expected = ["a", "b", "c"].map(|f| (f.to_string(), BulkString::new(f).into()));
+        assert_eq!(fields, expected);
+
+        let fields = backend.hrandfield("h", -10);
+        assert_eq!(fields.len(), 10);
+        assert!(fields.iter().all(|field| expected.contains(field)));
+        assert!(backend.hrandfield("h", 0).is_empty());
+        assert!(backend.hrandfield("missing", -3).is_empty());
+    }
+
+    #[test]
+    fn test_copy_to_itself() {
+        let backend = Backend::new();
+        backend.set("key", BulkString::new("value").into());
+        assert!(!backend.copy("key", "key", true));
+        assert_eq!(backend.get("key"), Some(BulkString::new("value").into()));
+    }
+
+    #[test]
+    fn test_copy_keeps_destination_of_other_type() {
+        let backend = Backend::new();
+        backend.set("src", BulkString::new("value").into());
+        backend.sadd("dst", ["member".into()]);
+        assert!(!backend.copy("src", "dst", false));
+        assert!(backend.sismember("dst", "member"));
+    }
+
+    #[test]
+    fn test_iter() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        backend.set("expired", BulkString::new("value").into());
+        backend.expire_at("expired", Instant::now());
+        backend.hset("user", "name", BulkString::new("alice").into());
+
+        let mut data: Vec<_> = backend.iter().collect();
+        data.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut user = RespMap::new();
+        user.insert("name".to_string(), BulkString::new("alice").into());
+        assert_eq!(
+            data,
+            vec![
+                ("hello".to_string(), BulkString::new("world").into()),
+                ("user".to_string(), user.into()),
+            ]
+        );
     }
 }
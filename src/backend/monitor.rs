@@ -0,0 +1,164 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::sync::broadcast;
+
+use crate::RespFrame;
+
+use super::Backend;
+
+// lines queued for each monitoring connection, one lagging behind loses the oldest ones
+const MONITOR_CAPACITY: usize = 4096;
+
+/// Stream of the commands executed by every client, sent to the connections in `MONITOR`.
+#[derive(Debug)]
+pub(super) struct Monitors(broadcast::Sender<String>);
+
+impl Default for Monitors {
+    fn default() -> Self {
+        Monitors(broadcast::channel(MONITOR_CAPACITY).0)
+    }
+}
+
+impl Backend {
+    /// receive a line for each command executed from now on, see `feed_monitors`
+    pub fn monitor(&self) -> broadcast::Receiver<String> {
+        self.monitors.0.subscribe()
+    }
+
+    /// whether a connection is in `MONITOR`
+    pub fn has_monitors(&self) -> bool {
+        self.monitors.0.receiver_count() > 0
+    }
+
+    /// Send the request of the client `client_id` to the monitoring connections, formatted
+    /// like redis: `1339518083.107412 [0 127.0.0.1:60866] "set" "key" "value"`. Passwords
+    /// are redacted.
+    pub fn feed_monitors(&self, client_id: u64, request: &RespFrame) {
+        if !self.has_monitors() {
+            return;
+        }
+        let (db, addr) = self
+            .client(client_id)
+            .map(|client| (client.db, client.addr))
+            .unwrap_or_default();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut line = format!(
+            "{}.{:06} [{} {}]",
+            now.as_secs(),
+            now.subsec_micros(),
+            db,
+            addr
+        );
+        for arg in redacted_args(request) {
+            line.push(' ');
+            line.push_str(&quote(arg));
+        }
+        // every monitoring connection may have gone meanwhile
+        let _ = self.monitors.0.send(line);
+    }
+}
+
+// the arguments of the request, with the passwords of `AUTH` and `HELLO ... AUTH` hidden
+fn redacted_args(request: &RespFrame) -> Vec<&[u8]> {
+    let RespFrame::Array(args) = request else {
+        return vec![];
+    };
+    let mut args = args
+        .iter()
+        .map(|arg| arg.as_bytes().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let redacted = match args.first() {
+        Some(name) if name.eq_ignore_ascii_case(b"auth") => 1..args.len(),
+        Some(name) if name.eq_ignore_ascii_case(b"hello") => {
+            match args
+                .iter()
+                .position(|arg| arg.eq_ignore_ascii_case(b"auth"))
+            {
+                // the username and the password
+                Some(i) => i + 1..(i + 3).min(args.len()),
+                None => 0..0,
+            }
+        }
+        _ => 0..0,
+    };
+    for arg in &mut args[redacted] {
+        *arg = b"(redacted)";
+    }
+    args
+}
+
+// the argument quoted like redis, escaping quotes and non printable bytes
+fn quote(arg: &[u8]) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for &b in arg {
+        match b {
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b' '..=b'~' => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray};
+
+    fn request(args: &[&str]) -> RespFrame {
+        let args = args.iter().map(|arg| BulkString::from(*arg).into());
+        RespArray::new(args.collect::<Vec<_>>()).into()
+    }
+
+    #[test]
+    fn test_feed_monitors() {
+        let backend = Backend::new();
+        // nobody monitors, nothing is formatted
+        backend.feed_monitors(1, &request(&["get", "a"]));
+        assert!(!backend.has_monitors());
+
+        let client = backend.register_client("127.0.0.1:6000");
+        let mut monitor = backend.monitor();
+        backend.feed_monitors(client.id(), &request(&["set", "k", "a \"b\"\n\x01"]));
+        let line = monitor.try_recv().unwrap();
+        let (timestamp, command) = line.split_once(' ').unwrap();
+        assert!(timestamp.parse::<f64>().is_ok());
+        assert_eq!(command, r#"[0 127.0.0.1:6000] "set" "k" "a \"b\"\n\x01""#);
+    }
+
+    fn redacted(args: &[&str]) -> Vec<String> {
+        redacted_args(&request(args))
+            .into_iter()
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_passwords_are_redacted() {
+        assert_eq!(
+            redacted(&["AUTH", "user", "secret"]),
+            ["AUTH", "(redacted)", "(redacted)"]
+        );
+        assert_eq!(
+            redacted(&["hello", "3", "auth", "user", "secret", "setname", "c"]),
+            [
+                "hello",
+                "3",
+                "auth",
+                "(redacted)",
+                "(redacted)",
+                "setname",
+                "c"
+            ]
+        );
+        assert_eq!(redacted(&["set", "auth", "x"]), ["set", "auth", "x"]);
+    }
+}
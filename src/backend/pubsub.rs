@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use dashmap::DashMap;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+
+use crate::{cmd::glob_match, BulkString, RespArray, RespFrame};
+
+use super::{config::OverflowPolicy, Backend};
+
+/// Sending half of the bounded queue of pub/sub messages delivered to a connection.
+#[derive(Debug, Clone)]
+pub struct MessageSender {
+    tx: mpsc::Sender<RespFrame>,
+    // set when the subscriber is dropped for lagging behind, shared by the clones
+    lagging: Arc<AtomicBool>,
+}
+
+impl MessageSender {
+    /// whether the connection must be closed because its queue overflowed
+    pub fn is_lagging(&self) -> bool {
+        self.lagging.load(Ordering::Relaxed)
+    }
+}
+
+// subscribed connections by client id
+type Subscribers = HashMap<u64, MessageSender>;
+
+/// Channel and pattern subscriptions of all the connections.
+#[derive(Debug, Default)]
+pub(super) struct PubSub {
+    channels: DashMap<String, Subscribers>,
+    patterns: DashMap<String, Subscribers>,
+}
+
+impl Backend {
+    /// Queue for the messages of a subscribing connection, holding at most
+    /// `pubsub-buffer-limit` messages.
+    pub fn message_channel(&self) -> (MessageSender, mpsc::Receiver<RespFrame>) {
+        let (tx, rx) = mpsc::channel(self.pubsub_buffer_limit());
+        let sender = MessageSender {
+            tx,
+            lagging: Arc::new(AtomicBool::new(false)),
+        };
+        (sender, rx)
+    }
+
+    /// deliver messages published to `channel` to the connection `client_id`
+    pub fn subscribe(&self, channel: &str, client_id: u64, sender: MessageSender) {
+        add_subscriber(&self.pubsub.channels, channel, client_id, sender);
+    }
+
+    pub fn unsubscribe(&self, channel: &str, client_id: u64) {
+        remove_subscriber(&self.pubsub.channels, channel, client_id);
+    }
+
+    /// deliver messages published to channels matching the glob `pattern` to the connection
+    pub fn psubscribe(&self, pattern: &str, client_id: u64, sender: MessageSender) {
+        add_subscriber(&self.pubsub.patterns, pattern, client_id, sender);
+    }
+
+    pub fn punsubscribe(&self, pattern: &str, client_id: u64) {
+        remove_subscriber(&self.pubsub.patterns, pattern, client_id);
+    }
+
+    /// Send `["message", channel, message]` to the subscribers of the channel and
+    /// `["pmessage", pattern, channel, message]` to the subscribers of each matching
+    /// pattern. Returns the number of deliveries, a connection subscribed both to the
+    /// channel and a pattern receives the message twice.
+    pub fn publish(&self, channel: &str, message: RespFrame) -> usize {
+        let mut delivered = 0;
+        if let Some(mut subscribers) = self.pubsub.channels.get_mut(channel) {
+            let frame: RespFrame = RespArray::new([
+                BulkString::from("message").into(),
+                BulkString::from(channel).into(),
+                message.clone(),
+            ])
+            .into();
+            delivered += deliver(&mut subscribers, frame, self.pubsub_overflow_policy());
+        }
+        for mut entry in self.pubsub.patterns.iter_mut() {
+            if !glob_match(entry.key().as_bytes(), channel.as_bytes()) {
+                continue;
+            }
+            let frame: RespFrame = RespArray::new([
+                BulkString::from("pmessage").into(),
+                BulkString::from(entry.key().as_str()).into(),
+                BulkString::from(channel).into(),
+                message.clone(),
+            ])
+            .into();
+            delivered += deliver(entry.value_mut(), frame, self.pubsub_overflow_policy());
+        }
+        delivered
+    }
+}
+
+fn add_subscriber(
+    map: &DashMap<String, Subscribers>,
+    name: &str,
+    client_id: u64,
+    sender: MessageSender,
+) {
+    map.entry(name.to_string())
+        .or_default()
+        .insert(client_id, sender);
+}
+
+fn remove_subscriber(map: &DashMap<String, Subscribers>, name: &str, client_id: u64) {
+    map.remove_if_mut(name, |_, subscribers| {
+        subscribers.remove(&client_id);
+        subscribers.is_empty()
+    });
+}
+
+// Send the frame to every subscriber, returns the number of connections it was queued for.
+// Publishing never waits for a subscriber: when the queue of a subscriber is full, the
+// new message is dropped for it, keeping the queued ones, or the subscriber is removed and
+// its connection closed.
+fn deliver(subscribers: &mut Subscribers, frame: RespFrame, policy: OverflowPolicy) -> usize {
+    let mut delivered = 0;
+    subscribers.retain(|id, sender| match sender.tx.try_send(frame.clone()) {
+        Ok(()) => {
+            delivered += 1;
+            true
+        }
+        // connections that went away without unsubscribing are dropped
+        Err(TrySendError::Closed(_)) => false,
+        Err(TrySendError::Full(_)) => match policy {
+            OverflowPolicy::Drop => {
+                warn!("dropped a message for lagging subscriber {}", id);
+                true
+            }
+            OverflowPolicy::Disconnect => {
+                warn!("disconnecting lagging subscriber {}", id);
+                sender.lagging.store(true, Ordering::Relaxed);
+                false
+            }
+        },
+    });
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(channel: &str, payload: &str) -> RespFrame {
+        RespArray::new([
+            BulkString::from("message").into(),
+            BulkString::from(channel).into(),
+            BulkString::from(payload).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_publish_reaches_subscribers() {
+        let backend = Backend::new();
+        let (tx, mut rx) = backend.message_channel();
+        backend.subscribe("news", 1, tx);
+
+        assert_eq!(backend.publish("news", BulkString::from("hi").into()), 1);
+        assert_eq!(backend.publish("other", BulkString::from("hi").into()), 0);
+        assert_eq!(rx.try_recv().unwrap(), message("news", "hi"));
+
+        backend.unsubscribe("news", 1);
+        assert_eq!(backend.publish("news", BulkString::from("hi").into()), 0);
+        assert!(backend.pubsub.channels.is_empty());
+    }
+
+    #[test]
+    fn test_publish_reaches_pattern_and_channel_subscribers() {
+        let backend = Backend::new();
+        let (tx, mut rx) = backend.message_channel();
+        backend.subscribe("news.tech", 1, tx.clone());
+        backend.psubscribe("news.*", 1, tx);
+
+        assert_eq!(
+            backend.publish("news.tech", BulkString::from("hi").into()),
+            2
+        );
+        assert_eq!(
+            backend.publish("news.art", BulkString::from("hi").into()),
+            1
+        );
+        assert_eq!(backend.publish("weather", BulkString::from("hi").into()), 0);
+
+        let frames: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert_eq!(frames.len(), 3);
+        let expected: RespFrame = RespArray::new([
+            BulkString::from("pmessage").into(),
+            BulkString::from("news.*").into(),
+            BulkString::from("news.tech").into(),
+            BulkString::from("hi").into(),
+        ])
+        .into();
+        assert_eq!(frames[1], expected);
+
+        backend.punsubscribe("news.*", 1);
+        assert_eq!(
+            backend.publish("news.art", BulkString::from("hi").into()),
+            0
+        );
+        assert!(backend.pubsub.patterns.is_empty());
+    }
+
+    #[test]
+    fn test_publish_drops_closed_subscribers() {
+        let backend = Backend::new();
+        let (tx, rx) = backend.message_channel();
+        backend.subscribe("news", 1, tx);
+        drop(rx);
+        assert_eq!(backend.publish("news", BulkString::from("hi").into()), 0);
+    }
+
+    #[test]
+    fn test_lagging_subscriber_is_disconnected() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        backend.config_set("pubsub-buffer-limit", "2")?;
+        let (slow, _slow_rx) = backend.message_channel();
+        let (fast, mut fast_rx) = backend.message_channel();
+        backend.subscribe("news", 1, slow.clone());
+        backend.subscribe("news", 2, fast);
+
+        for expected in [2, 2, 1] {
+            assert_eq!(
+                backend.publish("news", BulkString::from("hi").into()),
+                expected
+            );
+            fast_rx.try_recv()?;
+        }
+        assert!(slow.is_lagging());
+        assert_eq!(backend.pubsub.channels.get("news").unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow_drops_messages() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        backend.config_set("pubsub-buffer-limit", "1")?;
+        backend.config_set("pubsub-overflow-policy", "drop")?;
+        let (tx, mut rx) = backend.message_channel();
+        backend.subscribe("news", 1, tx.clone());
+
+        assert_eq!(backend.publish("news", BulkString::from("a").into()), 1);
+        assert_eq!(backend.publish("news", BulkString::from("b").into()), 0);
+        assert!(!tx.is_lagging());
+        // the newest message is the one dropped
+        assert_eq!(rx.try_recv()?, message("news", "a"));
+        assert!(rx.try_recv().is_err());
+        // once there is room again messages are delivered
+        assert_eq!(backend.publish("news", BulkString::from("c").into()), 1);
+        assert_eq!(rx.try_recv()?, message("news", "c"));
+        Ok(())
+    }
+}
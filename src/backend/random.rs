@@ -0,0 +1,65 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A random index below `len`, which must not be 0. Each `RandomState` is seeded with new
+/// keys, so hashing nothing with it gives a fresh value: good enough to sample elements,
+/// not for anything secret.
+pub(super) fn random_index(len: usize) -> usize {
+    let value = RandomState::new().build_hasher().finish();
+    (value % len as u64) as usize
+}
+
+/// `count` distinct elements of `elements` in random order, all of them if there are fewer.
+pub(super) fn sample<T>(mut elements: Vec<T>, count: usize) -> Vec<T> {
+    let count = count.min(elements.len());
+    // a partial Fisher-Yates shuffle of the first `count` positions
+    for i in 0..count {
+        let j = i + random_index(elements.len() - i);
+        elements.swap(i, j);
+    }
+    elements.truncate(count);
+    elements
+}
+
+/// `count` elements each picked at random independently, so an element may repeat. Empty if
+/// `elements` is.
+pub(super) fn sample_with_repeats<T: Clone>(elements: &[T], count: usize) -> Vec<T> {
+    if elements.is_empty() {
+        return vec![];
+    }
+    (0..count)
+        .map(|_| elements[random_index(elements.len())].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let mut sampled = sample((0..10).collect(), 4);
+        assert_eq!(sampled.len(), 4);
+        sampled.sort();
+        sampled.dedup();
+        assert_eq!(sampled.len(), 4);
+        assert!(sampled.iter().all(|i| *i < 10));
+
+        let mut sampled = sample(vec!["a", "b"], 5);
+        sampled.sort();
+        assert_eq!(sampled, ["a", "b"]);
+
+        let repeated = sample_with_repeats(&["a"], 3);
+        assert_eq!(repeated, ["a", "a", "a"]);
+        assert!(sample_with_repeats::<u8>(&[], 3).is_empty());
+
+        // every index comes up eventually
+        let mut seen = [false; 4];
+        for _ in 0..1000 {
+            seen[random_index(4)] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
+}
@@ -0,0 +1,179 @@
+use dashmap::DashSet;
+
+use super::{random, Backend, Value};
+
+impl Backend {
+    /// Add the members to the set, creating it if the key doesn't exist, and return the
+    /// number of members that weren't already in the set.
+    pub fn sadd(&self, key: &str, members: impl IntoIterator<Item = String>) -> usize {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let added =
+            match self.get_or_insert_as(key, || Value::Set(DashSet::new()), Value::as_set_mut) {
+                Some(set) => members
+                    .into_iter()
+                    .filter(|m| set.insert(m.clone()))
+                    .count(),
+                None => 0,
+            };
+        self.touch(key);
+        added
+    }
+
+    /// Remove the members from the set and return the number of members removed, the key is
+    /// removed with its last member.
+    pub fn srem<'a>(&self, key: &str, members: impl IntoIterator<Item = &'a str>) -> usize {
+        self.expire_if_needed(key);
+        let removed = {
+            let Some(set) = self.get_mut_as(key, Value::as_set_mut) else {
+                return 0;
+            };
+            members
+                .into_iter()
+                .filter(|m| set.remove(*m).is_some())
+                .count()
+        };
+        self.remove_if_empty_set(key);
+        removed
+    }
+
+    /// Random members of the set, like redis `SRANDMEMBER`: `count` distinct members, or if
+    /// `count` is negative `-count` members that may repeat. Empty if the key doesn't exist.
+    pub fn srandmember(&self, key: &str, count: i64) -> Vec<String> {
+        let Some(members) = self.smembers(key) else {
+            return vec![];
+        };
+        let n = usize::try_from(count.unsigned_abs()).unwrap_or(usize::MAX);
+        if count < 0 {
+            random::sample_with_repeats(&members, n)
+        } else {
+            random::sample(members, n)
+        }
+    }
+
+    /// Remove `count` random members from the set and return them, the key is removed with
+    /// its last member. The members are picked and removed under the key's entry lock.
+    pub fn spop(&self, key: &str, count: usize) -> Vec<String> {
+        self.expire_if_needed(key);
+        let popped = {
+            let Some(set) = self.get_mut_as(key, Value::as_set_mut) else {
+                return vec![];
+            };
+            let members = set.iter().map(|m| m.clone()).collect();
+            let popped = random::sample(members, count);
+            for member in &popped {
+                set.remove(member);
+            }
+            popped
+        };
+        self.remove_if_empty_set(key);
+        popped
+    }
+
+    // remove the key once its set has no member left, otherwise it's an access
+    fn remove_if_empty_set(&self, key: &str) {
+        if self
+            .keys
+            .remove_if(key, |_, v| v.as_set().is_some_and(|set| set.is_empty()))
+            .is_some()
+        {
+            self.remove(key);
+        } else {
+            self.touch(key);
+        }
+    }
+
+    pub fn sismember(&self, key: &str, member: &str) -> bool {
+        self.smismember(key, [member])[0]
+    }
+
+    /// membership of each of the members, in order, looking the set up only once
+    pub fn smismember<'a>(
+        &self,
+        key: &str,
+        members: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<bool> {
+        let set = self.get_live(key, Value::as_set);
+        if set.is_some() {
+            self.touch(key);
+        }
+        members
+            .into_iter()
+            .map(|member| set.as_ref().is_some_and(|set| set.contains(member)))
+            .collect()
+    }
+
+    /// members of the set, in no particular order. `None` if the key doesn't exist.
+    pub fn smembers(&self, key: &str) -> Option<Vec<String>> {
+        let members = self
+            .get_live(key, Value::as_set)
+            .map(|set| set.iter().map(|m| m.clone()).collect());
+        if members.is_some() {
+            self.touch(key);
+        }
+        members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sadd_and_membership() {
+        let backend = Backend::new();
+        assert_eq!(backend.sadd("s", ["a".into(), "b".into(), "a".into()]), 2);
+        assert_eq!(backend.sadd("s", ["b".into(), "c".into()]), 1);
+        assert_eq!(backend.key_type("s"), Some("set"));
+
+        assert!(backend.sismember("s", "c"));
+        assert!(!backend.sismember("s", "d"));
+        assert_eq!(
+            backend.smismember("s", ["a", "d", "b"]),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            backend.smismember("missing", ["a", "b"]),
+            vec![false, false]
+        );
+
+        let mut members = backend.smembers("s").unwrap();
+        members.sort();
+        assert_eq!(members, ["a", "b", "c"]);
+        assert_eq!(backend.smembers("missing"), None);
+    }
+
+    #[test]
+    fn test_srandmember_and_spop() {
+        let backend = Backend::new();
+        backend.sadd("s", ["a".into(), "b".into(), "c".into()]);
+        let mut members = backend.srandmember("s", 10);
+        members.sort();
+        assert_eq!(members, ["a", "b", "c"]);
+        assert_eq!(backend.srandmember("s", -5).len(), 5);
+        assert!(backend.srandmember("missing", 1).is_empty());
+
+        // popped members are gone, the key goes with the last one
+        let popped = backend.spop("s", 2);
+        assert_eq!(popped.len(), 2);
+        assert_eq!(
+            backend.smismember("s", popped.iter().map(String::as_str)),
+            [false, false]
+        );
+        assert_eq!(backend.smembers("s").unwrap().len(), 1);
+        assert_eq!(backend.spop("s", 5).len(), 1);
+        assert_eq!(backend.key_type("s"), None);
+        assert!(backend.spop("s", 1).is_empty());
+    }
+
+    #[test]
+    fn test_srem() {
+        let backend = Backend::new();
+        backend.sadd("s", ["a".into(), "b".into()]);
+        assert_eq!(backend.srem("s", ["a", "x", "a"]), 1);
+        assert_eq!(backend.smembers("s"), Some(vec!["b".to_string()]));
+        assert_eq!(backend.srem("s", ["b"]), 1);
+        assert_eq!(backend.key_type("s"), None);
+        assert_eq!(backend.srem("missing", ["b"]), 0);
+    }
+}
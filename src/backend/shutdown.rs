@@ -0,0 +1,49 @@
+use tokio::sync::watch;
+
+use super::Backend;
+
+/// Raised by `SHUTDOWN`, the server waits on it like on a termination signal.
+#[derive(Debug)]
+pub(super) struct ShutdownSignal(watch::Sender<bool>);
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        ShutdownSignal(watch::channel(false).0)
+    }
+}
+
+impl Backend {
+    /// ask the server to shut down, see `shutdown_requested`
+    pub fn request_shutdown(&self) {
+        self.shutdown.0.send_replace(true);
+    }
+
+    /// resolves once a shutdown is requested, right away if it already was
+    pub async fn shutdown_requested(&self) {
+        let mut requested = self.shutdown.0.subscribe();
+        // the sender lives as long as the backend, the wait can't fail
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_requested() {
+        let backend = Backend::new();
+        let waiter = tokio::spawn({
+            let backend = backend.clone();
+            async move { backend.shutdown_requested().await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        backend.request_shutdown();
+        waiter.await.unwrap();
+        // later waiters see the request too
+        backend.shutdown_requested().await;
+    }
+}
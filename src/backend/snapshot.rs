@@ -0,0 +1,370 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BytesMut};
+use dashmap::{DashMap, DashSet};
+
+use crate::{BulkString, RespArray, RespDecode, RespEncode, RespFrame, RespMap, RespSet};
+
+use super::{value::Value, zset::SortedSet, Backend};
+
+// snapshot layout: MAGIC followed by entries, each `<kind><key><expiry><frame>`, where the
+// key is encoded as a bulk string so it may contain any byte, expiry is an integer frame of
+// the unix time in milliseconds the key expires at (-1 if it doesn't), and kind is
+// KIND_STRING (frame is the stored value), KIND_HASH (frame is a flat field/value array),
+// KIND_SET (frame is an array of the members), KIND_ZSET (frame is a flat member/score
+// array, scores being doubles) or KIND_LIST (frame is the array of the elements in order).
+const MAGIC: &[u8] = b"SREDIS03\r\n";
+const KIND_STRING: u8 = b's';
+pub(super) const KIND_HASH: u8 = b'h';
+const KIND_SET: u8 = b'S';
+const KIND_ZSET: u8 = b'z';
+const KIND_LIST: u8 = b'l';
+
+impl Backend {
+    /// Copy every live key with its value: strings as stored, hashes as maps, sets as sets,
+    /// lists as arrays and sorted sets as arrays of `[member, score]` pairs by score.
+    ///
+    /// The keyspace is copied shard by shard without a global lock, each value whole, so a
+    /// write made meanwhile may or may not be seen. Hold `lock_writes_exclusive` around the
+    /// call for a point-in-time copy. The copy takes O(N) memory for the N keys and values.
+    pub fn snapshot(&self) -> Vec<(String, RespFrame)> {
+        let now = (Instant::now(), unix_now());
+        let live = |key: &str| self.expiry_millis(key, now).is_some();
+        let mut entries = Vec::new();
+        for entry in self.keys.iter().filter(|entry| live(entry.key())) {
+            let value = match entry.value() {
+                Value::String(value) => value.clone(),
+                Value::Hash(hash) => {
+                    let mut map = RespMap::new();
+                    for field in hash.iter() {
+                        map.insert(field.key().clone(), field.value().clone());
+                    }
+                    map.into()
+                }
+                Value::Set(set) => {
+                    let members = set
+                        .iter()
+                        .map(|member| BulkString::from(member.as_str()).into())
+                        .collect::<Vec<RespFrame>>();
+                    RespSet::new(members).into()
+                }
+                Value::ZSet(zset) => {
+                    let members = zset
+                        .iter()
+                        .map(|(member, score)| {
+                            RespArray::new([BulkString::from(member).into(), score.into()]).into()
+                        })
+                        .collect::<Vec<RespFrame>>();
+                    RespArray::new(members).into()
+                }
+                Value::List(list) => list_frame(list),
+            };
+            entries.push((entry.key().clone(), value));
+        }
+        entries
+    }
+
+    /// Serialize the whole keyspace to `path`. The data is written to a temporary file
+    /// first and renamed, so a crash never leaves a half written snapshot behind.
+    pub fn snapshot_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(fs::File::create(&tmp)?);
+        writer.write_all(MAGIC)?;
+        let now = (Instant::now(), unix_now());
+        // keys expired but not yet removed are skipped
+        for entry in self.keys.iter() {
+            if let Some(expiry) = self.expiry_millis(entry.key(), now) {
+                let (kind, value) = encode_entry(entry.value());
+                write_entry(&mut writer, kind, entry.key(), expiry, value)?;
+            }
+        }
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Load a snapshot written by `snapshot_to`, returning the number of loaded keys. Keys
+    /// that expired since the snapshot was taken are skipped. Existing keys with the same
+    /// name are overwritten.
+    pub fn load_from(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let data = fs::read(path)?;
+        let mut buf = BytesMut::from(&data[..]);
+        if !buf.starts_with(MAGIC) {
+            return Err(anyhow!("snapshot: invalid header"));
+        }
+        buf.advance(MAGIC.len());
+
+        let now = unix_now();
+        let mut count = 0;
+        while !buf.is_empty() {
+            let kind = buf.get_u8();
+            let key = String::from_utf8(BulkString::decode(&mut buf)?.to_vec())?;
+            let expiry = i64::decode(&mut buf)?;
+            let value = RespFrame::decode(&mut buf)?;
+            let ttl = match expiry {
+                -1 => None,
+                expiry if expiry as u128 > now.as_millis() => {
+                    Some(Duration::from_millis(expiry as u64) - now)
+                }
+                _ => continue,
+            };
+            self.insert_entry(&key, decode_entry(kind, value)?);
+            match ttl {
+                Some(ttl) => self.expire(&key, ttl),
+                None => self.persist(&key),
+            };
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // unix time in milliseconds the key expires at, -1 if it doesn't expire and `None` if it
+    // has already expired, `now` being the current instant and unix time
+    fn expiry_millis(&self, key: &str, now: (Instant, Duration)) -> Option<i64> {
+        match self.expires.get(key).map(|when| *when) {
+            None => Some(-1),
+            Some(when) if when <= now.0 => None,
+            Some(when) => Some((now.1 + (when - now.0)).as_millis() as i64),
+        }
+    }
+
+    /// store a value decoded by `decode_entry`, replacing any value of the key
+    pub(super) fn insert_entry(&self, key: &str, value: Value) {
+        self.make_room_for(key);
+        self.remove(key);
+        self.keys.insert(key.to_string(), value);
+        self.touch(key);
+    }
+}
+
+/// The kind of the value and the frame it is serialized as, see `decode_entry` for the
+/// reverse.
+pub(super) fn encode_entry(value: &Value) -> (u8, RespFrame) {
+    match value {
+        Value::String(value) => (KIND_STRING, value.clone()),
+        Value::Hash(hash) => (KIND_HASH, hash_frame(hash)),
+        Value::Set(set) => (KIND_SET, set_frame(set)),
+        Value::ZSet(zset) => (KIND_ZSET, zset_frame(zset)),
+        Value::List(list) => (KIND_LIST, list_frame(list)),
+    }
+}
+
+/// Decode a value from its serialized `<kind><frame>` form, decoding is done before
+/// anything is stored so a malformed entry never leaves a key half written.
+pub(super) fn decode_entry(kind: u8, value: RespFrame) -> Result<Value> {
+    let value = match (kind, value) {
+        (KIND_STRING, value) => Value::String(value),
+        (KIND_HASH, RespFrame::Array(fields)) => {
+            let hash = DashMap::new();
+            let mut iter = fields.into_iter();
+            while let (Some(RespFrame::BulkString(field)), Some(value)) = (iter.next(), iter.next())
+            {
+                hash.insert(String::from_utf8(field.to_vec())?, value);
+            }
+            Value::Hash(hash)
+        }
+        (KIND_SET, RespFrame::Array(members)) => {
+            let set = DashSet::new();
+            for member in members.into_iter() {
+                if let RespFrame::BulkString(member) = member {
+                    set.insert(String::from_utf8(member.to_vec())?);
+                }
+            }
+            Value::Set(set)
+        }
+        (KIND_ZSET, RespFrame::Array(members)) => {
+            let mut zset = SortedSet::default();
+            let mut iter = members.into_iter();
+            while let (Some(RespFrame::BulkString(member)), Some(RespFrame::Double(score))) =
+                (iter.next(), iter.next())
+            {
+                zset.insert(String::from_utf8(member.to_vec())?, score);
+            }
+            Value::ZSet(zset)
+        }
+        (KIND_LIST, RespFrame::Array(elements)) => Value::List(elements.into_iter().collect()),
+        (kind, _) => return Err(anyhow!("invalid entry kind {}", kind)),
+    };
+    Ok(value)
+}
+
+/// a hash as the flat field/value array it is serialized as
+fn hash_frame(hash: &DashMap<String, RespFrame>) -> RespFrame {
+    let fields = hash
+        .iter()
+        .flat_map(|v| [BulkString::from(v.key().as_str()).into(), v.value().clone()])
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(fields).into()
+}
+
+/// a set as the array of members it is serialized as
+fn set_frame(set: &DashSet<String>) -> RespFrame {
+    let members = set
+        .iter()
+        .map(|v| BulkString::from(v.as_str()).into())
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(members).into()
+}
+
+/// a sorted set as the flat member/score array it is serialized as
+fn zset_frame(zset: &SortedSet) -> RespFrame {
+    let members = zset
+        .iter()
+        .flat_map(|(member, score)| [BulkString::from(member).into(), score.into()])
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(members).into()
+}
+
+/// a list as the array of its elements it is serialized as
+fn list_frame(list: &VecDeque<RespFrame>) -> RespFrame {
+    RespArray::new(list.iter().cloned().collect::<Vec<_>>()).into()
+}
+
+// time elapsed since the unix epoch
+fn unix_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn write_entry(
+    writer: &mut impl Write,
+    kind: u8,
+    key: &str,
+    expiry: i64,
+    value: RespFrame,
+) -> Result<()> {
+    writer.write_all(&[kind])?;
+    writer.write_all(&BulkString::from(key).encode())?;
+    writer.write_all(&expiry.encode())?;
+    writer.write_all(&value.encode())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleString;
+
+    #[test]
+    fn test_snapshot_copies_live_keys() {
+        let backend = Backend::new();
+        backend.set("str", BulkString::new("v").into());
+        backend.set("expired", BulkString::new("stale").into());
+        backend.expire_at("expired", Instant::now());
+        backend.hset("hash", "f", BulkString::new("1").into());
+        backend.sadd("set", ["a".into()]);
+        backend.zadd("zset", [(2.0, "b".into()), (1.0, "a".into())]);
+
+        let mut entries = backend.snapshot();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut hash = RespMap::new();
+        hash.insert("f".into(), BulkString::new("1").into());
+        let pair = |member: &str, score: f64| -> RespFrame {
+            RespArray::new([BulkString::from(member).into(), score.into()]).into()
+        };
+        assert_eq!(
+            entries,
+            [
+                ("hash".to_string(), hash.into()),
+                (
+                    "set".to_string(),
+                    RespSet::new([BulkString::from("a").into()]).into()
+                ),
+                ("str".to_string(), BulkString::new("v").into()),
+                (
+                    "zset".to_string(),
+                    RespArray::new([pair("a", 1.0), pair("b", 2.0)]).into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_during_writes() {
+        let backend = Backend::new();
+        backend.set("stable", BulkString::new("v").into());
+        let writer = {
+            let backend = backend.clone();
+            std::thread::spawn(move || {
+                for i in 0..2000 {
+                    let key = format!("k{}", i % 100);
+                    backend.set(&key, BulkString::new(i.to_string()).into());
+                    backend.hset(&format!("h{}", i % 10), &key, i.into());
+                    if i % 3 == 0 {
+                        backend.remove(&key);
+                    }
+                }
+            })
+        };
+        while !writer.is_finished() {
+            let entries = backend.snapshot();
+            assert!(entries.iter().any(|(key, _)| key == "stable"));
+            for (key, value) in entries {
+                match value {
+                    RespFrame::BulkString(_) => {
+                        assert!(key == "stable" || key.starts_with('k'))
+                    }
+                    RespFrame::Map(_) => assert!(key.starts_with('h')),
+                    value => panic!("unexpected value {:?} of {}", value, key),
+                }
+            }
+        }
+        writer.join().unwrap();
+        // the last write of 33 of the 100 keys removes them
+        assert_eq!(backend.snapshot().len(), 1 + 67 + 10);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dump.rdb");
+
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        backend.set("a\0b", BulkString::new("nul").into());
+        backend.set("session", BulkString::new("token").into());
+        backend.expire("session", Duration::from_secs(100));
+        backend.set("expired", BulkString::new("stale").into());
+        backend.expire_at("expired", Instant::now());
+        backend.set("nested", RespArray::new([1.into(), true.into()]).into());
+        backend.hset("user", "name", SimpleString::new("alice").into());
+        backend.hset("user", "age", 42.into());
+        backend.sadd("tags", ["a".into(), "b".into()]);
+        backend.zadd("board", [(1.5, "a".into()), (-3e20, "b".into())]);
+        backend.snapshot_to(&path)?;
+
+        let restored = Backend::new();
+        // the expired key isn't written
+        assert_eq!(restored.load_from(&path)?, 7);
+        assert_eq!(restored.get("expired"), None);
+        let ttl = restored.ttl("session").unwrap().unwrap();
+        assert!(ttl > Duration::from_secs(90) && ttl <= Duration::from_secs(100));
+        assert_eq!(restored.ttl("hello"), Some(None));
+        assert_eq!(restored.get("a\0b"), Some(BulkString::new("nul").into()));
+        assert_eq!(restored.get("hello"), Some(BulkString::new("world").into()));
+        assert_eq!(
+            restored.get("nested"),
+            Some(RespArray::new([1.into(), true.into()]).into())
+        );
+        assert_eq!(
+            restored.hget("user", "name"),
+            Some(SimpleString::new("alice").into())
+        );
+        assert_eq!(restored.hget("user", "age"), Some(42.into()));
+        assert_eq!(restored.smismember("tags", ["a", "b"]), vec![true, true]);
+        assert_eq!(
+            restored.zrange("board", 0, -1),
+            vec![("b".to_string(), -3e20), ("a".to_string(), 1.5)]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use std::future::Future;
+
+use crate::RespFrame;
+
+use super::Backend;
+
+/// Key/value access to the string values of a store, async so a tier behind the in-memory
+/// backend (a disk or a remote store) can be read through. `Backend` is the in-memory store.
+pub trait Store {
+    fn get(&self, key: &str) -> impl Future<Output = Option<RespFrame>> + Send;
+
+    fn set(&self, key: &str, value: RespFrame) -> impl Future<Output = ()> + Send;
+
+    /// remove the key, returning whether it existed
+    fn remove(&self, key: &str) -> impl Future<Output = bool> + Send;
+}
+
+impl Store for Backend {
+    async fn get(&self, key: &str) -> Option<RespFrame> {
+        Backend::get(self, key)
+    }
+
+    async fn set(&self, key: &str, value: RespFrame) {
+        Backend::set(self, key, value)
+    }
+
+    async fn remove(&self, key: &str) -> bool {
+        Backend::remove(self, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    // read `key` from `cache`, filling it from `tier` on a miss
+    async fn read_through(cache: &impl Store, tier: &impl Store, key: &str) -> Option<RespFrame> {
+        if let Some(value) = cache.get(key).await {
+            return Some(value);
+        }
+        let value = tier.get(key).await?;
+        cache.set(key, value.clone()).await;
+        Some(value)
+    }
+
+    #[tokio::test]
+    async fn test_backend_store() {
+        let (cache, tier) = (Backend::new(), Backend::new());
+        let value = RespFrame::from(BulkString::new("v"));
+        Store::set(&tier, "k", value.clone()).await;
+        assert_eq!(read_through(&cache, &tier, "k").await, Some(value.clone()));
+        assert_eq!(cache.get("k"), Some(value));
+        assert_eq!(read_through(&cache, &tier, "missing").await, None);
+
+        assert!(Store::remove(&tier, "k").await);
+        assert!(!Store::remove(&tier, "k").await);
+    }
+}
@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use dashmap::mapref::entry::Entry;
+
+use crate::{BulkString, RespFrame};
+
+use super::{Backend, Value};
+
+/// Bytes of a value stored by `SET`, integers being stored as their decimal representation.
+pub(crate) fn string_bytes(frame: &RespFrame) -> Vec<u8> {
+    match frame {
+        RespFrame::BulkString(s) => s.to_vec(),
+        RespFrame::SimpleString(s) => s.as_bytes().to_vec(),
+        RespFrame::Integer(n) => n.to_string().into_bytes(),
+        RespFrame::Double(d) => d.to_string().into_bytes(),
+        _ => vec![],
+    }
+}
+
+/// Condition on the existence of the key for `SET` to write it, NX and XX options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCondition {
+    IfAbsent,
+    IfPresent,
+}
+
+/// Options of `Backend::set_with_flags`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SetFlags {
+    pub condition: Option<SetCondition>,
+    /// time to live of the new value, the key doesn't expire if `None`
+    pub ttl: Option<Duration>,
+    /// return the previous string value of the key
+    pub get: bool,
+}
+
+impl Backend {
+    /// `SET` with its options: the value is only written if `flags.condition` holds, with the
+    /// time to live `flags.ttl`. Returns whether the value was written and, if `flags.get`
+    /// is set, the previous string value of the key.
+    pub fn set_with_flags(
+        &self,
+        key: &str,
+        value: RespFrame,
+        flags: SetFlags,
+    ) -> (bool, Option<RespFrame>) {
+        self.expire_if_needed(key);
+        let old = if flags.get { self.get(key) } else { None };
+        let exists = self.key_type(key).is_some();
+        let write = match flags.condition {
+            Some(SetCondition::IfAbsent) => !exists,
+            Some(SetCondition::IfPresent) => exists,
+            None => true,
+        };
+        if write {
+            self.set(key, value);
+            if let Some(ttl) = flags.ttl {
+                self.expire(key, ttl);
+            }
+        }
+        (write, old)
+    }
+
+    /// Overwrite the string at `offset` with `value`, zero-padding the gap if the string is
+    /// shorter than `offset`, and return the new length. The read-modify-write is done under
+    /// the key's entry lock. A missing key is created unless `value` is empty.
+    pub fn setrange(&self, key: &str, offset: usize, value: &[u8]) -> usize {
+        self.expire_if_needed(key);
+        if value.is_empty() {
+            return self
+                .get_live(key, Value::as_string)
+                .map(|v| string_bytes(&v).len())
+                .unwrap_or(0);
+        }
+        self.make_room_for(key);
+        let len = {
+            let new = || Value::String(BulkString::new(vec![]).into());
+            let Some(mut string) = self.get_or_insert_as(key, new, Value::as_string_mut) else {
+                return 0;
+            };
+            let mut data = string_bytes(&string);
+            let end = offset + value.len();
+            if data.len() < end {
+                data.resize(end, 0);
+            }
+            data[offset..end].copy_from_slice(value);
+            let len = data.len();
+            *string = BulkString::new(data).into();
+            len
+        };
+        self.touch(key);
+        len
+    }
+
+    /// Set the bit at `offset` of the string, bit 0 being the most significant bit of the
+    /// first byte, and return its previous value. The string is grown with zero bytes up to
+    /// the byte holding the bit, a missing key is created.
+    pub fn setbit(&self, key: &str, offset: usize, bit: bool) -> bool {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let old = {
+            let new = || Value::String(BulkString::new(vec![]).into());
+            let Some(mut string) = self.get_or_insert_as(key, new, Value::as_string_mut) else {
+                return false;
+            };
+            let mut data = string_bytes(&string);
+            let (byte, mask) = (offset / 8, 0x80 >> (offset % 8));
+            if data.len() <= byte {
+                data.resize(byte + 1, 0);
+            }
+            let old = data[byte] & mask != 0;
+            if bit {
+                data[byte] |= mask;
+            } else {
+                data[byte] &= !mask;
+            }
+            *string = BulkString::new(data).into();
+            old
+        };
+        self.touch(key);
+        old
+    }
+
+    /// Atomically replace the string value of the key with the one computed by `f` from the
+    /// current value (`None` if the key doesn't exist), under the key's entry lock. Nothing is
+    /// written if `f` fails. The time to live of the key is kept. A value of another type,
+    /// only found if a write raced the type check of the command, is replaced like by `SET`.
+    pub fn update<T, E>(
+        &self,
+        key: &str,
+        f: impl FnOnce(Option<&RespFrame>) -> Result<(RespFrame, T), E>,
+    ) -> Result<T, E> {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let ret = match self.keys.entry(key.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let (value, ret) = f(entry.get().as_string())?;
+                entry.insert(Value::String(value));
+                ret
+            }
+            Entry::Vacant(entry) => {
+                let (value, ret) = f(None)?;
+                entry.insert(Value::String(value));
+                ret
+            }
+        };
+        self.touch(key);
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_with_flags() {
+        let backend = Backend::new();
+        let value = |v: &str| RespFrame::from(BulkString::new(v));
+        let nx = SetFlags {
+            condition: Some(SetCondition::IfAbsent),
+            ..Default::default()
+        };
+        let xx = SetFlags {
+            condition: Some(SetCondition::IfPresent),
+            get: true,
+            ..Default::default()
+        };
+
+        assert_eq!(backend.set_with_flags("key", value("a"), xx), (false, None));
+        assert_eq!(backend.get("key"), None);
+        assert_eq!(backend.set_with_flags("key", value("a"), nx), (true, None));
+        assert_eq!(backend.set_with_flags("key", value("b"), nx), (false, None));
+        assert_eq!(
+            backend.set_with_flags("key", value("c"), xx),
+            (true, Some(value("a")))
+        );
+        assert_eq!(backend.get("key"), Some(value("c")));
+
+        let ttl = SetFlags {
+            ttl: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        backend.set_with_flags("key", value("d"), ttl);
+        assert!(backend.ttl("key").flatten().is_some());
+        // without a ttl the previous one is discarded
+        backend.set_with_flags("key", value("e"), SetFlags::default());
+        assert_eq!(backend.ttl("key"), Some(None));
+    }
+
+    #[test]
+    fn test_setrange() {
+        let backend = Backend::new();
+        assert_eq!(backend.setrange("key", 0, b""), 0);
+        assert!(backend.get("key").is_none());
+
+        assert_eq!(backend.setrange("key", 3, b"abc"), 6);
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new(b"\0\0\0abc".to_vec()).into())
+        );
+
+        backend.set("num", RespFrame::Integer(12345));
+        assert_eq!(backend.setrange("num", 1, b"x"), 5);
+        assert_eq!(backend.get("num"), Some(BulkString::new("1x345").into()));
+    }
+
+    #[test]
+    fn test_setbit() {
+        let backend = Backend::new();
+        assert!(!backend.setbit("key", 9, true));
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new(b"\0\x40".to_vec()).into())
+        );
+        assert!(backend.setbit("key", 9, false));
+        assert!(!backend.setbit("key", 0, true));
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new(b"\x80\0".to_vec()).into())
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let backend = Backend::new();
+        let ret: Result<usize, ()> = backend.update("key", |value| {
+            assert!(value.is_none());
+            Ok((BulkString::new("a").into(), 1))
+        });
+        assert_eq!(ret, Ok(1));
+        assert_eq!(backend.get("key"), Some(BulkString::new("a").into()));
+
+        let ret: Result<(), &str> = backend.update("key", |_| Err("failed"));
+        assert_eq!(ret, Err("failed"));
+        assert_eq!(backend.get("key"), Some(BulkString::new("a").into()));
+    }
+}
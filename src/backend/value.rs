@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use dashmap::{DashMap, DashSet};
+
+use crate::RespFrame;
+
+use super::zset::SortedSet;
+
+/// The value stored under a key. All the types share one keyspace, so a key holds exactly
+/// one of them and a command reading it as another type gets `WRONGTYPE`.
+#[derive(Debug, Clone)]
+pub(super) enum Value {
+    String(RespFrame),
+    Hash(DashMap<String, RespFrame>),
+    Set(DashSet<String>),
+    ZSet(SortedSet),
+    List(VecDeque<RespFrame>),
+}
+
+impl Value {
+    /// type of the value, as reported by redis `TYPE`
+    pub(super) fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+            Value::List(_) => "list",
+        }
+    }
+
+    pub(super) fn as_string(&self) -> Option<&RespFrame> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_string_mut(&mut self) -> Option<&mut RespFrame> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_hash(&self) -> Option<&DashMap<String, RespFrame>> {
+        match self {
+            Value::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_hash_mut(&mut self) -> Option<&mut DashMap<String, RespFrame>> {
+        match self {
+            Value::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_set(&self) -> Option<&DashSet<String>> {
+        match self {
+            Value::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_set_mut(&mut self) -> Option<&mut DashSet<String>> {
+        match self {
+            Value::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_zset(&self) -> Option<&SortedSet> {
+        match self {
+            Value::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_zset_mut(&mut self) -> Option<&mut SortedSet> {
+        match self {
+            Value::ZSet(zset) => Some(zset),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_list(&self) -> Option<&VecDeque<RespFrame>> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_list_mut(&mut self) -> Option<&mut VecDeque<RespFrame>> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use dashmap::DashMap;
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+use super::Backend;
+
+/// Flags of the clients watching each key, raised when the key is written.
+pub(super) type Watchers = DashMap<String, Vec<Arc<AtomicBool>>>;
+
+impl Backend {
+    /// Lock held by write commands while they run, shared between them.
+    pub async fn lock_writes(&self) -> RwLockReadGuard<'_, ()> {
+        self.writes.read().await
+    }
+
+    /// Lock held by `EXEC`, so no other write runs in the middle of a transaction, and by
+    /// `WATCH`, so no write is half done when the watch starts.
+    pub async fn lock_writes_exclusive(&self) -> RwLockWriteGuard<'_, ()> {
+        self.writes.write().await
+    }
+
+    /// raise `dirty` when `key` is written, until `unwatch`
+    pub fn watch(&self, key: &str, dirty: &Arc<AtomicBool>) {
+        self.watchers
+            .entry(key.to_string())
+            .or_default()
+            .push(dirty.clone());
+    }
+
+    pub fn unwatch(&self, key: &str, dirty: &Arc<AtomicBool>) {
+        self.watchers.remove_if_mut(key, |_, flags| {
+            flags.retain(|flag| !Arc::ptr_eq(flag, dirty));
+            flags.is_empty()
+        });
+    }
+
+    /// whether any client watches a key
+    pub fn has_watchers(&self) -> bool {
+        !self.watchers.is_empty()
+    }
+
+    /// Signal that the keys were written, raising the flags of the clients watching them.
+    /// `None` when the written keys aren't known, every watching client is then signalled.
+    pub fn signal_modified(&self, keys: Option<&[String]>) {
+        let raise = |flags: &Vec<Arc<AtomicBool>>| {
+            for flag in flags {
+                flag.store(true, Ordering::Relaxed);
+            }
+        };
+        match keys {
+            Some(keys) => {
+                for key in keys {
+                    if let Some(flags) = self.watchers.get(key) {
+                        raise(&flags);
+                    }
+                }
+            }
+            None => self.watchers.iter().for_each(|flags| raise(&flags)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_key_is_signalled() {
+        let backend = Backend::new();
+        let dirty = Arc::new(AtomicBool::new(false));
+        backend.watch("a", &dirty);
+        assert!(backend.has_watchers());
+
+        backend.signal_modified(Some(&["b".to_string()]));
+        assert!(!dirty.load(Ordering::Relaxed));
+        backend.signal_modified(Some(&["a".to_string()]));
+        assert!(dirty.load(Ordering::Relaxed));
+
+        dirty.store(false, Ordering::Relaxed);
+        backend.signal_modified(None);
+        assert!(dirty.load(Ordering::Relaxed));
+
+        backend.unwatch("a", &dirty);
+        assert!(!backend.has_watchers());
+    }
+}
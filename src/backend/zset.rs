@@ -0,0 +1,241 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+};
+
+use crate::cmd::index_range;
+
+use super::{Backend, Value};
+
+/// A sorted set: the score of each member, plus an index of the members ordered by score,
+/// ties being broken by the byte order of the members like redis does.
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    index: BTreeSet<(Score, String)>,
+}
+
+// f64 ordered by `total_cmp` so it can be used in the index, scores are never NaN
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl SortedSet {
+    /// Set the score of the member, returns whether it is a new member.
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        // -0.0 and 0.0 are the same score, but not for `total_cmp`
+        let score = score + 0.0;
+        let old = self.scores.insert(member.clone(), score);
+        if let Some(old) = old {
+            self.index.remove(&(Score(old), member.clone()));
+        }
+        self.index.insert((Score(score), member));
+        old.is_none()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// 0-based position of the member in ascending score order. Walks the index up to the
+    /// member, so it costs O(rank).
+    pub fn rank(&self, member: &str) -> Option<usize> {
+        let score = self.score(member)?;
+        Some(
+            self.index
+                .range(..(Score(score), member.to_string()))
+                .count(),
+        )
+    }
+
+    /// members with their score, in ascending score order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&str, f64)> {
+        self.index
+            .iter()
+            .map(|(score, member)| (member.as_str(), score.0))
+    }
+}
+
+impl Backend {
+    /// Add the members with their scores to the sorted set, creating it if the key doesn't
+    /// exist. The score of existing members is updated. Returns the number of new members.
+    pub fn zadd(&self, key: &str, members: impl IntoIterator<Item = (f64, String)>) -> usize {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let new = || Value::ZSet(SortedSet::default());
+        let added = match self.get_or_insert_as(key, new, Value::as_zset_mut) {
+            Some(mut zset) => members
+                .into_iter()
+                .filter(|(score, member)| zset.insert(member.clone(), *score))
+                .count(),
+            None => 0,
+        };
+        self.touch(key);
+        added
+    }
+
+    /// Add `increment` to the score of the member, which is created with a score of
+    /// `increment` if absent. Returns the new score, or `None` without changing anything if
+    /// it would not be a number, e.g. when adding `-inf` to `+inf`.
+    pub fn zincrby(&self, key: &str, increment: f64, member: &str) -> Option<f64> {
+        self.expire_if_needed(key);
+        self.make_room_for(key);
+        let score = {
+            let new = || Value::ZSet(SortedSet::default());
+            let mut zset = self.get_or_insert_as(key, new, Value::as_zset_mut)?;
+            let score = zset.score(member).unwrap_or(0.0) + increment;
+            if score.is_nan() {
+                drop(zset);
+                self.keys
+                    .remove_if(key, |_, v| v.as_zset().is_some_and(|zset| zset.len() == 0));
+                return None;
+            }
+            zset.insert(member.to_string(), score);
+            score
+        };
+        self.touch(key);
+        Some(score)
+    }
+
+    pub fn zscore(&self, key: &str, member: &str) -> Option<f64> {
+        let ret = self.get_live(key, Value::as_zset)?.score(member);
+        self.touch(key);
+        ret
+    }
+
+    /// rank of the member in ascending score order, `None` if the key or member doesn't exist
+    pub fn zrank(&self, key: &str, member: &str) -> Option<usize> {
+        let ret = self.get_live(key, Value::as_zset)?.rank(member);
+        self.touch(key);
+        ret
+    }
+
+    /// number of members of the sorted set, 0 if the key doesn't exist
+    pub fn zcard(&self, key: &str) -> usize {
+        let Some(zset) = self.get_live(key, Value::as_zset) else {
+            return 0;
+        };
+        let len = zset.len();
+        drop(zset);
+        self.touch(key);
+        len
+    }
+
+    /// Members with their score between the inclusive ranks `start` and `stop`, in ascending
+    /// score order. Negative ranks count from the highest score like redis `ZRANGE`.
+    pub fn zrange(&self, key: &str, start: i64, stop: i64) -> Vec<(String, f64)> {
+        let Some(zset) = self.get_live(key, Value::as_zset) else {
+            return vec![];
+        };
+        let range = index_range(zset.len(), start, stop);
+        let ret = zset
+            .iter()
+            .skip(range.start)
+            .take(range.len())
+            .map(|(member, score)| (member.to_string(), score))
+            .collect();
+        drop(zset);
+        self.touch(key);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_set_order() {
+        let mut zset = SortedSet::default();
+        assert!(zset.insert("b".into(), 1.0));
+        assert!(zset.insert("a".into(), 1.0));
+        assert!(zset.insert("c".into(), -2.5));
+        assert!(!zset.insert("c".into(), 3.0));
+        assert_eq!(zset.len(), 3);
+        assert_eq!(zset.score("c"), Some(3.0));
+
+        let members: Vec<_> = zset.iter().collect();
+        assert_eq!(members, vec![("a", 1.0), ("b", 1.0), ("c", 3.0)]);
+        assert_eq!(zset.rank("a"), Some(0));
+        assert_eq!(zset.rank("c"), Some(2));
+        assert_eq!(zset.rank("d"), None);
+    }
+
+    #[test]
+    fn test_zadd_and_zrange() {
+        let backend = Backend::new();
+        let added = backend.zadd(
+            "board",
+            [
+                (10.0, "alice".into()),
+                (5.0, "bob".into()),
+                (7.5, "carol".into()),
+            ],
+        );
+        assert_eq!(added, 3);
+        assert_eq!(backend.zadd("board", [(1.0, "bob".into())]), 0);
+        assert_eq!(backend.key_type("board"), Some("zset"));
+        assert_eq!(backend.zcard("board"), 3);
+        assert_eq!(backend.zscore("board", "bob"), Some(1.0));
+        assert_eq!(backend.zscore("board", "dave"), None);
+
+        assert_eq!(
+            backend.zrange("board", 0, -1),
+            vec![
+                ("bob".to_string(), 1.0),
+                ("carol".to_string(), 7.5),
+                ("alice".to_string(), 10.0)
+            ]
+        );
+        assert_eq!(
+            backend.zrange("board", -2, -2),
+            vec![("carol".to_string(), 7.5)]
+        );
+        assert!(backend.zrange("board", 5, 10).is_empty());
+        assert!(backend.zrange("missing", 0, -1).is_empty());
+        assert_eq!(backend.zcard("missing"), 0);
+    }
+
+    #[test]
+    fn test_zincrby_changes_rank() {
+        let backend = Backend::new();
+        backend.zadd("board", [(1.0, "alice".into()), (2.0, "bob".into())]);
+        assert_eq!(backend.zrank("board", "alice"), Some(0));
+        assert_eq!(backend.zrank("board", "bob"), Some(1));
+
+        assert_eq!(backend.zincrby("board", 1.5, "alice"), Some(2.5));
+        assert_eq!(backend.zrank("board", "alice"), Some(1));
+        assert_eq!(backend.zrank("board", "bob"), Some(0));
+
+        // absent members start from 0
+        assert_eq!(backend.zincrby("board", -1.0, "carol"), Some(-1.0));
+        assert_eq!(backend.zrank("board", "carol"), Some(0));
+        assert_eq!(backend.zrank("board", "dave"), None);
+        assert_eq!(backend.zrank("missing", "alice"), None);
+
+        backend.zadd("board", [(f64::INFINITY, "eve".into())]);
+        assert_eq!(backend.zincrby("board", f64::NEG_INFINITY, "eve"), None);
+        assert_eq!(backend.zscore("board", "eve"), Some(f64::INFINITY));
+
+        // a failed increment doesn't leave an empty sorted set behind
+        assert_eq!(backend.zincrby("empty", f64::NAN, "alice"), None);
+        assert_eq!(backend.key_type("empty"), None);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    backend::{string_bytes, Backend},
+    RespArray, RespFrame, SimpleError,
+};
+
+use super::{
+    extract_args, index_range, int_arg, validate_command, wrongtype, BitCount, CommandError,
+    CommandExecutor, GetBit, SetBit,
+};
+
+impl CommandExecutor for SetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "string") {
+            return wrongtype();
+        }
+        if (self.offset / 8 + 1) as u64 > backend.proto_max_bulk_len() {
+            return SimpleError::new(
+                "ERR string exceeds maximum allowed size (proto-max-bulk-len)",
+            )
+            .into();
+        }
+        RespFrame::integer(backend.setbit(&self.key, self.offset, self.bit) as i64)
+    }
+}
+
+impl CommandExecutor for GetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let data = match string_value(backend, &self.key) {
+            Ok(data) => data,
+            Err(e) => return e,
+        };
+        let bit = data
+            .get(self.offset / 8)
+            .is_some_and(|byte| byte & (0x80 >> (self.offset % 8)) != 0);
+        RespFrame::integer(bit as i64)
+    }
+}
+
+impl CommandExecutor for BitCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let data = match string_value(backend, &self.key) {
+            Ok(data) => data,
+            Err(e) => return e,
+        };
+        let range = match self.range {
+            Some((start, end)) => index_range(data.len(), start, end),
+            None => 0..data.len(),
+        };
+        let count: u32 = data[range].iter().map(|byte| byte.count_ones()).sum();
+        RespFrame::integer(count as i64)
+    }
+}
+
+// the bytes of the string value of the key, empty if the key doesn't exist
+fn string_value(backend: &Backend, key: &str) -> Result<Vec<u8>, RespFrame> {
+    match backend.get(key) {
+        Some(value) => Ok(string_bytes(&value)),
+        None if backend.key_type(key).is_some() => Err(wrongtype()),
+        None => Ok(vec![]),
+    }
+}
+
+// a bit offset, which redis limits to the bits of a 512MB string
+fn offset_arg(value: RespFrame) -> Result<usize, CommandError> {
+    int_arg(value)
+        .ok()
+        .filter(|offset| (0..1 << 32).contains(offset))
+        .map(|offset| offset as usize)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument("bit offset is not an integer or out of range".into())
+        })
+}
+
+impl TryFrom<RespArray> for SetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setbit"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(offset), Some(bit)) => {
+                let bit = match int_arg(bit) {
+                    Ok(0) => false,
+                    Ok(1) => true,
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "bit is not an integer or out of range".into(),
+                        ))
+                    }
+                };
+                Ok(SetBit {
+                    key: key.try_into()?,
+                    offset: offset_arg(offset)?,
+                    bit,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "invalid key, offset or bit".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for GetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getbit"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(offset)) => Ok(GetBit {
+                key: key.try_into()?,
+                offset: offset_arg(offset)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid key or offset".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["bitcount"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(key @ RespFrame::BulkString(_)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("invalid key".into())),
+        };
+        let range = match (args.next(), args.next(), args.next()) {
+            (None, _, _) => None,
+            (Some(start), Some(end), None) => Some((int_arg(start)?, int_arg(end)?)),
+            _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+        };
+        Ok(BitCount { key, range })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::Command, BulkString};
+    use anyhow::Result;
+
+    fn run(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let args = args.iter().map(|arg| BulkString::from(*arg).into());
+        Ok(Command::try_from(RespArray::new(args.collect::<Vec<_>>()))?.execute(backend))
+    }
+
+    #[test]
+    fn test_setbit_grows_the_string() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key", BulkString::from("a").into());
+        assert_eq!(
+            run(&backend, &["setbit", "key", "23", "1"])?,
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new(b"a\0\x01".to_vec()).into())
+        );
+        assert_eq!(
+            run(&backend, &["setbit", "key", "23", "0"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            run(&backend, &["getbit", "key", "23"])?,
+            RespFrame::Integer(0)
+        );
+        // "a" is 0b01100001
+        assert_eq!(
+            run(&backend, &["getbit", "key", "1"])?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            run(&backend, &["getbit", "key", "100"])?,
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run(&backend, &["getbit", "missing", "0"])?,
+            RespFrame::Integer(0)
+        );
+
+        assert!(run(&backend, &["setbit", "key", "1", "2"]).is_err());
+        assert!(run(&backend, &["setbit", "key", "-1", "1"]).is_err());
+        assert!(run(&backend, &["getbit", "key", "4294967296"]).is_err());
+
+        backend.sadd("set", ["a".into()]);
+        assert_eq!(run(&backend, &["setbit", "set", "0", "1"])?, wrongtype());
+        assert_eq!(run(&backend, &["getbit", "set", "0"])?, wrongtype());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitcount_range() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key", BulkString::from("foobar").into());
+        assert_eq!(run(&backend, &["bitcount", "key"])?, RespFrame::Integer(26));
+        assert_eq!(
+            run(&backend, &["bitcount", "key", "0", "0"])?,
+            RespFrame::Integer(4)
+        );
+        assert_eq!(
+            run(&backend, &["bitcount", "key", "1", "1"])?,
+            RespFrame::Integer(6)
+        );
+        assert_eq!(
+            run(&backend, &["bitcount", "key", "-2", "-1"])?,
+            RespFrame::Integer(7)
+        );
+        assert_eq!(
+            run(&backend, &["bitcount", "key", "5", "1"])?,
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run(&backend, &["bitcount", "missing", "0", "-1"])?,
+            RespFrame::Integer(0)
+        );
+        assert!(run(&backend, &["bitcount", "key", "0"]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{BulkString, RespArray, RespBuilder, RespFrame, RespMap, SimpleError};
+
+use super::{
+    extract_args, request_meta, validate_command, Command, CommandError, CommandExecutor,
+    CommandInfo, CommandMeta, CommandSubcommand, COMMAND_TABLE,
+};
+
+impl CommandExecutor for CommandInfo {
+    fn execute(self, _backend: &crate::backend::Backend) -> RespFrame {
+        match self.subcommand {
+            CommandSubcommand::List => {
+                let ret = COMMAND_TABLE
+                    .iter()
+                    .map(|meta| meta.to_frame())
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(ret).into()
+            }
+            CommandSubcommand::Count => RespFrame::integer(COMMAND_TABLE.len() as i64),
+            // like redis, unknown commands are left out
+            CommandSubcommand::Docs(names) => {
+                let mut docs = RespMap::new();
+                for meta in COMMAND_TABLE {
+                    if names.is_empty() || names.iter().any(|name| name == meta.name) {
+                        docs.insert(meta.name.to_string(), meta.to_docs());
+                    }
+                }
+                docs.into()
+            }
+            CommandSubcommand::GetKeys(args) => get_keys(args),
+        }
+    }
+}
+
+// the key arguments of the command, at the positions given by its metadata
+fn get_keys(args: RespArray) -> RespFrame {
+    let request = RespFrame::Array(args);
+    let Some(meta) = request_meta(&request) else {
+        return SimpleError::new("ERR Invalid command specified").into();
+    };
+    let RespFrame::Array(args) = &request else {
+        unreachable!("the request was built as an array");
+    };
+    let arity_ok = match meta.arity {
+        arity if arity < 0 => args.len() as i64 >= -arity,
+        arity => args.len() as i64 == arity,
+    };
+    if !arity_ok {
+        return SimpleError::new("ERR Invalid number of arguments specified for command").into();
+    }
+    if meta.first_key == 0 {
+        return SimpleError::new("ERR The command has no key arguments").into();
+    }
+    let keys = Command::key_args(&request)
+        .into_iter()
+        .map(|key| BulkString::from(key).into())
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(keys).into()
+}
+
+impl CommandMeta {
+    // - [name, arity, [flag ...], first key, last key, step]
+    fn to_frame(&self) -> RespFrame {
+        let flags = self
+            .flags
+            .iter()
+            .fold(RespBuilder::array(), |flags, flag| flags.push_simple(*flag));
+        RespBuilder::array()
+            .push_bulk(self.name)
+            .push_int(self.arity)
+            .push(flags.build())
+            .push_int(self.first_key)
+            .push_int(self.last_key)
+            .push_int(self.step)
+            .build()
+    }
+
+    // - {summary, since, arity}
+    fn to_docs(&self) -> RespFrame {
+        RespBuilder::map()
+            .entry("summary", self.summary)
+            .entry("since", self.since)
+            .entry("arity", self.arity)
+            .build()
+    }
+}
+
+impl TryFrom<RespArray> for CommandInfo {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let is_docs = matches!(value.get(1), Some(RespFrame::BulkString(s)) if s.eq_ignore_ascii_case(b"docs"));
+        let is_getkeys = matches!(value.get(1), Some(RespFrame::BulkString(s)) if s.eq_ignore_ascii_case(b"getkeys"));
+        match value.len() {
+            _ if is_getkeys => {
+                validate_command(&value, &["command", "getkeys"])?;
+                let args = extract_args(value, 2)?;
+                if args.is_empty() {
+                    return Err(CommandError::InvalidArgument(
+                        "command getkeys needs a command".into(),
+                    ));
+                }
+                Ok(CommandInfo {
+                    subcommand: CommandSubcommand::GetKeys(RespArray::new(args)),
+                })
+            }
+            _ if is_docs => {
+                validate_command(&value, &["command", "docs"])?;
+                let names = extract_args(value, 2)?
+                    .into_iter()
+                    .map(|name| String::try_from(name).map(|name| name.to_ascii_lowercase()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(CommandInfo {
+                    subcommand: CommandSubcommand::Docs(names),
+                })
+            }
+            1 => {
+                validate_command(&value, &["command"])?;
+                Ok(CommandInfo {
+                    subcommand: CommandSubcommand::List,
+                })
+            }
+            2 => {
+                validate_command(&value, &["command", "count"])?;
+                Ok(CommandInfo {
+                    subcommand: CommandSubcommand::Count,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "command supports only no subcommand, COUNT, DOCS or GETKEYS".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::Backend, BulkString, RespDecode, RespEncode, SimpleString};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_command_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*1\r\n$7\r\ncommand\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = CommandInfo::try_from(frame)?;
+        assert_eq!(cmd.subcommand, CommandSubcommand::List);
+
+        let mut buf = BytesMut::from("*2\r\n$7\r\ncommand\r\n$5\r\nCOUNT\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = CommandInfo::try_from(frame)?;
+        assert_eq!(cmd.subcommand, CommandSubcommand::Count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_command() {
+        let backend = Backend::new();
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::Count,
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, (COMMAND_TABLE.len() as i64).into());
+
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::List,
+        };
+        let RespFrame::Array(result) = cmd.execute(&backend) else {
+            panic!("COMMAND should return an array");
+        };
+        assert_eq!(result.len(), COMMAND_TABLE.len());
+        assert_eq!(
+            result[0],
+            RespArray::new([
+                BulkString::from("get").into(),
+                2.into(),
+                RespArray::new([
+                    SimpleString::new("readonly").into(),
+                    SimpleString::new("fast").into()
+                ])
+                .into(),
+                1.into(),
+                1.into(),
+                1.into(),
+            ])
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_command_docs() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$7\r\ncommand\r\n$4\r\nDOCS\r\n$3\r\nGET\r\n$4\r\nnope\r\n");
+        let cmd = CommandInfo::try_from(RespArray::decode(&mut buf)?)?;
+        assert_eq!(
+            cmd.subcommand,
+            CommandSubcommand::Docs(vec!["get".into(), "nope".into()])
+        );
+
+        let mut buf = BytesMut::from(&cmd.execute(&Backend::new()).encode()[..]);
+        let RespFrame::Map(docs) = RespFrame::decode(&mut buf)? else {
+            panic!("COMMAND DOCS should return a map");
+        };
+        assert_eq!(docs.len(), 1);
+        let mut get = RespMap::new();
+        get.insert(
+            "summary".into(),
+            BulkString::from("Returns the string value of a key.").into(),
+        );
+        get.insert("since".into(), BulkString::from("1.0.0").into());
+        get.insert("arity".into(), 2.into());
+        assert_eq!(docs.get("get"), Some(&get.into()));
+
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::Docs(vec![]),
+        };
+        let RespFrame::Map(docs) = cmd.execute(&Backend::new()) else {
+            panic!("COMMAND DOCS should return a map");
+        };
+        assert_eq!(docs.len(), COMMAND_TABLE.len());
+        Ok(())
+    }
+
+    fn getkeys(args: &[&str]) -> Result<RespFrame> {
+        let args = ["command", "getkeys"]
+            .iter()
+            .chain(args)
+            .map(|arg| BulkString::from(*arg).into())
+            .collect::<Vec<RespFrame>>();
+        Ok(CommandInfo::try_from(RespArray::new(args))?.execute(&Backend::new()))
+    }
+
+    fn keys(keys: &[&str]) -> RespFrame {
+        let keys = keys.iter().map(|key| BulkString::from(*key).into());
+        RespArray::new(keys.collect::<Vec<RespFrame>>()).into()
+    }
+
+    #[test]
+    fn test_command_getkeys() -> Result<()> {
+        assert_eq!(getkeys(&["GET", "k"])?, keys(&["k"]));
+        assert_eq!(getkeys(&["MSET", "a", "1", "b", "2"])?, keys(&["a", "b"]));
+        assert_eq!(getkeys(&["touch", "a", "b", "c"])?, keys(&["a", "b", "c"]));
+        assert_eq!(
+            getkeys(&["ping"])?,
+            SimpleError::new("ERR The command has no key arguments").into()
+        );
+        assert_eq!(
+            getkeys(&["get"])?,
+            SimpleError::new("ERR Invalid number of arguments specified for command").into()
+        );
+        assert_eq!(
+            getkeys(&["nope", "k"])?,
+            SimpleError::new("ERR Invalid command specified").into()
+        );
+        assert!(getkeys(&[]).is_err());
+        Ok(())
+    }
+}
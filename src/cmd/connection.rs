@@ -0,0 +1,722 @@
+use std::{
+    collections::{BTreeSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tracing::warn;
+
+use crate::{
+    backend::{Backend, MessageSender},
+    BulkString, RespArray, RespBuilder, RespFrame, SimpleError, SimpleString,
+};
+
+use super::{
+    extract_args, transaction::Transaction, validate_command, validate_subcommand, Client, Command,
+    CommandError, CommandExecutor, ConnectionExecutor, Hello, Monitor, Ping, Quit, Reset, RESP_OK,
+};
+
+/// State of a client connection, shared by the commands it sends.
+#[derive(Debug)]
+pub struct ConnectionState {
+    pub id: u64,
+    /// negotiated RESP protocol version, 2 until the client sends `HELLO 3`
+    pub protocol: u8,
+    /// set with `CLIENT SETNAME`
+    pub name: Option<String>,
+    // subscribed pub/sub channels and patterns
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    // published messages are queued here by the backend, created on the first subscription
+    messages: Option<(MessageSender, mpsc::Receiver<RespFrame>)>,
+    // replies sent after the reply of the current command, for commands replying more than once
+    replies: VecDeque<RespFrame>,
+    // commands queued since MULTI, `None` outside of a transaction
+    transaction: Option<Transaction>,
+    // keys watched since WATCH, and the flag the backend raises when one of them is written
+    watched: Vec<String>,
+    watched_dirty: Arc<AtomicBool>,
+    // commands of every client since MONITOR, `None` unless monitoring
+    monitor: Option<broadcast::Receiver<String>>,
+    // set by QUIT, the connection is closed once the reply is sent
+    closing: bool,
+    // set by SHUTDOWN, the connection is closed without a reply
+    shutting_down: bool,
+    // malformed requests sent so far, see `protocol-error-budget`
+    protocol_errors: u64,
+}
+
+impl ConnectionState {
+    pub fn new(id: u64) -> Self {
+        ConnectionState {
+            id,
+            protocol: 2,
+            name: None,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            messages: None,
+            replies: VecDeque::new(),
+            transaction: None,
+            watched: Vec::new(),
+            watched_dirty: Arc::new(AtomicBool::new(false)),
+            monitor: None,
+            closing: false,
+            shutting_down: false,
+            protocol_errors: 0,
+        }
+    }
+
+    /// count a malformed request of the client, returns how many it sent
+    pub fn record_protocol_error(&mut self) -> u64 {
+        self.protocol_errors += 1;
+        self.protocol_errors
+    }
+
+    /// subscribe to the channel, returns the number of subscriptions of the connection
+    pub fn subscribe(&mut self, backend: &Backend, channel: &str) -> usize {
+        if self.channels.insert(channel.to_string()) {
+            let sender = self.message_sender(backend);
+            backend.subscribe(channel, self.id, sender);
+        }
+        self.subscription_count()
+    }
+
+    /// unsubscribe from the channel, returns the number of subscriptions left
+    pub fn unsubscribe(&mut self, backend: &Backend, channel: &str) -> usize {
+        if self.channels.remove(channel) {
+            backend.unsubscribe(channel, self.id);
+        }
+        self.subscription_count()
+    }
+
+    /// subscribe to the glob pattern, returns the number of subscriptions of the connection
+    pub fn psubscribe(&mut self, backend: &Backend, pattern: &str) -> usize {
+        if self.patterns.insert(pattern.to_string()) {
+            let sender = self.message_sender(backend);
+            backend.psubscribe(pattern, self.id, sender);
+        }
+        self.subscription_count()
+    }
+
+    /// unsubscribe from the pattern, returns the number of subscriptions left
+    pub fn punsubscribe(&mut self, backend: &Backend, pattern: &str) -> usize {
+        if self.patterns.remove(pattern) {
+            backend.punsubscribe(pattern, self.id);
+        }
+        self.subscription_count()
+    }
+
+    fn message_sender(&mut self, backend: &Backend) -> MessageSender {
+        let (sender, _) = self
+            .messages
+            .get_or_insert_with(|| backend.message_channel());
+        sender.clone()
+    }
+
+    /// whether messages were published faster than the connection could take them, the
+    /// connection must then be closed
+    pub fn is_lagging(&self) -> bool {
+        self.messages
+            .as_ref()
+            .is_some_and(|(sender, _)| sender.is_lagging())
+    }
+
+    /// unsubscribe from every channel and pattern, e.g. when the connection is closed
+    pub fn unsubscribe_all(&mut self, backend: &Backend) {
+        for channel in std::mem::take(&mut self.channels) {
+            backend.unsubscribe(&channel, self.id);
+        }
+        for pattern in std::mem::take(&mut self.patterns) {
+            backend.punsubscribe(&pattern, self.id);
+        }
+    }
+
+    pub fn channels(&self) -> Vec<String> {
+        self.channels.iter().cloned().collect()
+    }
+
+    pub fn patterns(&self) -> Vec<String> {
+        self.patterns.iter().cloned().collect()
+    }
+
+    /// number of channels and patterns the connection is subscribed to
+    pub fn subscription_count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    /// queue a reply to be sent after the reply of the current command
+    pub fn push_reply(&mut self, frame: RespFrame) {
+        self.replies.push_back(frame);
+    }
+
+    /// Return the connection to its defaults (RESP2, no subscriptions, no name), keeping
+    /// its id. Per-connection state added later must be reset here too.
+    pub fn reset(&mut self, backend: &Backend) {
+        self.unsubscribe_all(backend);
+        self.transaction = None;
+        self.unwatch(backend);
+        self.monitor = None;
+        self.protocol = 2;
+        self.name = None;
+        backend.set_client_name(self.id, None);
+        self.replies.clear();
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// start queuing commands for `EXEC`, `false` if a transaction was already started
+    pub fn begin_transaction(&mut self) -> bool {
+        if self.transaction.is_some() {
+            return false;
+        }
+        self.transaction = Some(Transaction::default());
+        true
+    }
+
+    /// queue the command parsed from `request` for `EXEC`
+    pub fn queue_command(&mut self, cmd: Command, request: RespFrame) {
+        if let Some(transaction) = &mut self.transaction {
+            transaction.commands.push((cmd, request));
+        }
+    }
+
+    /// make `EXEC` discard the current transaction, e.g. when a command was rejected
+    pub fn fail_transaction(&mut self) {
+        if let Some(transaction) = &mut self.transaction {
+            transaction.failed = true;
+        }
+    }
+
+    /// end the current transaction, returning it
+    pub fn take_transaction(&mut self) -> Option<Transaction> {
+        self.transaction.take()
+    }
+
+    /// watch the key until `unwatch`
+    pub fn watch(&mut self, backend: &Backend, key: String) {
+        if !self.watched.contains(&key) {
+            backend.watch(&key, &self.watched_dirty);
+            self.watched.push(key);
+        }
+    }
+
+    /// stop watching every key, returns whether one of them was written meanwhile
+    pub fn unwatch(&mut self, backend: &Backend) -> bool {
+        for key in self.watched.drain(..) {
+            backend.unwatch(&key, &self.watched_dirty);
+        }
+        self.watched_dirty.swap(false, Ordering::Relaxed)
+    }
+
+    /// take the replies queued by the current command
+    pub fn take_replies(&mut self) -> impl Iterator<Item = RespFrame> + '_ {
+        self.replies.drain(..)
+    }
+
+    /// whether the connection must be closed once the reply of the command is sent
+    pub fn is_closing(&self) -> bool {
+        self.closing
+    }
+
+    /// Close the connection without replying to the current command, like redis does once
+    /// `SHUTDOWN` succeeds.
+    pub fn close_without_reply(&mut self) {
+        self.shutting_down = true;
+    }
+
+    /// whether the connection is closed instead of replying, see `close_without_reply`
+    pub fn is_closing_without_reply(&self) -> bool {
+        self.shutting_down
+    }
+
+    /// whether the connection receives the commands of every client, see `MONITOR`
+    pub fn is_monitoring(&self) -> bool {
+        self.monitor.is_some()
+    }
+
+    /// Wait for the next message published to a subscribed channel, or for the next command
+    /// of a client when monitoring.
+    pub async fn next_message(&mut self) -> Option<RespFrame> {
+        let (messages, monitor) = (&mut self.messages, &mut self.monitor);
+        let message = async {
+            match messages {
+                Some((_, receiver)) => receiver.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        let line = async {
+            match monitor {
+                Some(receiver) => next_line(receiver).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            message = message => message,
+            line = line => line,
+        }
+    }
+}
+
+// the next command line for a monitoring connection, lines it lagged behind are skipped
+async fn next_line(receiver: &mut broadcast::Receiver<String>) -> Option<RespFrame> {
+    loop {
+        match receiver.recv().await {
+            Ok(line) => return Some(SimpleString::new(line).into()),
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("monitoring connection skipped {} commands", skipped)
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+impl ConnectionExecutor for Hello {
+    fn execute_with(self, _backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        match self.protocol {
+            None => {}
+            Some(protocol @ (2 | 3)) => conn.protocol = protocol as u8,
+            Some(_) => return SimpleError::new("NOPROTO unsupported protocol version").into(),
+        }
+
+        RespBuilder::map()
+            .entry("server", "redis")
+            .entry("version", env!("CARGO_PKG_VERSION"))
+            .entry("proto", conn.protocol as i64)
+            .entry("id", conn.id as i64)
+            .entry("mode", "standalone")
+            .entry("role", "master")
+            .entry("modules", RespBuilder::array().build())
+            .build()
+    }
+}
+
+impl CommandExecutor for Hello {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl ConnectionExecutor for Reset {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        conn.reset(backend);
+        SimpleString::new("RESET").into()
+    }
+}
+
+/// Like redis, a RESP2 subscriber gets the reply as a pub/sub message, `["pong", message]`,
+/// so it can be read by the client's pub/sub loop.
+impl ConnectionExecutor for Ping {
+    fn execute_with(self, _backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        if conn.protocol == 2 && conn.subscription_count() > 0 {
+            let message = BulkString::new(self.message.unwrap_or_default());
+            return RespArray::new([BulkString::from("pong").into(), message.into()]).into();
+        }
+        match self.message {
+            Some(message) => BulkString::new(message).into(),
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl CommandExecutor for Ping {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl ConnectionExecutor for Quit {
+    fn execute_with(self, _backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        conn.closing = true;
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for Quit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl ConnectionExecutor for Monitor {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        conn.monitor.get_or_insert_with(|| backend.monitor());
+        RESP_OK.clone()
+    }
+}
+
+/// Commands are only sent to a connection, a fresh one is gone right after.
+impl CommandExecutor for Monitor {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for Reset {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl ConnectionExecutor for Client {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        match self {
+            Client::Id => RespFrame::integer(conn.id as i64),
+            Client::GetName => BulkString::new(conn.name.clone().unwrap_or_default()).into(),
+            Client::SetName(name) => {
+                if name.bytes().any(|b| !(b'!'..=b'~').contains(&b)) {
+                    return SimpleError::new(
+                        "ERR Client names cannot contain spaces, newlines or special characters.",
+                    )
+                    .into();
+                }
+                // an empty name removes the name
+                conn.name = Some(name).filter(|name| !name.is_empty());
+                backend.set_client_name(conn.id, conn.name.clone());
+                RESP_OK.clone()
+            }
+            Client::List => {
+                let list: String = backend
+                    .clients()
+                    .iter()
+                    .map(|client| {
+                        format!(
+                            "id={} addr={} name={} age={} db={}\n",
+                            client.id,
+                            client.addr,
+                            client.name.as_deref().unwrap_or_default(),
+                            client.age().as_secs(),
+                            client.db
+                        )
+                    })
+                    .collect();
+                BulkString::new(list).into()
+            }
+        }
+    }
+}
+
+impl CommandExecutor for Client {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl TryFrom<RespArray> for Hello {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        match value.len() {
+            1 => {
+                validate_command(&value, &["hello"])?;
+                Ok(Hello { protocol: None })
+            }
+            2 => {
+                validate_command(&value, &["hello"])?;
+                let mut args = extract_args(value, 1)?.into_iter();
+                match args.next() {
+                    Some(protocol @ RespFrame::BulkString(_)) => {
+                        let protocol = String::try_from(protocol)?.parse().map_err(|_| {
+                            CommandError::InvalidArgument(
+                                "Protocol version is not an integer or out of range".into(),
+                            )
+                        })?;
+                        Ok(Hello {
+                            protocol: Some(protocol),
+                        })
+                    }
+                    _ => Err(CommandError::InvalidArgument(
+                        "invalid protocol version".into(),
+                    )),
+                }
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "hello command takes at most a protocol version".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Ping {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ping"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let message = match (args.next(), args.next()) {
+            (None, _) => None,
+            (Some(RespFrame::BulkString(message)), None) => Some(message.to_vec()),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "wrong number of arguments for 'ping' command".into(),
+                ))
+            }
+        };
+        Ok(Ping { message })
+    }
+}
+
+impl TryFrom<RespArray> for Quit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // like redis, arguments are ignored
+        validate_command(&value, &["quit"])?;
+        Ok(Quit)
+    }
+}
+
+impl TryFrom<RespArray> for Monitor {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["monitor"])?;
+        Ok(Monitor)
+    }
+}
+
+impl TryFrom<RespArray> for Reset {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["reset"])?;
+        Ok(Reset)
+    }
+}
+
+impl TryFrom<RespArray> for Client {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(s)) => s.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "client command needs a subcommand".into(),
+                ))
+            }
+        };
+        match subcommand.as_slice() {
+            b"id" => {
+                validate_subcommand(&value, &["client", "id"], 1)?;
+                Ok(Client::Id)
+            }
+            b"list" => {
+                validate_subcommand(&value, &["client", "list"], 1)?;
+                Ok(Client::List)
+            }
+            b"getname" => {
+                validate_subcommand(&value, &["client", "getname"], 1)?;
+                Ok(Client::GetName)
+            }
+            b"setname" => {
+                validate_subcommand(&value, &["client", "setname"], 2)?;
+                match extract_args(value, 2)?.into_iter().next() {
+                    Some(name @ RespFrame::BulkString(_)) => Ok(Client::SetName(name.try_into()?)),
+                    _ => Err(CommandError::InvalidArgument("invalid name".into())),
+                }
+            }
+            _ => Err(CommandError::InvalidArgument(format!(
+                "unknown subcommand '{}'",
+                String::from_utf8_lossy(&subcommand)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_ping() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\nPING\r\n$2\r\nhi\r\n");
+        let cmd = Ping::try_from(RespArray::decode(&mut buf)?)?;
+        assert_eq!(cmd.message.as_deref(), Some(&b"hi"[..]));
+        let mut buf = BytesMut::from("*3\r\n$4\r\nping\r\n$1\r\na\r\n$1\r\nb\r\n");
+        assert!(Ping::try_from(RespArray::decode(&mut buf)?).is_err());
+
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        let ping = |message: Option<&str>| Ping {
+            message: message.map(|message| message.as_bytes().to_vec()),
+        };
+        assert_eq!(
+            ping(None).execute_with(&backend, &mut conn),
+            SimpleString::new("PONG").into()
+        );
+        assert_eq!(
+            ping(Some("hi")).execute_with(&backend, &mut conn),
+            BulkString::from("hi").into()
+        );
+
+        conn.subscribe(&backend, "news");
+        let pong = |message: &str| -> RespFrame {
+            RespArray::new([
+                BulkString::from("pong").into(),
+                BulkString::from(message).into(),
+            ])
+            .into()
+        };
+        assert_eq!(ping(None).execute_with(&backend, &mut conn), pong(""));
+        assert_eq!(
+            ping(Some("hi")).execute_with(&backend, &mut conn),
+            pong("hi")
+        );
+        // RESP3 replies and messages can't be mixed up
+        conn.protocol = 3;
+        assert_eq!(
+            ping(None).execute_with(&backend, &mut conn),
+            SimpleString::new("PONG").into()
+        );
+        conn.unsubscribe_all(&backend);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$5\r\nhello\r\n$1\r\n3\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Hello::try_from(frame)?;
+        assert_eq!(cmd.protocol, Some(3));
+
+        let mut buf = BytesMut::from("*2\r\n$5\r\nhello\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Hello::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_command() {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(7);
+
+        let cmd = Hello { protocol: Some(3) };
+        let RespFrame::Map(map) = cmd.execute_with(&backend, &mut conn) else {
+            panic!("HELLO should return a map");
+        };
+        assert_eq!(conn.protocol, 3);
+        assert_eq!(map["proto"], 3.into());
+        assert_eq!(map["id"], 7.into());
+        assert_eq!(map["server"], BulkString::from("redis").into());
+        assert_eq!(map["mode"], BulkString::from("standalone").into());
+        assert_eq!(map["role"], BulkString::from("master").into());
+        assert!(map.contains_key("version"));
+
+        let cmd = Hello { protocol: Some(4) };
+        assert_eq!(
+            cmd.execute_with(&backend, &mut conn),
+            SimpleError::new("NOPROTO unsupported protocol version").into()
+        );
+        assert_eq!(conn.protocol, 3);
+    }
+
+    #[test]
+    fn test_reset_command() -> Result<()> {
+        let mut buf = BytesMut::from("*1\r\n$5\r\nreset\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Reset::try_from(frame)?;
+
+        let backend = Backend::new();
+        backend.set("hello", BulkString::from("world").into());
+        let client = backend.register_client("127.0.0.1:4000");
+        let mut conn = ConnectionState::new(client.id());
+        conn.protocol = 3;
+        conn.name = Some("worker".into());
+        backend.set_client_name(client.id(), conn.name.clone());
+        conn.subscribe(&backend, "news");
+        conn.psubscribe(&backend, "news.*");
+
+        assert_eq!(
+            cmd.execute_with(&backend, &mut conn),
+            SimpleString::new("RESET").into()
+        );
+        assert_eq!(conn.protocol, 2);
+        assert_eq!(conn.id, client.id());
+        assert_eq!(conn.name, None);
+        assert_eq!(backend.clients()[0].name, None);
+        assert_eq!(conn.subscription_count(), 0);
+        assert_eq!(backend.publish("news", BulkString::from("hi").into()), 0);
+        // the data is untouched
+        assert_eq!(backend.get("hello"), Some(BulkString::from("world").into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$6\r\nclient\r\n$2\r\nID\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(Client::try_from(frame)?, Client::Id));
+
+        let mut buf = BytesMut::from("*3\r\n$6\r\nclient\r\n$7\r\nsetname\r\n$3\r\nfoo\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(Client::try_from(frame)?, Client::SetName(name) if name == "foo"));
+
+        let mut buf = BytesMut::from("*2\r\n$6\r\nclient\r\n$4\r\nkill\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Client::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_command() {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(7);
+
+        assert_eq!(
+            Client::Id.execute_with(&backend, &mut conn),
+            RespFrame::Integer(7)
+        );
+        assert_eq!(
+            Client::GetName.execute_with(&backend, &mut conn),
+            BulkString::from("").into()
+        );
+        assert_eq!(
+            Client::SetName("worker-1".into()).execute_with(&backend, &mut conn),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            Client::GetName.execute_with(&backend, &mut conn),
+            BulkString::from("worker-1").into()
+        );
+        assert!(matches!(
+            Client::SetName("a b".into()).execute_with(&backend, &mut conn),
+            RespFrame::Error(_)
+        ));
+        assert_eq!(conn.name.as_deref(), Some("worker-1"));
+    }
+
+    #[test]
+    fn test_client_list() {
+        let backend = Backend::new();
+        let client = backend.register_client("127.0.0.1:4000");
+        let mut conn = ConnectionState::new(client.id());
+        Client::SetName("worker".into()).execute_with(&backend, &mut conn);
+        let _other = backend.register_client("127.0.0.1:4001");
+
+        let RespFrame::BulkString(list) = Client::List.execute_with(&backend, &mut conn) else {
+            panic!("CLIENT LIST should return a bulk string");
+        };
+        let list = String::from_utf8(list.to_vec()).unwrap();
+        let lines: Vec<_> = list.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!(
+                "id={} addr=127.0.0.1:4000 name=worker age=0 db=0",
+                client.id()
+            )
+        );
+        assert!(lines[1].contains("addr=127.0.0.1:4001 name= "));
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use crate::{backend::Backend, RespArray, RespFrame, SimpleError, SimpleString};
+
+use super::{
+    extract_args, num::parse_float, validate_subcommand, CommandError, CommandExecutor,
+    DebugCommand, RESP_OK,
+};
+
+impl DebugCommand {
+    /// run the command without blocking the runtime, other connections keep being served
+    pub async fn execute_async(self, backend: &Backend) -> RespFrame {
+        match self {
+            DebugCommand::Sleep(duration) => {
+                tokio::time::sleep(duration).await;
+                RESP_OK.clone()
+            }
+            DebugCommand::Object(key) => debug_object(backend, &key),
+        }
+    }
+}
+
+impl CommandExecutor for DebugCommand {
+    /// blocks the calling thread, connections run it with `execute_async`
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            DebugCommand::Sleep(duration) => {
+                std::thread::sleep(duration);
+                RESP_OK.clone()
+            }
+            DebugCommand::Object(key) => debug_object(backend, &key),
+        }
+    }
+}
+
+// like redis: `Value at:0 refcount:1 encoding:embstr serializedlength:6 lru_seconds_idle:0`,
+// the length being the one of the frame the value is serialized as
+fn debug_object(backend: &Backend, key: &str) -> RespFrame {
+    let (Some(encoding), Some(len)) = (backend.object_encoding(key), backend.serialized_len(key))
+    else {
+        return SimpleError::new("ERR no such key").into();
+    };
+    let idle = backend.idle_time(key).unwrap_or_default();
+    SimpleString::new(format!(
+        "Value at:0 refcount:1 encoding:{} serializedlength:{} lru_seconds_idle:{}",
+        encoding,
+        len,
+        idle.as_secs()
+    ))
+    .into()
+}
+
+impl TryFrom<RespArray> for DebugCommand {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(s)) => s.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "debug command needs a subcommand".into(),
+                ))
+            }
+        };
+        if subcommand == b"object" {
+            validate_subcommand(&value, &["debug", "object"], 2)?;
+            return match extract_args(value, 2)?.into_iter().next() {
+                Some(key @ RespFrame::BulkString(_)) => Ok(DebugCommand::Object(key.try_into()?)),
+                _ => Err(CommandError::InvalidArgument("invalid key".into())),
+            };
+        }
+        validate_subcommand(&value, &["debug", "sleep"], 2)?;
+        let mut args = extract_args(value, 2)?.into_iter();
+        let seconds = match args.next() {
+            Some(RespFrame::BulkString(seconds)) => parse_float(&seconds)?,
+            _ => return Err(CommandError::NotAFloat),
+        };
+        if seconds < 0.0 {
+            return Err(CommandError::InvalidArgument(
+                "duration must not be negative".into(),
+            ));
+        }
+        let duration = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| CommandError::InvalidArgument("duration is out of range".into()))?;
+        Ok(DebugCommand::Sleep(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_debug_sleep_try_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\ndebug\r\n$5\r\nsleep\r\n$3\r\n0.5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let DebugCommand::Sleep(duration) = DebugCommand::try_from(frame)? else {
+            panic!("expected DEBUG SLEEP");
+        };
+        assert_eq!(duration, Duration::from_millis(500));
+
+        let frame = RespArray::new([
+            BulkString::from("debug").into(),
+            BulkString::from("sleep").into(),
+            BulkString::from("-1").into(),
+        ]);
+        assert!(DebugCommand::try_from(frame).is_err());
+
+        let frame = RespArray::new([
+            BulkString::from("debug").into(),
+            BulkString::from("sleep").into(),
+            BulkString::from("1e20").into(),
+        ]);
+        assert!(DebugCommand::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_debug_sleep_execute_async() {
+        let start = tokio::time::Instant::now();
+        let reply = DebugCommand::Sleep(Duration::from_millis(50))
+            .execute_async(&Backend::new())
+            .await;
+        assert_eq!(reply, RESP_OK.clone());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_debug_object() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\nDEBUG\r\n$6\r\nOBJECT\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = DebugCommand::try_from(frame)?;
+        assert!(matches!(&cmd, DebugCommand::Object(key) if key == "key"));
+
+        let backend = Backend::new();
+        assert_eq!(
+            DebugCommand::Object("key".into()).execute(&backend),
+            SimpleError::new("ERR no such key").into()
+        );
+        backend.set("key", BulkString::from("hello").into());
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleString::new(
+                "Value at:0 refcount:1 encoding:embstr serializedlength:11 lru_seconds_idle:0"
+            )
+            .into()
+        );
+
+        backend.sadd("set", ["1".into(), "2".into()]);
+        let RespFrame::SimpleString(reply) = DebugCommand::Object("set".into()).execute(&backend)
+        else {
+            panic!("expected a simple string");
+        };
+        // "*2\r\n$1\r\n1\r\n$1\r\n2\r\n"
+        assert!(reply.contains("encoding:intset serializedlength:18 "));
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{backend::Backend, BulkString, RespArray, RespFrame};
+
+use super::{
+    extract_args, int_arg, parse_key, validate_command, CommandError, CommandExecutor, Expire,
+    ExpireAt, PExpire, PExpireAt, PTtl, Persist, Ttl,
+};
+
+impl CommandExecutor for Expire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_in(backend, &self.key, self.seconds.saturating_mul(1000))
+    }
+}
+
+impl CommandExecutor for PExpire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_in(backend, &self.key, self.milliseconds)
+    }
+}
+
+impl CommandExecutor for ExpireAt {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let millis = self.timestamp.saturating_mul(1000);
+        expire_in(backend, &self.key, millis.saturating_sub(unix_millis()))
+    }
+}
+
+impl CommandExecutor for PExpireAt {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        expire_in(
+            backend,
+            &self.key,
+            self.timestamp.saturating_sub(unix_millis()),
+        )
+    }
+}
+
+/// current unix time in milliseconds
+pub(super) fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// `PEXPIREAT key timestamp`, the form a time to live is appended to the aof in so that
+/// replaying it later doesn't extend the time to live
+pub(super) fn pexpireat_frame(key: &str, timestamp: i64) -> RespFrame {
+    RespArray::new([
+        BulkString::from("pexpireat").into(),
+        BulkString::from(key).into(),
+        BulkString::from(timestamp.to_string()).into(),
+    ])
+    .into()
+}
+
+impl CommandExecutor for Persist {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::integer(backend.persist(&self.key) as i64)
+    }
+}
+
+impl CommandExecutor for Ttl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // round to the nearest second like redis
+        ttl_reply(backend, &self.key, |ttl| {
+            ((ttl.as_millis() + 500) / 1000) as i64
+        })
+    }
+}
+
+impl CommandExecutor for PTtl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        ttl_reply(backend, &self.key, |ttl| ttl.as_millis() as i64)
+    }
+}
+
+// set the key to expire in `millis`, a deadline in the past deletes the key right away
+fn expire_in(backend: &Backend, key: &str, millis: i64) -> RespFrame {
+    let ret = if millis <= 0 {
+        backend.remove(key)
+    } else {
+        backend.expire_at(key, Instant::now() + Duration::from_millis(millis as u64))
+    };
+    RespFrame::integer(ret as i64)
+}
+
+// -2 if the key doesn't exist, -1 if it has no expiry
+fn ttl_reply(backend: &Backend, key: &str, f: impl Fn(Duration) -> i64) -> RespFrame {
+    let ret = match backend.ttl(key) {
+        None => -2,
+        Some(None) => -1,
+        Some(Some(ttl)) => f(ttl),
+    };
+    RespFrame::integer(ret)
+}
+
+// parse `<cmd> key <integer>`
+fn parse_key_and_int(value: RespArray, name: &'static str) -> Result<(String, i64), CommandError> {
+    validate_command(&value, &[name])?;
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next()) {
+        (Some(key @ RespFrame::BulkString(_)), Some(n)) => Ok((key.try_into()?, int_arg(n)?)),
+        _ => Err(CommandError::InvalidArgument("invalid key or value".into())),
+    }
+}
+
+impl TryFrom<RespArray> for Expire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, seconds) = parse_key_and_int(value, "expire")?;
+        Ok(Expire { key, seconds })
+    }
+}
+
+impl TryFrom<RespArray> for PExpire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, milliseconds) = parse_key_and_int(value, "pexpire")?;
+        Ok(PExpire { key, milliseconds })
+    }
+}
+
+impl TryFrom<RespArray> for ExpireAt {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, timestamp) = parse_key_and_int(value, "expireat")?;
+        if timestamp < 0 {
+            return Err(CommandError::InvalidArgument(
+                "timestamp must not be negative".into(),
+            ));
+        }
+        Ok(ExpireAt { key, timestamp })
+    }
+}
+
+impl TryFrom<RespArray> for PExpireAt {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, timestamp) = parse_key_and_int(value, "pexpireat")?;
+        if timestamp < 0 {
+            return Err(CommandError::InvalidArgument(
+                "timestamp must not be negative".into(),
+            ));
+        }
+        Ok(PExpireAt { key, timestamp })
+    }
+}
+
+impl TryFrom<RespArray> for Persist {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Persist {
+            key: parse_key(value, "persist")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Ttl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Ttl {
+            key: parse_key(value, "ttl")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PTtl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(PTtl {
+            key: parse_key(value, "pttl")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_expireat_from_resp_array() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*3\r\n$8\r\nexpireat\r\n$5\r\nhello\r\n$10\r\n1700000000\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = ExpireAt::try_from(frame)?;
+        assert_eq!(cmd.key, "hello");
+        assert_eq!(cmd.timestamp, 1700000000);
+
+        let mut buf = BytesMut::from("*3\r\n$8\r\nexpireat\r\n$5\r\nhello\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ExpireAt::try_from(frame).is_err());
+
+        let mut buf = BytesMut::from("*3\r\n$7\r\npexpire\r\n$5\r\nhello\r\n$3\r\nabc\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(PExpire::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_ttl_commands() {
+        let backend = Backend::new();
+        let ttl = |key: &str| Ttl { key: key.into() }.execute(&backend);
+        let pttl = |key: &str| PTtl { key: key.into() }.execute(&backend);
+
+        assert_eq!(ttl("hello"), RespFrame::Integer(-2));
+        assert_eq!(pttl("hello"), RespFrame::Integer(-2));
+        let cmd = Expire {
+            key: "hello".into(),
+            seconds: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        backend.set("hello", BulkString::new("world").into());
+        assert_eq!(ttl("hello"), RespFrame::Integer(-1));
+        assert_eq!(pttl("hello"), RespFrame::Integer(-1));
+
+        let cmd = PExpire {
+            key: "hello".into(),
+            milliseconds: 10_000,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(ttl("hello"), RespFrame::Integer(10));
+        let RespFrame::Integer(ms) = pttl("hello") else {
+            panic!("PTTL should return an integer");
+        };
+        assert!(ms > 9_900 && ms <= 10_000);
+
+        let cmd = ExpireAt {
+            key: "hello".into(),
+            timestamp: 1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(ttl("hello"), RespFrame::Integer(-2));
+        assert_eq!(backend.get("hello"), None);
+
+        backend.set("hello", BulkString::new("world").into());
+        let cmd = PExpireAt {
+            key: "hello".into(),
+            timestamp: unix_millis() + 10_000,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(ttl("hello"), RespFrame::Integer(10));
+    }
+
+    #[test]
+    fn test_persist_command() {
+        let backend = Backend::new();
+        let persist = |key: &str| Persist { key: key.into() }.execute(&backend);
+        assert_eq!(persist("hello"), RespFrame::Integer(0));
+
+        backend.set("hello", BulkString::new("world").into());
+        assert_eq!(persist("hello"), RespFrame::Integer(0));
+
+        backend.expire("hello", Duration::from_secs(10));
+        assert_eq!(persist("hello"), RespFrame::Integer(1));
+        assert_eq!(
+            Ttl {
+                key: "hello".into()
+            }
+            .execute(&backend),
+            RespFrame::Integer(-1)
+        );
+    }
+}
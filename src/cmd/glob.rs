@@ -0,0 +1,108 @@
+/// Match `s` against a redis style glob pattern supporting `*`, `?`, `[...]` (with `^`
+/// negation and `a-z` ranges) and `\` escapes.
+pub(crate) fn glob_match(pattern: &[u8], s: &[u8]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // position to resume from when a `*` has to absorb one more byte
+    let mut backtrack: Option<(usize, usize)> = None;
+    while i < s.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    backtrack = Some((p, i));
+                    p += 1;
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    i += 1;
+                    continue;
+                }
+                b'[' => {
+                    if let Some((matched, next)) = match_class(pattern, p, s[i]) {
+                        if matched {
+                            p = next;
+                            i += 1;
+                            continue;
+                        }
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == s[i] {
+                        p += 2;
+                        i += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == s[i] {
+                        p += 1;
+                        i += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        match backtrack {
+            Some((bp, bi)) => {
+                backtrack = Some((bp, bi + 1));
+                p = bp + 1;
+                i = bi + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+// match a `[...]` class starting at `pattern[start]`, returns whether `c` matched and the
+// position after the class, or None for an unterminated class
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<(bool, usize)> {
+    let mut p = start + 1;
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == c;
+            p += 2;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' && pattern[p + 2] != b']' {
+            let (lo, hi) = (
+                pattern[p].min(pattern[p + 2]),
+                pattern[p].max(pattern[p + 2]),
+            );
+            matched |= lo <= c && c <= hi;
+            p += 3;
+        } else {
+            matched |= pattern[p] == c;
+            p += 1;
+        }
+    }
+    if p >= pattern.len() {
+        return None;
+    }
+    Some((matched != negate, p + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"*", b"timeout"));
+        assert!(glob_match(b"time*", b"timeout"));
+        assert!(glob_match(b"*max*", b"proto-max-bulk-len"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-b]llo", b"hbllo"));
+        assert!(glob_match(b"h\\*llo", b"h*llo"));
+        assert!(!glob_match(b"h\\*llo", b"hello"));
+        assert!(!glob_match(b"time", b"timeout"));
+        assert!(glob_match(b"news.*", b"news.tech"));
+        assert!(!glob_match(b"news.*", b"sports.tech"));
+    }
+}
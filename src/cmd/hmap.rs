@@ -1,11 +1,15 @@
-use crate::{BulkString, RespArray, RespFrame, RespNull};
+use crate::{BulkString, RespArray, RespFrame, RespMap, RespNull};
 
 use super::{
-    extract_args, validate_command, CommandError, CommandExecutor, HGet, HGetAll, HSet, RESP_OK,
+    extract_args, int_arg, validate_command, wrongtype, CommandError, CommandExecutor, HGet,
+    HGetAll, HRandField, HSet, HSetNx, RESP_OK,
 };
 
 impl CommandExecutor for HGet {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "hash") {
+            return wrongtype();
+        }
         match backend.hget(&self.key, &self.field) {
             Some(value) => value,
             None => RespFrame::Null(RespNull),
@@ -15,30 +19,59 @@ impl CommandExecutor for HGet {
 
 impl CommandExecutor for HSet {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "hash") {
+            return wrongtype();
+        }
         backend.hset(&self.key, &self.field, self.value);
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for HSetNx {
+    fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "hash") {
+            return wrongtype();
+        }
+        let set = backend.hset_nx(&self.key, &self.field, self.value);
+        RespFrame::integer(set as i64)
+    }
+}
+
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
-        match backend.hgetall(&self.key) {
-            Some(hmap) => {
-                let mut data: Vec<_> = hmap
-                    .iter()
-                    .map(|v| (v.key().to_owned(), v.value().clone()))
-                    .collect();
-                if self.sort {
-                    data.sort_by(|a, b| a.0.cmp(&b.0))
-                }
-                let ret = data
-                    .into_iter()
-                    .flat_map(|(k, v)| vec![BulkString::from(k.as_ref()).into(), v])
-                    .collect::<Vec<RespFrame>>();
-                RespArray::new(ret).into()
+        if backend.key_type(&self.key).is_some_and(|t| t != "hash") {
+            return wrongtype();
+        }
+        // a map sorted by field, RESP2 connections get it as a flat field/value array
+        let mut ret = RespMap::new();
+        if let Some(hmap) = backend.hgetall(&self.key) {
+            for v in hmap.iter() {
+                ret.insert(v.key().to_owned(), v.value().clone());
+            }
+        }
+        ret.into()
+    }
+}
+
+impl CommandExecutor for HRandField {
+    fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "hash") {
+            return wrongtype();
+        }
+        let Some(count) = self.count else {
+            return match backend.hrandfield(&self.key, 1).pop() {
+                Some((field, _)) => BulkString::from(field).into(),
+                None => RespFrame::Null(RespNull),
+            };
+        };
+        let mut ret = vec![];
+        for (field, value) in backend.hrandfield(&self.key, count) {
+            ret.push(BulkString::from(field).into());
+            if self.with_values {
+                ret.push(value);
             }
-            None => RespArray::new([]).into(),
         }
+        RespArray::new(ret).into()
     }
 }
 
@@ -46,13 +79,15 @@ impl TryFrom<RespArray> for HGet {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["hget"], 2)?;
+        validate_command(&value, &["hget"])?;
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => Ok(HGet {
-                key: String::from_utf8(key.to_vec())?,
-                field: String::from_utf8(field.to_vec())?,
-            }),
+            (Some(key @ RespFrame::BulkString(_)), Some(field @ RespFrame::BulkString(_))) => {
+                Ok(HGet {
+                    key: key.try_into()?,
+                    field: field.try_into()?,
+                })
+            }
             _ => Err(CommandError::InvalidArgument("invalid key or field".into())),
         }
     }
@@ -62,16 +97,41 @@ impl TryFrom<RespArray> for HSet {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["hset"], 3)?;
+        validate_command(&value, &["hset"])?;
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field)), Some(value)) => {
-                Ok(HSet {
-                    key: String::from_utf8(key.to_vec())?,
-                    field: String::from_utf8(field.to_vec())?,
-                    value,
-                })
-            }
+            (
+                Some(key @ RespFrame::BulkString(_)),
+                Some(field @ RespFrame::BulkString(_)),
+                Some(value),
+            ) => Ok(HSet {
+                key: key.try_into()?,
+                field: field.try_into()?,
+                value,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid key or field or value".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for HSetNx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hsetnx"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(key @ RespFrame::BulkString(_)),
+                Some(field @ RespFrame::BulkString(_)),
+                Some(value),
+            ) => Ok(HSetNx {
+                key: key.try_into()?,
+                field: field.try_into()?,
+                value,
+            }),
             _ => Err(CommandError::InvalidArgument(
                 "invalid key or field or value".into(),
             )),
@@ -83,18 +143,44 @@ impl TryFrom<RespArray> for HGetAll {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["hgetall"], 1)?;
+        validate_command(&value, &["hgetall"])?;
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
-            Some(RespFrame::BulkString(key)) => Ok(HGetAll {
-                key: String::from_utf8(key.to_vec())?,
-                sort: false,
+            Some(key @ RespFrame::BulkString(_)) => Ok(HGetAll {
+                key: key.try_into()?,
             }),
             _ => Err(CommandError::InvalidArgument("invalid key".into())),
         }
     }
 }
 
+impl TryFrom<RespArray> for HRandField {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hrandfield"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(key @ RespFrame::BulkString(_)) => key.try_into()?,
+            _ => return Err(CommandError::InvalidArgument("invalid key".into())),
+        };
+        let count = args.next().map(int_arg).transpose()?;
+        let with_values = match args.next() {
+            None => false,
+            Some(RespFrame::BulkString(arg)) if arg.eq_ignore_ascii_case(b"withvalues") => true,
+            Some(_) => return Err(CommandError::InvalidArgument("syntax error".into())),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::InvalidArgument("syntax error".into()));
+        }
+        Ok(HRandField {
+            key,
+            count,
+            with_values,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,17 +247,101 @@ mod tests {
 
         let cmd = HGetAll {
             key: "hello".into(),
-            sort: true,
         };
 
-        let expected = RespArray::new([
-            BulkString::from("myfield").into(),
-            BulkString::from("world").into(),
-            BulkString::from("myfield1").into(),
-            BulkString::from("world1").into(),
-        ]);
+        let mut expected = RespMap::new();
+        expected.insert("myfield".into(), BulkString::new("world").into());
+        expected.insert("myfield1".into(), BulkString::new("world1").into());
         let result = cmd.execute(&backend);
         assert_eq!(result, expected.into());
+        assert_eq!(
+            result.into_resp2(),
+            RespArray::new([
+                BulkString::from("myfield").into(),
+                BulkString::from("world").into(),
+                BulkString::from("myfield1").into(),
+                BulkString::from("world1").into(),
+            ])
+            .into()
+        );
+
+        let cmd = HGetAll {
+            key: "missing".into(),
+        };
+        assert_eq!(cmd.execute(&backend), RespMap::new().into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hsetnx_command() -> Result<()> {
+        let backend = Backend::new();
+        let hsetnx = |value: &str| -> Result<RespFrame> {
+            let args = ["hsetnx", "h", "f", value]
+                .map(|arg| BulkString::from(arg).into())
+                .to_vec();
+            Ok(HSetNx::try_from(RespArray::new(args))?.execute(&backend))
+        };
+        assert_eq!(hsetnx("v1")?, RespFrame::Integer(1));
+        // the field exists, it keeps its value
+        assert_eq!(hsetnx("v2")?, RespFrame::Integer(0));
+        assert_eq!(backend.hget("h", "f"), Some(BulkString::new("v1").into()));
+
+        backend.set("s", BulkString::new("v").into());
+        let cmd = HSetNx {
+            key: "s".into(),
+            field: "f".into(),
+            value: BulkString::new("v").into(),
+        };
+        assert_eq!(cmd.execute(&backend), wrongtype());
         Ok(())
     }
+
+    #[test]
+    fn test_hrandfield_command() -> Result<()> {
+        let backend = Backend::new();
+        let hrandfield = |args: &str| -> Result<RespFrame> {
+            let args = format!("hrandfield {args}")
+                .split(' ')
+                .map(|arg| BulkString::from(arg).into())
+                .collect::<Vec<RespFrame>>();
+            Ok(HRandField::try_from(RespArray::new(args))?.execute(&backend))
+        };
+        assert_eq!(hrandfield("h")?, RespFrame::Null(RespNull));
+        assert_eq!(hrandfield("h 2")?, RespArray::new([]).into());
+
+        backend.hset("h", "f", BulkString::new("v").into());
+        assert_eq!(hrandfield("h")?, BulkString::new("f").into());
+        let field = |n| RespArray::new(vec![BulkString::new("f").into(); n]).into();
+        assert_eq!(hrandfield("h 3")?, field(1));
+        assert_eq!(hrandfield("h -3")?, field(3));
+        assert_eq!(
+            hrandfield("h -2 WITHVALUES")?,
+            RespArray::new(["f", "v", "f", "v"].map(|s| BulkString::new(s).into())).into()
+        );
+
+        assert!(hrandfield("h 1 withscores").is_err());
+        assert!(hrandfield("h x").is_err());
+        backend.set("s", BulkString::new("v").into());
+        assert_eq!(hrandfield("s")?, wrongtype());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_commands_wrongtype() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        let cmd = HGet {
+            key: "hello".into(),
+            field: "myfield".into(),
+        };
+        assert_eq!(cmd.execute(&backend), wrongtype());
+
+        let cmd = HSet {
+            key: "hello".into(),
+            field: "myfield".into(),
+            value: BulkString::new("world").into(),
+        };
+        assert_eq!(cmd.execute(&backend), wrongtype());
+        assert_eq!(backend.hget("hello", "myfield"), None);
+    }
 }
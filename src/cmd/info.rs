@@ -0,0 +1,158 @@
+use std::fmt::Write;
+
+use crate::{backend::Backend, BulkString, RespArray, RespFrame};
+
+use super::{extract_args, validate_command, CommandError, CommandExecutor, Info};
+
+const SECTIONS: &[&str] = &["server", "clients", "stats", "keyspace"];
+// sections only shown when asked for explicitly or with `all`
+const EXTRA_SECTIONS: &[&str] = &["commandstats"];
+
+impl CommandExecutor for Info {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let sections: Vec<&str> = match self.section.as_deref() {
+            None | Some("default") => SECTIONS.to_vec(),
+            Some("all") | Some("everything") => {
+                SECTIONS.iter().chain(EXTRA_SECTIONS).copied().collect()
+            }
+            Some(section) => SECTIONS
+                .iter()
+                .chain(EXTRA_SECTIONS)
+                .filter(|s| **s == section)
+                .copied()
+                .collect(),
+        };
+
+        let body = sections
+            .into_iter()
+            .map(|section| render_section(section, backend))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        BulkString::new(body).into()
+    }
+}
+
+fn render_section(section: &str, backend: &Backend) -> String {
+    let mut ret = String::new();
+    let _ = writeln!(ret, "# {}\r", capitalize(section));
+    let _ = match section {
+        "server" => write!(
+            ret,
+            "redis_version:{}\r\nprocess_id:{}\r\nuptime_in_seconds:{}\r\n",
+            env!("CARGO_PKG_VERSION"),
+            std::process::id(),
+            backend.uptime().as_secs()
+        ),
+        "clients" => write!(ret, "connected_clients:{}\r\n", backend.connected_clients()),
+        "stats" => write!(
+            ret,
+            "total_commands_processed:{}\r\n",
+            backend.commands_processed()
+        ),
+        "keyspace" => match backend.key_count() {
+            0 => Ok(()),
+            n => write!(
+                ret,
+                "db0:keys={},expires={},avg_ttl=0\r\n",
+                n,
+                backend.expires_count()
+            ),
+        },
+        "commandstats" => {
+            for (name, calls) in backend.command_stats() {
+                let _ = write!(ret, "cmdstat_{}:calls={}\r\n", name, calls);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    ret
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl TryFrom<RespArray> for Info {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        match value.len() {
+            1 => {
+                validate_command(&value, &["info"])?;
+                Ok(Info { section: None })
+            }
+            2 => {
+                validate_command(&value, &["info"])?;
+                let mut args = extract_args(value, 1)?.into_iter();
+                match args.next() {
+                    Some(section @ RespFrame::BulkString(_)) => Ok(Info {
+                        section: Some(String::try_from(section)?.to_ascii_lowercase()),
+                    }),
+                    _ => Err(CommandError::InvalidArgument("invalid section".into())),
+                }
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "info command takes at most one section".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespDecode, SimpleString};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn info_text(backend: &Backend, section: Option<&str>) -> String {
+        let cmd = Info {
+            section: section.map(|s| s.to_string()),
+        };
+        match cmd.execute(backend) {
+            RespFrame::BulkString(s) => String::from_utf8(s.to_vec()).unwrap(),
+            frame => panic!("unexpected frame: {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_info_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\ninfo\r\n$6\r\nServer\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let info = Info::try_from(frame)?;
+        assert_eq!(info.section.as_deref(), Some("server"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_command() {
+        let backend = Backend::new();
+        backend.set("hello", SimpleString::new("world").into());
+        backend.record_command("set");
+        backend.record_command("get");
+        backend.record_command("get");
+
+        let text = info_text(&backend, None);
+        assert!(text.contains("# Server\r\n"));
+        assert!(text.contains("uptime_in_seconds:"));
+        assert!(text.contains("connected_clients:0\r\n"));
+        assert!(text.contains("total_commands_processed:3\r\n"));
+        assert!(!text.contains("# Commandstats"));
+        assert!(text.contains("db0:keys=1,"));
+
+        let text = info_text(&backend, Some("server"));
+        assert!(text.contains("redis_version:"));
+        assert!(!text.contains("# Keyspace"));
+
+        let text = info_text(&backend, Some("commandstats"));
+        assert_eq!(
+            text,
+            "# Commandstats\r\ncmdstat_get:calls=2\r\ncmdstat_set:calls=1\r\n"
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use std::time::Duration;
+
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+use super::{
+    extract_args, int_arg, validate_command, CommandError, CommandExecutor, ConnectionExecutor,
+    ConnectionState, Copy, Dump, FlushAll, FlushDb, Move, Restore, Touch, Unlink, RESP_OK,
+};
+
+impl ConnectionExecutor for FlushDb {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        let db = backend.client(conn.id).map_or(0, |client| client.db);
+        backend.clear_db(db);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for FlushDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for FlushAll {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.clear_all();
+        RESP_OK.clone()
+    }
+}
+
+/// Like redis configured with a single database: the only valid index is the current
+/// database, which the key can't be moved to.
+impl CommandExecutor for Move {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        if self.db != 0 {
+            return SimpleError::new("ERR DB index is out of range").into();
+        }
+        SimpleError::new("ERR source and destination objects are the same").into()
+    }
+}
+
+impl TryFrom<RespArray> for Move {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["move"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(_)), Some(db)) => Ok(Move { db: int_arg(db)? }),
+            _ => Err(CommandError::InvalidArgument("invalid key or db".into())),
+        }
+    }
+}
+
+impl CommandExecutor for Unlink {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::integer(backend.unlink(&self.keys) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Unlink {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unlink"])?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| match key {
+                RespFrame::BulkString(_) => key.try_into(),
+                _ => Err(CommandError::InvalidArgument("invalid key".into())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'unlink' command".into(),
+            ));
+        }
+        Ok(Unlink { keys })
+    }
+}
+
+// the optional ASYNC or SYNC of FLUSHDB and FLUSHALL, keys are always removed right away
+fn parse_flush_mode(value: RespArray, name: &'static str) -> Result<(), CommandError> {
+    validate_command(&value, &[name])?;
+    match &extract_args(value, 1)?[..] {
+        [] => Ok(()),
+        [RespFrame::BulkString(mode)]
+            if mode.eq_ignore_ascii_case(b"async") || mode.eq_ignore_ascii_case(b"sync") =>
+        {
+            Ok(())
+        }
+        _ => Err(CommandError::InvalidArgument("syntax error".into())),
+    }
+}
+
+impl TryFrom<RespArray> for FlushDb {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        parse_flush_mode(value, "flushdb")?;
+        Ok(FlushDb)
+    }
+}
+
+impl TryFrom<RespArray> for FlushAll {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        parse_flush_mode(value, "flushall")?;
+        Ok(FlushAll)
+    }
+}
+
+impl CommandExecutor for Copy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.source == self.destination {
+            return SimpleError::new("ERR source and destination objects are the same").into();
+        }
+        let copied = backend.copy(&self.source, &self.destination, self.replace);
+        RespFrame::integer(copied as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Copy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let replace = match value.len() {
+            3 => {
+                validate_command(&value, &["copy"])?;
+                false
+            }
+            4 => {
+                validate_command(&value, &["copy"])?;
+                match value[3] {
+                    RespFrame::BulkString(ref flag) if flag.eq_ignore_ascii_case(b"replace") => {
+                        true
+                    }
+                    _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+                }
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "copy command must have 2 or 3 arguments".into(),
+                ))
+            }
+        };
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (
+                Some(source @ RespFrame::BulkString(_)),
+                Some(destination @ RespFrame::BulkString(_)),
+            ) => Ok(Copy {
+                source: source.try_into()?,
+                destination: destination.try_into()?,
+                replace,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid source or destination".into(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for Touch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let touched = self
+            .keys
+            .iter()
+            .filter(|key| backend.touch_key(key))
+            .count();
+        RespFrame::integer(touched as i64)
+    }
+}
+
+impl TryFrom<RespArray> for Touch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["touch"])?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| match key {
+                RespFrame::BulkString(_) => key.try_into(),
+                _ => Err(CommandError::InvalidArgument("invalid key".into())),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Touch { keys })
+    }
+}
+
+impl CommandExecutor for Dump {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.dump(&self.key) {
+            Some(payload) => BulkString::new(payload).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Dump {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dump"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(key @ RespFrame::BulkString(_)) => Ok(Dump {
+                key: key.try_into()?,
+            }),
+            _ => Err(CommandError::InvalidArgument("invalid key".into())),
+        }
+    }
+}
+
+impl CommandExecutor for Restore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if !self.replace && backend.key_type(&self.key).is_some() {
+            return SimpleError::new("BUSYKEY Target key name already exists.").into();
+        }
+        match backend.restore(&self.key, &self.payload, self.ttl) {
+            Ok(()) => RESP_OK.clone(),
+            Err(_) => SimpleError::new("ERR DUMP payload version or checksum are wrong").into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Restore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["restore"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (key, ttl, payload) = match (args.next(), args.next(), args.next()) {
+            (
+                Some(key @ RespFrame::BulkString(_)),
+                Some(ttl),
+                Some(RespFrame::BulkString(payload)),
+            ) => (key.try_into()?, int_arg(ttl)?, payload.to_vec()),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "invalid key or payload".into(),
+                ))
+            }
+        };
+        let ttl = match ttl {
+            0 => None,
+            ttl if ttl > 0 => Some(Duration::from_millis(ttl as u64)),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid TTL value, must be >= 0".into(),
+                ))
+            }
+        };
+        let mut replace = false;
+        for flag in args {
+            match flag {
+                RespFrame::BulkString(ref flag) if flag.eq_ignore_ascii_case(b"replace") => {
+                    replace = true
+                }
+                _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+            }
+        }
+        Ok(Restore {
+            key,
+            ttl,
+            payload,
+            replace,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::Command;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+    use std::time::Duration;
+
+    #[test]
+    fn test_copy_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*4\r\n$4\r\ncopy\r\n$1\r\na\r\n$1\r\nb\r\n$7\r\nREPLACE\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Copy::try_from(frame)?;
+        assert_eq!(cmd.source, "a");
+        assert_eq!(cmd.destination, "b");
+        assert!(cmd.replace);
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\ncopy\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\nfoo\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Copy::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_command() {
+        let backend = Backend::new();
+        let copy = |replace: bool| {
+            Copy {
+                source: "src".into(),
+                destination: "dst".into(),
+                replace,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(copy(false), RespFrame::Integer(0));
+
+        backend.set("src", BulkString::new("hello").into());
+        backend.expire("src", Duration::from_secs(100));
+        assert_eq!(copy(false), RespFrame::Integer(1));
+        assert_eq!(backend.get("dst"), Some(BulkString::new("hello").into()));
+        assert!(backend.ttl("dst").unwrap().is_some());
+
+        // refuses to overwrite by default
+        backend.set("src", BulkString::new("world").into());
+        assert_eq!(copy(false), RespFrame::Integer(0));
+        assert_eq!(backend.get("dst"), Some(BulkString::new("hello").into()));
+
+        // REPLACE overwrites, including the ttl
+        assert_eq!(copy(true), RespFrame::Integer(1));
+        assert_eq!(backend.get("dst"), Some(BulkString::new("world").into()));
+        assert_eq!(backend.ttl("dst"), Some(None));
+
+        // hashes are copied as well, replacing a string destination
+        backend.remove("src");
+        backend.hset("src", "field", BulkString::new("value").into());
+        assert_eq!(copy(true), RespFrame::Integer(1));
+        assert_eq!(
+            backend.hget("dst", "field"),
+            Some(BulkString::new("value").into())
+        );
+        assert_eq!(backend.get("dst"), None);
+    }
+
+    #[test]
+    fn test_touch_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\ntouch\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Touch::try_from(frame)?;
+        assert_eq!(cmd.keys, ["a", "b"]);
+
+        let mut buf = BytesMut::from("*1\r\n$5\r\ntouch\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Command::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_touch_command() {
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        backend.hset("h", "field", BulkString::new("2").into());
+        std::thread::sleep(Duration::from_millis(20));
+
+        let cmd = Touch {
+            keys: vec!["a".into(), "missing".into(), "h".into()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert!(backend.idle_time("a").unwrap() < Duration::from_millis(20));
+        assert!(backend.idle_time("h").unwrap() < Duration::from_millis(20));
+        assert!(backend.idle_time("missing").is_none());
+    }
+
+    #[test]
+    fn test_restore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from(
+            "*5\r\n$7\r\nrestore\r\n$1\r\na\r\n$4\r\n1500\r\n$3\r\nxyz\r\n$7\r\nREPLACE\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Restore::try_from(frame)?;
+        assert_eq!(cmd.key, "a");
+        assert_eq!(cmd.ttl, Some(Duration::from_millis(1500)));
+        assert_eq!(cmd.payload, b"xyz");
+        assert!(cmd.replace);
+
+        let mut buf = BytesMut::from("*4\r\n$7\r\nrestore\r\n$1\r\na\r\n$2\r\n-1\r\n$3\r\nxyz\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Restore::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_restore_command() {
+        let backend = Backend::new();
+        let dump = |key: &str| Dump { key: key.into() }.execute(&backend);
+        assert_eq!(dump("src"), RespFrame::Null(RespNull));
+
+        backend.hset("src", "field", BulkString::new("value").into());
+        let RespFrame::BulkString(payload) = dump("src") else {
+            panic!("expected a bulk string payload");
+        };
+        let restore = |key: &str, payload: &[u8], replace: bool| {
+            Restore {
+                key: key.into(),
+                ttl: None,
+                payload: payload.to_vec(),
+                replace,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(restore("dst", &payload, false), RESP_OK.clone());
+        assert_eq!(
+            backend.hget("dst", "field"),
+            Some(BulkString::new("value").into())
+        );
+
+        // an existing key is only overwritten with REPLACE
+        assert_eq!(
+            restore("dst", &payload, false),
+            SimpleError::new("BUSYKEY Target key name already exists.").into()
+        );
+        assert_eq!(restore("dst", &payload, true), RESP_OK.clone());
+
+        assert_eq!(
+            restore("other", b"garbage", false),
+            SimpleError::new("ERR DUMP payload version or checksum are wrong").into()
+        );
+        assert_eq!(backend.key_type("other"), None);
+    }
+
+    #[test]
+    fn test_flushdb_and_flushall() -> Result<()> {
+        let command = |s: &str| {
+            let args = s.split(' ').map(|arg| BulkString::from(arg).into());
+            Command::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+        };
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        backend.hset("h", "field", BulkString::new("v").into());
+        assert_eq!(command("flushdb")?.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.key_count(), 0);
+
+        backend.set("a", BulkString::new("1").into());
+        assert_eq!(
+            command("FLUSHALL ASYNC")?.execute(&backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(backend.key_count(), 0);
+
+        assert!(command("flushdb lazy").is_err());
+        assert!(command("flushall sync async").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unlink_command() -> Result<()> {
+        let command = |s: &str| {
+            let args = s.split(' ').map(|arg| BulkString::from(arg).into());
+            Command::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+        };
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        backend.set("b", BulkString::new("x".repeat(1024 * 1024)).into());
+        assert_eq!(
+            command("UNLINK a b c")?.execute(&backend),
+            RespFrame::Integer(2)
+        );
+        assert_eq!(backend.key_count(), 0);
+        assert!(command("unlink").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_with_a_single_database() -> Result<()> {
+        let command = |s: &str| {
+            let args = s.split(' ').map(|arg| BulkString::from(arg).into());
+            Command::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+        };
+        let backend = Backend::new();
+        backend.set("a", BulkString::new("1").into());
+        assert_eq!(
+            command("move a 1")?.execute(&backend),
+            SimpleError::new("ERR DB index is out of range").into()
+        );
+        assert_eq!(
+            command("MOVE a 0")?.execute(&backend),
+            SimpleError::new("ERR source and destination objects are the same").into()
+        );
+        assert!(backend.get("a").is_some());
+        assert!(command("move a db").is_err());
+        assert!(command("move a").is_err());
+        Ok(())
+    }
+}
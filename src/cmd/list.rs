@@ -0,0 +1,623 @@
+use std::time::Duration;
+
+use crate::{
+    backend::{Backend, ListEnd},
+    BulkString, RespArray, RespFrame, RespNull, RespNullArray, SimpleError,
+};
+
+use super::{
+    extract_args, int_arg, num::parse_float, parse_key, validate_command, wrongtype, BLPop, BRPop,
+    CommandError, CommandExecutor, LInsert, LPop, LPos, LPush, LRem, LSet, LTrim, RPop, RPush,
+    RESP_OK,
+};
+
+impl CommandExecutor for LPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        push(backend, &self.key, ListEnd::Left, self.values)
+    }
+}
+
+impl CommandExecutor for RPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        push(backend, &self.key, ListEnd::Right, self.values)
+    }
+}
+
+impl CommandExecutor for LPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        pop(backend, &self.key, ListEnd::Left)
+    }
+}
+
+impl CommandExecutor for RPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        pop(backend, &self.key, ListEnd::Right)
+    }
+}
+
+/// Without waiting, e.g. when replaying, connections run it with `execute_async`.
+impl CommandExecutor for BLPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        pop_first(backend, &self.keys, ListEnd::Left)
+    }
+}
+
+/// Without waiting, e.g. when replaying, connections run it with `execute_async`.
+impl CommandExecutor for BRPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        pop_first(backend, &self.keys, ListEnd::Right)
+    }
+}
+
+impl CommandExecutor for LSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_list(backend, &self.key) {
+            return wrongtype();
+        }
+        match backend.lset(&self.key, self.index, self.value) {
+            Some(true) => RESP_OK.clone(),
+            Some(false) => SimpleError::new("ERR index out of range").into(),
+            None => SimpleError::new("ERR no such key").into(),
+        }
+    }
+}
+
+impl CommandExecutor for LInsert {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_list(backend, &self.key) {
+            return wrongtype();
+        }
+        let len = backend.linsert(&self.key, self.before, &self.pivot, self.value);
+        RespFrame::integer(len.unwrap_or(0))
+    }
+}
+
+impl CommandExecutor for LRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_list(backend, &self.key) {
+            return wrongtype();
+        }
+        RespFrame::integer(backend.lrem(&self.key, self.count, &self.value) as i64)
+    }
+}
+
+impl CommandExecutor for LTrim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_list(backend, &self.key) {
+            return wrongtype();
+        }
+        backend.ltrim(&self.key, self.start, self.stop);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for LPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_list(backend, &self.key) {
+            return wrongtype();
+        }
+        let count = self.count.unwrap_or(1);
+        let positions = backend.lpos(&self.key, &self.value, self.rank, count);
+        match self.count {
+            Some(_) => RespArray::new(
+                positions
+                    .into_iter()
+                    .map(|i| RespFrame::integer(i as i64))
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            None => match positions.first() {
+                Some(&i) => RespFrame::integer(i as i64),
+                None => RespFrame::Null(RespNull),
+            },
+        }
+    }
+}
+
+impl BLPop {
+    /// wait for an element to pop without blocking the runtime
+    pub async fn execute_async(self, backend: &Backend) -> RespFrame {
+        blocking_pop(backend, &self.keys, ListEnd::Left, self.timeout).await
+    }
+}
+
+impl BRPop {
+    /// wait for an element to pop without blocking the runtime
+    pub async fn execute_async(self, backend: &Backend) -> RespFrame {
+        blocking_pop(backend, &self.keys, ListEnd::Right, self.timeout).await
+    }
+}
+
+fn push(backend: &Backend, key: &str, end: ListEnd, values: Vec<RespFrame>) -> RespFrame {
+    if is_not_list(backend, key) {
+        return wrongtype();
+    }
+    RespFrame::integer(backend.push(key, end, values) as i64)
+}
+
+fn pop(backend: &Backend, key: &str, end: ListEnd) -> RespFrame {
+    if is_not_list(backend, key) {
+        return wrongtype();
+    }
+    backend.pop(key, end).unwrap_or(RespFrame::Null(RespNull))
+}
+
+fn pop_first(backend: &Backend, keys: &[String], end: ListEnd) -> RespFrame {
+    if keys.iter().any(|key| is_not_list(backend, key)) {
+        return wrongtype();
+    }
+    keys.iter()
+        .find_map(|key| Some((key.clone(), backend.pop(key, end)?)))
+        .map_or(RespFrame::NullArray(RespNullArray), popped_frame)
+}
+
+async fn blocking_pop(
+    backend: &Backend,
+    keys: &[String],
+    end: ListEnd,
+    timeout: Option<Duration>,
+) -> RespFrame {
+    if keys.iter().any(|key| is_not_list(backend, key)) {
+        return wrongtype();
+    }
+    backend
+        .blocking_pop(keys, end, timeout)
+        .await
+        .map_or(RespFrame::NullArray(RespNullArray), popped_frame)
+}
+
+// `[key, element]`, the reply of a blocking pop
+fn popped_frame((key, value): (String, RespFrame)) -> RespFrame {
+    RespArray::new([BulkString::from(key).into(), value]).into()
+}
+
+impl TryFrom<RespArray> for LPush {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lpush"])?;
+        let (key, values) = parse_key_and_values(value)?;
+        Ok(LPush { key, values })
+    }
+}
+
+impl TryFrom<RespArray> for RPush {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["rpush"])?;
+        let (key, values) = parse_key_and_values(value)?;
+        Ok(RPush { key, values })
+    }
+}
+
+impl TryFrom<RespArray> for LPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(LPop {
+            key: parse_key(value, "lpop")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for RPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(RPop {
+            key: parse_key(value, "rpop")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for BLPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["blpop"])?;
+        let (keys, timeout) = parse_keys_and_timeout(value)?;
+        Ok(BLPop { keys, timeout })
+    }
+}
+
+impl TryFrom<RespArray> for BRPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["brpop"])?;
+        let (keys, timeout) = parse_keys_and_timeout(value)?;
+        Ok(BRPop { keys, timeout })
+    }
+}
+
+impl TryFrom<RespArray> for LSet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lset"])?;
+        let (key, index, value) = parse_key_int_and_value(value)?;
+        Ok(LSet { key, index, value })
+    }
+}
+
+impl TryFrom<RespArray> for LInsert {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["linsert"])?;
+        let (key, mut args) = parse_key_and_values(value)?;
+        let (Some(value), Some(pivot), Some(RespFrame::BulkString(position))) =
+            (args.pop(), args.pop(), args.pop())
+        else {
+            return Err(CommandError::InvalidArgument("syntax error".into()));
+        };
+        let before = match position.to_ascii_lowercase().as_slice() {
+            b"before" => true,
+            b"after" => false,
+            _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+        };
+        Ok(LInsert {
+            key,
+            before,
+            pivot,
+            value,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LRem {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lrem"])?;
+        let (key, count, value) = parse_key_int_and_value(value)?;
+        Ok(LRem { key, count, value })
+    }
+}
+
+impl TryFrom<RespArray> for LPos {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lpos"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut lpos = match (args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(value @ RespFrame::BulkString(_))) => {
+                LPos {
+                    key: key.try_into()?,
+                    value,
+                    rank: 1,
+                    count: None,
+                }
+            }
+            _ => return Err(CommandError::InvalidArgument("invalid key or value".into())),
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".into());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(arg) = arg else {
+                return Err(syntax_error());
+            };
+            let value = args.next().ok_or_else(syntax_error)?;
+            match arg.to_ascii_lowercase().as_slice() {
+                b"rank" => {
+                    lpos.rank = match int_arg(value)? {
+                        0 => {
+                            return Err(CommandError::InvalidArgument(
+                                "RANK can't be zero: use 1 to start from the first match, 2 \
+                                 from the second ... or use negative to start from the end of \
+                                 the list"
+                                    .into(),
+                            ))
+                        }
+                        i64::MIN => {
+                            return Err(CommandError::InvalidArgument(
+                                "value is out of range".into(),
+                            ))
+                        }
+                        rank => rank,
+                    }
+                }
+                b"count" => {
+                    let count = usize::try_from(int_arg(value)?).map_err(|_| {
+                        CommandError::InvalidArgument("COUNT can't be negative".into())
+                    })?;
+                    lpos.count = Some(count);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(lpos)
+    }
+}
+
+impl TryFrom<RespArray> for LTrim {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ltrim"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(start), Some(stop)) => Ok(LTrim {
+                key: key.try_into()?,
+                start: int_arg(start)?,
+                stop: int_arg(stop)?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid key, start or stop".into(),
+            )),
+        }
+    }
+}
+
+// the key of the list, an integer like an index or count, and a value
+fn parse_key_int_and_value(value: RespArray) -> Result<(String, i64, RespFrame), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(key @ RespFrame::BulkString(_)), Some(n), Some(value @ RespFrame::BulkString(_))) => {
+            Ok((key.try_into()?, int_arg(n)?, value))
+        }
+        _ => Err(CommandError::InvalidArgument("invalid key or value".into())),
+    }
+}
+
+// the key of the list and the values to push, which must be bulk strings
+fn parse_key_and_values(value: RespArray) -> Result<(String, Vec<RespFrame>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(key @ RespFrame::BulkString(_)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("invalid key".into())),
+    };
+    let values = args
+        .map(|arg| match arg {
+            RespFrame::BulkString(_) => Ok(arg),
+            _ => Err(CommandError::InvalidArgument("invalid value".into())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((key, values))
+}
+
+// the keys to pop from and the timeout in seconds, the last argument; `0` waits forever
+fn parse_keys_and_timeout(
+    value: RespArray,
+) -> Result<(Vec<String>, Option<Duration>), CommandError> {
+    let mut args = extract_args(value, 1)?;
+    let timeout = match args.pop() {
+        Some(RespFrame::BulkString(timeout)) => parse_float(&timeout).map_err(|_| {
+            CommandError::InvalidArgument("timeout is not a float or out of range".into())
+        })?,
+        _ => {
+            return Err(CommandError::InvalidArgument(
+                "timeout is not a float or out of range".into(),
+            ))
+        }
+    };
+    if timeout < 0.0 {
+        return Err(CommandError::InvalidArgument("timeout is negative".into()));
+    }
+    let timeout = match timeout {
+        0.0 => None,
+        timeout => Some(
+            Duration::try_from_secs_f64(timeout)
+                .map_err(|_| CommandError::InvalidArgument("timeout is out of range".into()))?,
+        ),
+    };
+    let keys = args
+        .into_iter()
+        .map(|key| match key {
+            RespFrame::BulkString(_) => key.try_into(),
+            _ => Err(CommandError::InvalidArgument("invalid key".into())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((keys, timeout))
+}
+
+// the key holds a value of another type than list
+fn is_not_list(backend: &Backend, key: &str) -> bool {
+    backend.key_type(key).is_some_and(|t| t != "list")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cmd::Command, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    fn command(args: &[&str]) -> Result<Command, CommandError> {
+        Command::try_from(RespArray::new(
+            args.iter().map(|arg| bulk(arg)).collect::<Vec<_>>(),
+        ))
+    }
+
+    #[test]
+    fn test_blpop_try_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*4\r\n$5\r\nblpop\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\n0.5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = BLPop::try_from(frame)?;
+        assert_eq!(cmd.keys, ["a", "b"]);
+        assert_eq!(cmd.timeout, Some(Duration::from_millis(500)));
+
+        let Command::BRPop(cmd) = command(&["brpop", "a", "0"])? else {
+            panic!("expected BRPOP");
+        };
+        assert_eq!(cmd.timeout, None);
+
+        let err = command(&["blpop", "a", "-1"]).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid arguments: timeout is negative");
+        let err = command(&["blpop", "a", "soon"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid arguments: timeout is not a float or out of range"
+        );
+        assert!(command(&["blpop", "a"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_and_pop() -> Result<()> {
+        let backend = Backend::new();
+        let ret = command(&["rpush", "l", "b", "c"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(2));
+        let ret = command(&["lpush", "l", "a"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(3));
+
+        assert_eq!(command(&["lpop", "l"])?.execute(&backend), bulk("a"));
+        assert_eq!(command(&["rpop", "l"])?.execute(&backend), bulk("c"));
+        assert_eq!(command(&["rpop", "l"])?.execute(&backend), bulk("b"));
+        assert_eq!(
+            command(&["lpop", "l"])?.execute(&backend),
+            RespFrame::Null(RespNull)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_commands_on_wrong_type() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("s", bulk("v"));
+        for args in [
+            &["lpush", "s", "a"][..],
+            &["rpush", "s", "a"],
+            &["lpop", "s"],
+            &["rpop", "s"],
+            &["blpop", "s", "1"],
+            &["lset", "s", "0", "a"],
+            &["linsert", "s", "before", "a", "b"],
+            &["lrem", "s", "0", "a"],
+            &["ltrim", "s", "0", "1"],
+            &["lpos", "s", "a"],
+        ] {
+            assert_eq!(command(args)?.execute(&backend), wrongtype());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lset_linsert_and_lrem() -> Result<()> {
+        let backend = Backend::new();
+        command(&["rpush", "l", "a", "b", "a"])?.execute(&backend);
+
+        assert_eq!(
+            command(&["lset", "l", "-1", "c"])?.execute(&backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(
+            command(&["lset", "l", "3", "c"])?.execute(&backend),
+            SimpleError::new("ERR index out of range").into()
+        );
+        assert_eq!(
+            command(&["lset", "missing", "0", "c"])?.execute(&backend),
+            SimpleError::new("ERR no such key").into()
+        );
+        assert!(command(&["lset", "l", "one", "c"]).is_err());
+
+        let ret = command(&["linsert", "l", "AFTER", "b", "x"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(4));
+        let ret = command(&["linsert", "l", "before", "nope", "x"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(-1));
+        let ret = command(&["linsert", "missing", "before", "a", "x"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(0));
+        assert!(command(&["linsert", "l", "around", "a", "x"]).is_err());
+
+        // a b x c
+        command(&["rpush", "l", "b"])?.execute(&backend);
+        let ret = command(&["lrem", "l", "-1", "b"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(1));
+        assert_eq!(command(&["rpop", "l"])?.execute(&backend), bulk("c"));
+        let ret = command(&["lrem", "l", "0", "b"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(1));
+        assert_eq!(command(&["lpop", "l"])?.execute(&backend), bulk("a"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ltrim() -> Result<()> {
+        let backend = Backend::new();
+        command(&["rpush", "log", "1", "2", "3", "4"])?.execute(&backend);
+        let ret = command(&["ltrim", "log", "-2", "-1"])?.execute(&backend);
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(command(&["lpop", "log"])?.execute(&backend), bulk("3"));
+
+        let ret = command(&["ltrim", "log", "5", "10"])?.execute(&backend);
+        assert_eq!(ret, RESP_OK.clone());
+        assert_eq!(backend.key_type("log"), None);
+        assert!(command(&["ltrim", "log", "0"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lpos() -> Result<()> {
+        let backend = Backend::new();
+        command(&["rpush", "tasks", "a", "b", "c", "b", "b"])?.execute(&backend);
+        let positions = |positions: &[i64]| -> RespFrame {
+            RespArray::new(
+                positions
+                    .iter()
+                    .map(|&i| RespFrame::Integer(i))
+                    .collect::<Vec<_>>(),
+            )
+            .into()
+        };
+
+        let ret = command(&["lpos", "tasks", "b"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(1));
+        let ret = command(&["lpos", "tasks", "b", "RANK", "2"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(3));
+        let ret = command(&["lpos", "tasks", "x"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Null(RespNull));
+
+        let ret = command(&["lpos", "tasks", "b", "COUNT", "2"])?.execute(&backend);
+        assert_eq!(ret, positions(&[1, 3]));
+        let ret = command(&["lpos", "tasks", "b", "count", "0"])?.execute(&backend);
+        assert_eq!(ret, positions(&[1, 3, 4]));
+        let ret = command(&["lpos", "tasks", "x", "count", "0"])?.execute(&backend);
+        assert_eq!(ret, positions(&[]));
+
+        // from the tail
+        let ret = command(&["lpos", "tasks", "b", "rank", "-1"])?.execute(&backend);
+        assert_eq!(ret, RespFrame::Integer(4));
+        let ret = command(&["lpos", "tasks", "b", "rank", "-2", "count", "5"])?.execute(&backend);
+        assert_eq!(ret, positions(&[3, 1]));
+
+        assert!(command(&["lpos", "tasks", "b", "rank", "0"]).is_err());
+        assert!(command(&["lpos", "tasks", "b", "count", "-1"]).is_err());
+        assert!(command(&["lpos", "tasks", "b", "count"]).is_err());
+        assert!(command(&["lpos", "tasks", "b", "maxlen", "1"]).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blpop_is_woken_by_push() -> Result<()> {
+        let backend = Backend::new();
+        let Command::BLPop(cmd) = command(&["blpop", "a", "b", "0"])? else {
+            panic!("expected BLPOP");
+        };
+        let waiter = {
+            let backend = backend.clone();
+            tokio::spawn(async move { cmd.execute_async(&backend).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        command(&["rpush", "b", "x", "y"])?.execute(&backend);
+
+        let ret = waiter.await?;
+        assert_eq!(ret, RespArray::new([bulk("b"), bulk("x")]).into());
+        assert_eq!(command(&["lpop", "b"])?.execute(&backend), bulk("y"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_brpop_times_out() -> Result<()> {
+        let backend = Backend::new();
+        let Command::BRPop(cmd) = command(&["brpop", "a", "0.01"])? else {
+            panic!("expected BRPOP");
+        };
+        let ret = cmd.execute_async(&backend).await;
+        assert_eq!(ret, RespFrame::NullArray(RespNullArray));
+        Ok(())
+    }
+}
@@ -1,11 +1,23 @@
-use crate::{RespArray, RespFrame, RespNull};
+use std::time::Duration;
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, Get, Set, RESP_OK};
+use crate::{
+    backend::{string_bytes, Backend, SetCondition, SetFlags},
+    BulkString, RespArray, RespFrame, RespNull, SimpleError,
+};
+
+use super::{
+    expire::unix_millis,
+    extract_args, index_range, int_arg,
+    num::{format_float, parse_float, parse_int},
+    parse_key, validate_command, wrongtype, CommandError, CommandExecutor, Decr, DecrBy, Get,
+    GetEx, GetRange, Incr, IncrBy, IncrByFloat, MSet, Set, SetExpiry, SetRange, RESP_OK,
+};
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
         match backend.get(&self.key) {
             Some(value) => value,
+            None if backend.key_type(&self.key).is_some() => wrongtype(),
             None => RespFrame::Null(RespNull),
         }
     }
@@ -13,40 +25,419 @@ impl CommandExecutor for Get {
 
 impl CommandExecutor for Set {
     fn execute(self, backend: &crate::backend::Backend) -> RespFrame {
-        backend.set(&self.key, self.value);
+        if self.get && backend.key_type(&self.key).is_some_and(|t| t != "string") {
+            return wrongtype();
+        }
+        let ttl = self.expiry.map(|expiry| {
+            let millis = expiry.millis_from(unix_millis());
+            Duration::from_millis(millis.max(0) as u64)
+        });
+        let flags = SetFlags {
+            condition: self.condition,
+            ttl,
+            get: self.get,
+        };
+        match backend.set_with_flags(&self.key, self.value, flags) {
+            (_, Some(old)) => old,
+            (written, None) if written && !self.get => RESP_OK.clone(),
+            _ => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for MSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        for (key, value) in self.pairs {
+            backend.set(&key, value);
+        }
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for GetEx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(value) = backend.get(&self.key) else {
+            if backend.key_type(&self.key).is_some() {
+                return wrongtype();
+            }
+            return RespFrame::Null(RespNull);
+        };
+        if let Some(expiry) = self.expiry {
+            let millis = expiry.millis_from(unix_millis());
+            if millis > 0 {
+                backend.expire(&self.key, Duration::from_millis(millis as u64));
+            } else {
+                backend.remove(&self.key);
+            }
+        } else if self.persist {
+            backend.persist(&self.key);
+        }
+        value
+    }
+}
+
+impl Set {
+    // the command with an expiry relative to now rewritten as `PXAT`, for the aof
+    pub(super) fn aof_frame(&self, now: i64) -> RespArray {
+        let mut args = vec![
+            BulkString::from("set").into(),
+            BulkString::from(self.key.as_str()).into(),
+            self.value.clone(),
+        ];
+        match self.condition {
+            Some(SetCondition::IfAbsent) => args.push(BulkString::from("nx").into()),
+            Some(SetCondition::IfPresent) => args.push(BulkString::from("xx").into()),
+            None => {}
+        }
+        if let Some(expiry) = self.expiry {
+            let timestamp = expiry.unix_millis(now);
+            args.push(BulkString::from("pxat").into());
+            args.push(BulkString::from(timestamp.to_string()).into());
+        }
+        RespArray::new(args)
+    }
+}
+
+impl CommandExecutor for GetRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let data = match backend.get(&self.key) {
+            Some(value) => string_bytes(&value),
+            None if backend.key_type(&self.key).is_some() => return wrongtype(),
+            None => vec![],
+        };
+        let range = index_range(data.len(), self.start, self.end);
+        BulkString::new(data[range].to_vec()).into()
+    }
+}
+
+impl CommandExecutor for SetRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "string") {
+            return wrongtype();
+        }
+        if (self.offset + self.value.len()) as u64 > backend.proto_max_bulk_len() {
+            return SimpleError::new(
+                "ERR string exceeds maximum allowed size (proto-max-bulk-len)",
+            )
+            .into();
+        }
+        RespFrame::integer(backend.setrange(&self.key, self.offset, &self.value) as i64)
+    }
+}
+
+impl CommandExecutor for Incr {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, &self.key, 1)
+    }
+}
+
+impl CommandExecutor for Decr {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, &self.key, -1)
+    }
+}
+
+impl CommandExecutor for IncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, &self.key, self.increment)
+    }
+}
+
+impl CommandExecutor for DecrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.decrement.checked_neg() {
+            Some(delta) => incr_by(backend, &self.key, delta),
+            None => SimpleError::new("ERR decrement would overflow").into(),
+        }
+    }
+}
+
+impl CommandExecutor for IncrByFloat {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if backend.key_type(&self.key).is_some_and(|t| t != "string") {
+            return wrongtype();
+        }
+        let ret = backend.update(&self.key, |value| -> Result<_, RespFrame> {
+            let current = match value {
+                Some(value) => parse_float(&string_bytes(value))?,
+                None => 0.0,
+            };
+            let new = current + self.increment;
+            if !new.is_finite() {
+                return Err(SimpleError::new("ERR increment would produce NaN or Infinity").into());
+            }
+            let new = BulkString::new(format_float(new));
+            Ok((new.clone().into(), new))
+        });
+        ret.map_or_else(|e| e, RespFrame::from)
+    }
+}
+
+// the shared path of INCR, DECR, INCRBY and DECRBY
+fn incr_by(backend: &Backend, key: &str, delta: i64) -> RespFrame {
+    if backend.key_type(key).is_some_and(|t| t != "string") {
+        return wrongtype();
+    }
+    let ret = backend.update(key, |value| -> Result<_, RespFrame> {
+        let current = match value {
+            Some(value) => parse_int(&string_bytes(value))?,
+            None => 0,
+        };
+        let new = current
+            .checked_add(delta)
+            .ok_or_else(|| SimpleError::new("ERR increment or decrement would overflow"))?;
+        Ok((BulkString::new(new.to_string()).into(), new))
+    });
+    ret.map_or_else(|e| e, RespFrame::Integer)
+}
+
 impl TryFrom<RespArray> for Get {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["get"], 1)?;
+        validate_command(&value, &["get"])?;
         let args = extract_args(value, 1)?;
 
-        match args[0] {
-            RespFrame::BulkString(ref key) => Ok(Get {
-                key: String::from_utf8(key.as_slice().to_vec())?,
+        match args.into_iter().next() {
+            Some(key @ RespFrame::BulkString(_)) => Ok(Get {
+                key: key.try_into()?,
             }),
             _ => Err(CommandError::InvalidArgument("Invalid key".into())),
         }
     }
 }
 
+impl TryFrom<RespArray> for MSet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["mset"])?;
+        let args = extract_args(value, 1)?;
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'mset' command".into(),
+            ));
+        }
+        let mut args = args.into_iter();
+        let mut pairs = Vec::new();
+        while let (Some(key), Some(value)) = (args.next(), args.next()) {
+            let RespFrame::BulkString(_) = key else {
+                return Err(CommandError::InvalidArgument("invalid key".into()));
+            };
+            pairs.push((key.try_into()?, value));
+        }
+        Ok(MSet { pairs })
+    }
+}
+
 impl TryFrom<RespArray> for Set {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        validate_command(&value, &["set"])?;
         let mut args = extract_args(value, 1)?.into_iter();
 
-        match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(Set {
-                key: String::from_utf8(key.to_vec())?,
-                value,
+        let (key, value) = match (args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(value)) => (key.try_into()?, value),
+            _ => return Err(CommandError::InvalidArgument("Invalid key or value".into())),
+        };
+        let mut set = Set {
+            key,
+            value,
+            condition: None,
+            expiry: None,
+            get: false,
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".into());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(arg) = arg else {
+                return Err(syntax_error());
+            };
+            match arg.to_ascii_lowercase().as_slice() {
+                b"nx" | b"xx" if set.condition.is_some() => return Err(syntax_error()),
+                b"nx" => set.condition = Some(SetCondition::IfAbsent),
+                b"xx" => set.condition = Some(SetCondition::IfPresent),
+                b"get" => set.get = true,
+                unit @ (b"ex" | b"px" | b"exat" | b"pxat") => {
+                    if set.expiry.is_some() {
+                        return Err(syntax_error());
+                    }
+                    set.expiry = Some(parse_expiry(unit, args.next(), "set")?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl TryFrom<RespArray> for GetEx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getex"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut getex = match args.next() {
+            Some(key @ RespFrame::BulkString(_)) => GetEx {
+                key: key.try_into()?,
+                expiry: None,
+                persist: false,
+            },
+            _ => return Err(CommandError::InvalidArgument("Invalid key".into())),
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".into());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(arg) = arg else {
+                return Err(syntax_error());
+            };
+            // at most one of the options
+            if getex.expiry.is_some() || getex.persist {
+                return Err(syntax_error());
+            }
+            match arg.to_ascii_lowercase().as_slice() {
+                b"persist" => getex.persist = true,
+                unit @ (b"ex" | b"px" | b"exat" | b"pxat") => {
+                    getex.expiry = Some(parse_expiry(unit, args.next(), "getex")?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(getex)
+    }
+}
+
+// the argument of an EX, PX, EXAT or PXAT option, which must be positive
+fn parse_expiry(
+    unit: &[u8],
+    arg: Option<RespFrame>,
+    name: &str,
+) -> Result<SetExpiry, CommandError> {
+    let n = arg
+        .ok_or_else(|| CommandError::InvalidArgument("syntax error".into()))
+        .and_then(int_arg)?;
+    let scale = if unit.starts_with(b"e") { 1000 } else { 1 };
+    let millis = n
+        .checked_mul(scale)
+        .filter(|millis| *millis > 0)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(format!("invalid expire time in '{name}' command"))
+        })?;
+    Ok(if unit.ends_with(b"at") {
+        SetExpiry::At(millis)
+    } else {
+        SetExpiry::After(millis)
+    })
+}
+
+impl TryFrom<RespArray> for Incr {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Incr {
+            key: parse_key(value, "incr")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Decr {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Decr {
+            key: parse_key(value, "decr")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for IncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, increment) = parse_key_and_arg(value, "incrby")?;
+        Ok(IncrBy {
+            key,
+            increment: int_arg(increment)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for DecrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, decrement) = parse_key_and_arg(value, "decrby")?;
+        Ok(DecrBy {
+            key,
+            decrement: int_arg(decrement)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for IncrByFloat {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, increment) = parse_key_and_arg(value, "incrbyfloat")?;
+        let increment = match increment {
+            RespFrame::BulkString(s) => parse_float(&s)?,
+            _ => return Err(CommandError::NotAFloat),
+        };
+        Ok(IncrByFloat { key, increment })
+    }
+}
+
+fn parse_key_and_arg(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, RespFrame), CommandError> {
+    validate_command(&value, &[name])?;
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next()) {
+        (Some(key @ RespFrame::BulkString(_)), Some(arg)) => Ok((key.try_into()?, arg)),
+        _ => Err(CommandError::InvalidArgument("invalid key or value".into())),
+    }
+}
+
+impl TryFrom<RespArray> for GetRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getrange"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (Some(key @ RespFrame::BulkString(_)), Some(start), Some(end)) => Ok(GetRange {
+                key: key.try_into()?,
+                start: int_arg(start)?,
+                end: int_arg(end)?,
             }),
-            _ => Err(CommandError::InvalidArgument("Invalid key or value".into())),
+            _ => Err(CommandError::InvalidArgument("invalid key or range".into())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SetRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setrange"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(key @ RespFrame::BulkString(_)),
+                Some(offset),
+                Some(RespFrame::BulkString(value)),
+            ) => {
+                let offset = usize::try_from(int_arg(offset)?)
+                    .map_err(|_| CommandError::InvalidArgument("offset is out of range".into()))?;
+                Ok(SetRange {
+                    key: key.try_into()?,
+                    offset,
+                    value: value.to_vec(),
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "invalid key, offset or value".into(),
+            )),
         }
     }
 }
@@ -55,7 +446,7 @@ impl TryFrom<RespArray> for Set {
 mod tests {
     use bytes::BytesMut;
 
-    use crate::{BulkString, RespDecode};
+    use crate::{BulkString, RespDecode, RespEncode};
     use anyhow::Result;
 
     use super::*;
@@ -80,12 +471,183 @@ mod tests {
         Ok(())
     }
 
+    fn parse_set(args: &str) -> Result<Set, CommandError> {
+        let args = args
+            .split(' ')
+            .map(|arg| BulkString::from(arg).into())
+            .collect::<Vec<RespFrame>>();
+        Set::try_from(RespArray::new(args))
+    }
+
+    #[test]
+    fn test_set_flags_try_from_resp_array() -> Result<()> {
+        let cmd = parse_set("set k v")?;
+        assert_eq!((cmd.condition, cmd.expiry, cmd.get), (None, None, false));
+
+        let cmd = parse_set("SET k v nx EX 10 GET")?;
+        assert_eq!(cmd.condition, Some(SetCondition::IfAbsent));
+        assert_eq!(cmd.expiry, Some(SetExpiry::After(10_000)));
+        assert!(cmd.get);
+
+        let cmd = parse_set("set k v px 1500 xx")?;
+        assert_eq!(cmd.condition, Some(SetCondition::IfPresent));
+        assert_eq!(cmd.expiry, Some(SetExpiry::After(1500)));
+
+        let cmd = parse_set("set k v pxat 1700000000000")?;
+        assert_eq!(cmd.expiry, Some(SetExpiry::At(1_700_000_000_000)));
+
+        for args in [
+            "set k v nx xx",
+            "set k v ex 10 px 100",
+            "set k v ex",
+            "set k v ex 0",
+            "set k v px -1",
+            "set k v ex abc",
+            "set k v ex 9223372036854775807",
+            "set k v keepall",
+        ] {
+            assert!(parse_set(args).is_err(), "{args}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_flags_command() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        let world: RespFrame = BulkString::new("world").into();
+        let null = RespFrame::Null(RespNull);
+
+        assert_eq!(parse_set("set hello v xx")?.execute(&backend), null);
+        assert_eq!(backend.get("hello"), None);
+        assert_eq!(
+            parse_set("set hello world nx")?.execute(&backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(parse_set("set hello v nx")?.execute(&backend), null);
+        assert_eq!(parse_set("set hello v nx get")?.execute(&backend), world);
+        assert_eq!(backend.get("hello"), Some(world.clone()));
+
+        assert_eq!(
+            parse_set("set hello v2 get ex 100")?.execute(&backend),
+            world
+        );
+        let ttl = backend.ttl("hello").flatten().unwrap();
+        assert!(ttl > Duration::from_secs(99) && ttl <= Duration::from_secs(100));
+        assert_eq!(parse_set("set new v get")?.execute(&backend), null);
+
+        backend.hset("hash", "field", world.clone());
+        assert_eq!(parse_set("set hash v get")?.execute(&backend), wrongtype());
+        assert_eq!(backend.key_type("hash"), Some("hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mset_command() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        let parse_mset = |args: &str| {
+            let args = args.split(' ').map(|arg| BulkString::from(arg).into());
+            MSet::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+        };
+        backend.hset("b", "field", BulkString::new("v").into());
+        assert_eq!(
+            parse_mset("mset a 1 b 2 a 3")?.execute(&backend),
+            RESP_OK.clone()
+        );
+        assert_eq!(backend.get("a"), Some(BulkString::new("3").into()));
+        assert_eq!(backend.get("b"), Some(BulkString::new("2").into()));
+
+        assert!(parse_mset("mset a").is_err());
+        assert!(parse_mset("mset a 1 b").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_aof_frame_uses_absolute_expiry() -> Result<()> {
+        let cmd = parse_set("set k v nx ex 10")?;
+        assert_eq!(
+            cmd.aof_frame(1_000).encode(),
+            b"*6\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nnx\r\n$4\r\npxat\r\n$5\r\n11000\r\n"
+        );
+        let cmd = parse_set(&format!("set k v pxat {}", unix_millis() - 1))?;
+        let backend = crate::backend::Backend::new();
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.get("k"), None);
+        Ok(())
+    }
+
+    fn parse_getex(args: &str) -> Result<GetEx, CommandError> {
+        let args = args
+            .split(' ')
+            .map(|arg| BulkString::from(arg).into())
+            .collect::<Vec<RespFrame>>();
+        GetEx::try_from(RespArray::new(args))
+    }
+
+    #[test]
+    fn test_getex_try_from_resp_array() -> Result<()> {
+        let cmd = parse_getex("getex k")?;
+        assert_eq!((cmd.expiry, cmd.persist), (None, false));
+        let cmd = parse_getex("GETEX k EX 5")?;
+        assert_eq!(cmd.expiry, Some(SetExpiry::After(5000)));
+        let cmd = parse_getex("getex k persist")?;
+        assert!(cmd.persist);
+
+        for args in [
+            "getex k ex 5 px 10",
+            "getex k persist ex 5",
+            "getex k persist persist",
+            "getex k ex 0",
+            "getex k ex",
+            "getex k nx",
+        ] {
+            assert!(parse_getex(args).is_err(), "{args}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_getex_command() -> Result<()> {
+        let backend = crate::backend::Backend::new();
+        let world: RespFrame = BulkString::new("world").into();
+        assert_eq!(
+            parse_getex("getex hello ex 10")?.execute(&backend),
+            RespFrame::Null(RespNull)
+        );
+        assert_eq!(backend.key_type("hello"), None);
+
+        backend.set("hello", world.clone());
+        assert_eq!(parse_getex("getex hello px 5000")?.execute(&backend), world);
+        let ttl = backend.ttl("hello").flatten().unwrap();
+        assert!(ttl > Duration::from_secs(4) && ttl <= Duration::from_secs(5));
+
+        // without option the time to live is kept
+        assert_eq!(parse_getex("getex hello")?.execute(&backend), world);
+        assert!(backend.ttl("hello").flatten().is_some());
+
+        assert_eq!(parse_getex("getex hello persist")?.execute(&backend), world);
+        assert_eq!(backend.ttl("hello"), Some(None));
+
+        let past = format!("getex hello pxat {}", unix_millis() - 1);
+        assert_eq!(parse_getex(&past)?.execute(&backend), world);
+        assert_eq!(backend.get("hello"), None);
+
+        backend.hset("hash", "field", world);
+        assert_eq!(
+            parse_getex("getex hash persist")?.execute(&backend),
+            wrongtype()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_set_get_command() -> Result<()> {
         let backend = crate::backend::Backend::new();
         let cmd = Set {
             key: "hello".into(),
             value: BulkString::new("world").into(),
+            condition: None,
+            expiry: None,
+            get: false,
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -98,4 +660,188 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_wrongtype() {
+        let backend = crate::backend::Backend::new();
+        backend.hset("hash", "field", BulkString::new("value").into());
+        let cmd = Get { key: "hash".into() };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, wrongtype());
+        assert_eq!(
+            result.encode(),
+            b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    }
+
+    #[test]
+    fn test_getrange_command() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$8\r\ngetrange\r\n$3\r\nkey\r\n$1\r\n0\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = GetRange::try_from(frame)?;
+        assert_eq!((cmd.start, cmd.end), (0, -1));
+
+        let backend = Backend::new();
+        backend.set("key", BulkString::new("This is a string").into());
+        let getrange = |start, end| {
+            GetRange {
+                key: "key".into(),
+                start,
+                end,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(getrange(0, 3), BulkString::new("This").into());
+        assert_eq!(getrange(-3, -1), BulkString::new("ing").into());
+        assert_eq!(getrange(0, -1), BulkString::new("This is a string").into());
+        assert_eq!(getrange(10, 100), BulkString::new("string").into());
+        assert_eq!(getrange(-100, 1), BulkString::new("Th").into());
+        assert_eq!(getrange(5, 2), BulkString::new("").into());
+        assert_eq!(getrange(-1, -100), BulkString::new("").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_setrange_command() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$8\r\nsetrange\r\n$3\r\nkey\r\n$2\r\n-1\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(SetRange::try_from(frame).is_err());
+
+        let backend = Backend::new();
+        backend.set("key", BulkString::new("Hello World").into());
+        let cmd = SetRange {
+            key: "key".into(),
+            offset: 6,
+            value: b"Redis".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new("Hello Redis").into())
+        );
+
+        // the gap up to the offset is zero-padded
+        let cmd = SetRange {
+            key: "padded".into(),
+            offset: 3,
+            value: b"ab".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(5));
+        assert_eq!(
+            backend.get("padded"),
+            Some(BulkString::new(b"\0\0\0ab".to_vec()).into())
+        );
+
+        backend.hset("hash", "field", BulkString::new("value").into());
+        let cmd = SetRange {
+            key: "hash".into(),
+            offset: 0,
+            value: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), wrongtype());
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_commands() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nincrby\r\n$3\r\nkey\r\n$1\r\nx\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(IncrBy::try_from(frame).is_err());
+
+        let backend = Backend::new();
+        let key = || "counter".to_string();
+        assert_eq!(Incr { key: key() }.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(
+            IncrBy {
+                key: key(),
+                increment: 10
+            }
+            .execute(&backend),
+            RespFrame::Integer(11)
+        );
+        assert_eq!(
+            DecrBy {
+                key: key(),
+                decrement: 20
+            }
+            .execute(&backend),
+            RespFrame::Integer(-9)
+        );
+        assert_eq!(
+            Decr { key: key() }.execute(&backend),
+            RespFrame::Integer(-10)
+        );
+        assert_eq!(backend.get("counter"), Some(BulkString::new("-10").into()));
+
+        backend.set("text", BulkString::new("abc").into());
+        assert_eq!(
+            Incr { key: "text".into() }.execute(&backend),
+            SimpleError::new("ERR value is not an integer or out of range").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_overflow() {
+        let backend = Backend::new();
+        backend.set("max", BulkString::new(i64::MAX.to_string()).into());
+        assert_eq!(
+            Incr { key: "max".into() }.execute(&backend),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+        // the value is left untouched
+        assert_eq!(
+            backend.get("max"),
+            Some(BulkString::new(i64::MAX.to_string()).into())
+        );
+
+        backend.set("min", BulkString::new(i64::MIN.to_string()).into());
+        assert_eq!(
+            DecrBy {
+                key: "min".into(),
+                decrement: 1
+            }
+            .execute(&backend),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+        assert_eq!(
+            DecrBy {
+                key: "zero".into(),
+                decrement: i64::MIN
+            }
+            .execute(&backend),
+            SimpleError::new("ERR decrement would overflow").into()
+        );
+    }
+
+    #[test]
+    fn test_incrbyfloat_command() {
+        let backend = Backend::new();
+        backend.set("key", BulkString::new("10.50").into());
+        let cmd = IncrByFloat {
+            key: "key".into(),
+            increment: 0.1,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::new("10.6").into());
+
+        backend.set("key", BulkString::new("5.0e3").into());
+        let cmd = IncrByFloat {
+            key: "key".into(),
+            increment: 2.0e2,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::new("5200").into());
+        assert_eq!(backend.get("key"), Some(BulkString::new("5200").into()));
+
+        backend.set("text", BulkString::new("abc").into());
+        let cmd = IncrByFloat {
+            key: "text".into(),
+            increment: 1.0,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            SimpleError::new("ERR value is not a valid float").into()
+        );
+    }
 }
@@ -1,18 +1,896 @@
+mod bitops;
+mod command;
+mod connection;
+#[cfg(debug_assertions)]
+mod debug;
+mod expire;
+mod glob;
 mod hmap;
+mod info;
+mod keys;
+mod list;
 mod map;
+mod num;
+mod object;
+mod pubsub;
+mod server;
+mod set;
+mod sort;
+mod transaction;
+mod zset;
 use std::string::FromUtf8Error;
 
 use anyhow::Result;
 use enum_dispatch::enum_dispatch;
 
-use crate::{backend::Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{
+    backend::{Backend, SetCondition},
+    BulkString, RespArray, RespError, RespFrame, SimpleError,
+};
 use lazy_static::lazy_static;
 use thiserror::Error;
 
+pub use connection::ConnectionState;
+pub(crate) use glob::glob_match;
+
 lazy_static! {
-    static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
+    static ref RESP_OK: RespFrame = RespFrame::ok();
+}
+
+/// Static metadata describing a supported command, following the layout of
+/// the redis `COMMAND` reply.
+#[derive(Debug)]
+pub struct CommandMeta {
+    pub name: &'static str,
+    /// one line description, as shown by `COMMAND DOCS`
+    pub summary: &'static str,
+    /// the redis version that introduced the command
+    pub since: &'static str,
+    /// number of arguments including the command name, negative means "at least"
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    /// position of the first key argument, 0 for commands without keys
+    pub first_key: i64,
+    /// position of the last key argument, negative counts from the end
+    pub last_key: i64,
+    /// distance between key arguments
+    pub step: i64,
 }
 
+pub static COMMAND_TABLE: &[CommandMeta] = &[
+    CommandMeta {
+        name: "get",
+        summary: "Returns the string value of a key.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "set",
+        summary: "Sets the string value of a key, ignoring its type.",
+        since: "1.0.0",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "hget",
+        summary: "Returns the value of a field in a hash.",
+        since: "2.0.0",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "hset",
+        summary: "Creates or modifies the value of a field in a hash.",
+        since: "2.0.0",
+        arity: 4,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "hgetall",
+        summary: "Returns all fields and values in a hash.",
+        since: "2.0.0",
+        arity: 2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "copy",
+        summary: "Copies the value of a key to a new key.",
+        since: "6.2.0",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 2,
+        step: 1,
+    },
+    CommandMeta {
+        name: "expire",
+        summary: "Sets the expiration time of a key in seconds.",
+        since: "1.0.0",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "expireat",
+        summary: "Sets the expiration time of a key to a Unix timestamp.",
+        since: "1.2.0",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "pexpire",
+        summary: "Sets the expiration time of a key in milliseconds.",
+        since: "2.6.0",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "persist",
+        summary: "Removes the expiration time of a key.",
+        since: "2.2.0",
+        arity: 2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "ttl",
+        summary: "Returns the expiration time in seconds of a key.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "pttl",
+        summary: "Returns the expiration time in milliseconds of a key.",
+        since: "2.6.0",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "command",
+        summary: "Returns detailed information about all commands.",
+        since: "2.8.13",
+        arity: -1,
+        flags: &["loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "hello",
+        summary: "Handshakes with the Redis server.",
+        since: "6.0.0",
+        arity: -1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "info",
+        summary: "Returns information and statistics about the server.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "config",
+        summary: "A container for server configuration commands.",
+        since: "2.0.0",
+        arity: -2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "object",
+        summary: "A container for object introspection commands.",
+        since: "2.2.3",
+        arity: -2,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+    },
+    CommandMeta {
+        name: "save",
+        summary: "Synchronously saves the database(s) to disk.",
+        since: "1.0.0",
+        arity: 1,
+        flags: &["admin", "noscript"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "subscribe",
+        summary: "Listens for messages published to channels.",
+        since: "2.0.0",
+        arity: -2,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "unsubscribe",
+        summary: "Stops listening to messages posted to channels.",
+        since: "2.0.0",
+        arity: -1,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "publish",
+        summary: "Posts a message to a channel.",
+        since: "2.0.0",
+        arity: 3,
+        flags: &["pubsub", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    #[cfg(debug_assertions)]
+    CommandMeta {
+        name: "debug",
+        summary: "A container for debugging commands.",
+        since: "1.0.0",
+        arity: -2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "psubscribe",
+        summary: "Listens for messages published to channels that match one or more patterns.",
+        since: "2.0.0",
+        arity: -2,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "punsubscribe",
+        summary: "Stops listening to messages published to channels that match one or more patterns.",
+        since: "2.0.0",
+        arity: -1,
+        flags: &["pubsub", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "reset",
+        summary: "Resets the connection.",
+        since: "6.2.0",
+        arity: 1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "client",
+        summary: "A container for client connection commands.",
+        since: "2.4.0",
+        arity: -2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "getrange",
+        summary: "Returns a substring of the string stored at a key.",
+        since: "2.4.0",
+        arity: 4,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "setrange",
+        summary: "Overwrites a part of a string value with another by an offset.",
+        since: "2.2.0",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "incr",
+        summary: "Increments the integer value of a key by one.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "decr",
+        summary: "Decrements the integer value of a key by one.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "incrby",
+        summary: "Increments the integer value of a key by a number.",
+        since: "1.0.0",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "decrby",
+        summary: "Decrements a number from the integer value of a key.",
+        since: "1.0.0",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "incrbyfloat",
+        summary: "Increment the floating point value of a key by a number.",
+        since: "2.6.0",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "touch",
+        summary: "Returns the number of existing keys out of those specified after updating the time they were last accessed.",
+        since: "3.2.1",
+        arity: -2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "dump",
+        summary: "Returns a serialized representation of the value stored at a key.",
+        since: "2.6.0",
+        arity: 2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "restore",
+        summary: "Creates a key from the serialized representation of a value.",
+        since: "2.6.0",
+        arity: -4,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "sadd",
+        summary: "Adds one or more members to a set.",
+        since: "1.0.0",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "sismember",
+        summary: "Determines whether a member belongs to a set.",
+        since: "1.0.0",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "smismember",
+        summary: "Determines whether multiple members belong to a set.",
+        since: "6.2.0",
+        arity: -3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zadd",
+        summary: "Adds one or more members to a sorted set, or updates their scores.",
+        since: "1.2.0",
+        arity: -4,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zscore",
+        summary: "Returns the score of a member in a sorted set.",
+        since: "1.2.0",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zrange",
+        summary: "Returns members in a sorted set within a range of indexes.",
+        since: "1.2.0",
+        arity: -4,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zcard",
+        summary: "Returns the number of members in a sorted set.",
+        since: "1.2.0",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "pexpireat",
+        summary: "Sets the expiration time of a key to a Unix milliseconds timestamp.",
+        since: "2.6.0",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zincrby",
+        summary: "Increments the score of a member in a sorted set.",
+        since: "1.2.0",
+        arity: 4,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "zrank",
+        summary: "Returns the index of a member in a sorted set ordered by ascending scores.",
+        since: "2.0.0",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "getex",
+        summary: "Returns the string value of a key after setting its expiration time.",
+        since: "6.2.0",
+        arity: -2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "lpush",
+        summary: "Prepends one or more elements to a list.",
+        since: "1.0.0",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "rpush",
+        summary: "Appends one or more elements to a list.",
+        since: "1.0.0",
+        arity: -3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "lpop",
+        summary: "Returns the first elements in a list after removing it.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "rpop",
+        summary: "Returns and removes the last elements of a list.",
+        since: "1.0.0",
+        arity: 2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "blpop",
+        summary: "Removes and returns the first element in a list. Blocks until an element is available otherwise.",
+        since: "2.0.0",
+        arity: -3,
+        flags: &["write", "blocking"],
+        first_key: 1,
+        last_key: -2,
+        step: 1,
+    },
+    CommandMeta {
+        name: "brpop",
+        summary: "Removes and returns the last element in a list. Blocks until an element is available otherwise.",
+        since: "2.0.0",
+        arity: -3,
+        flags: &["write", "blocking"],
+        first_key: 1,
+        last_key: -2,
+        step: 1,
+    },
+    CommandMeta {
+        name: "multi",
+        summary: "Starts a transaction.",
+        since: "1.2.0",
+        arity: 1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "exec",
+        summary: "Executes all commands in a transaction.",
+        since: "1.2.0",
+        arity: 1,
+        flags: &["noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "discard",
+        summary: "Discards a transaction.",
+        since: "2.0.0",
+        arity: 1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "watch",
+        summary: "Monitors changes to keys to determine the execution of a transaction.",
+        since: "2.2.0",
+        arity: -2,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "unwatch",
+        summary: "Forgets about watched keys of a transaction.",
+        since: "2.2.0",
+        arity: 1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "sort",
+        summary: "Sorts the elements in a list, a set, or a sorted set.",
+        since: "1.0.0",
+        arity: -2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "lset",
+        summary: "Sets the value of an element in a list by its index.",
+        since: "1.0.0",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "linsert",
+        summary: "Inserts an element before or after another element in a list.",
+        since: "2.2.0",
+        arity: 5,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "lrem",
+        summary: "Removes elements from a list.",
+        since: "1.0.0",
+        arity: 4,
+        flags: &["write"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "ltrim",
+        summary: "Removes elements from both ends a list.",
+        since: "1.0.0",
+        arity: 4,
+        flags: &["write"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "monitor",
+        summary: "Listens for all requests received by the server in real-time.",
+        since: "1.0.0",
+        arity: 1,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "quit",
+        summary: "Closes the connection.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["noscript", "loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "setbit",
+        summary: "Sets or clears the bit at offset of the string value.",
+        since: "2.2.0",
+        arity: 4,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "getbit",
+        summary: "Returns a bit value by offset.",
+        since: "2.2.0",
+        arity: 3,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "bitcount",
+        summary: "Counts the number of set bits (population counting) in a string.",
+        since: "2.6.0",
+        arity: -2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "lolwut",
+        summary: "Displays computer art and the Redis version.",
+        since: "5.0.0",
+        arity: -1,
+        flags: &["readonly", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "ping",
+        summary: "Returns the server's liveliness response.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "lpos",
+        summary: "Returns the index of matching elements in a list.",
+        since: "6.0.6",
+        arity: -3,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "hrandfield",
+        summary: "Returns one or more random fields from a hash.",
+        since: "6.2.0",
+        arity: -2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "srem",
+        summary: "Removes one or more members from a set. Deletes the set if the last member was removed.",
+        since: "1.0.0",
+        arity: -3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "srandmember",
+        summary: "Get one or multiple random members from a set",
+        since: "1.0.0",
+        arity: -2,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "spop",
+        summary: "Returns one or more random members from a set after removing them. Deletes the set if the last member was popped.",
+        since: "1.0.0",
+        arity: -2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "mset",
+        summary: "Atomically creates or modifies the string values of one or more keys.",
+        since: "1.0.1",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: -1,
+        step: 2,
+    },
+    CommandMeta {
+        name: "flushall",
+        summary: "Removes all keys from all databases.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["write"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "flushdb",
+        summary: "Remove all keys from the current database.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["write"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "move",
+        summary: "Moves a key to another database.",
+        since: "1.0.0",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "unlink",
+        summary: "Asynchronously deletes one or more keys.",
+        since: "4.0.0",
+        arity: -2,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+    },
+    CommandMeta {
+        name: "wait",
+        summary: "Blocks until the asynchronous replication of all preceding write commands sent by the connection is completed.",
+        since: "3.0.0",
+        arity: 3,
+        flags: &["noscript"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "shutdown",
+        summary: "Synchronously saves the database(s) to disk and shuts down the Redis server.",
+        since: "1.0.0",
+        arity: -1,
+        flags: &["admin", "noscript"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+    },
+    CommandMeta {
+        name: "hsetnx",
+        summary: "Sets the value of a field in a hash only when the field doesn't exist.",
+        since: "2.0.0",
+        arity: 4,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+    },
+];
+
 #[derive(Error, Debug)]
 pub enum CommandError {
     #[error("Invalid command: {0}")]
@@ -23,13 +901,100 @@ pub enum CommandError {
     RespError(#[from] RespError),
     #[error("Utf8 error: {0}")]
     Utf8Error(#[from] FromUtf8Error),
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("value is not an integer or out of range")]
+    NotAnInteger,
+    #[error("value is not a valid float")]
+    NotAFloat,
+}
+
+/// Range of the elements selected by the inclusive `start` and `end` indices like redis
+/// does: negative indices count from the end, and indices out of range are clamped.
+pub(crate) fn index_range(len: usize, start: i64, end: i64) -> std::ops::Range<usize> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { len + end } else { end.min(len - 1) };
+    if len == 0 || start > end {
+        return 0..0;
+    }
+    start as usize..end as usize + 1
+}
+
+/// reply for an operation against a key holding the wrong kind of value
+pub(crate) fn wrongtype() -> RespFrame {
+    CommandError::WrongType.into()
+}
+
+impl From<CommandError> for RespFrame {
+    fn from(e: CommandError) -> Self {
+        match e {
+            // the error carries its own redis error prefix
+            CommandError::WrongType => SimpleError::new(e.to_string()).into(),
+            e => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+/// Parses a command that takes a single key argument.
+pub(crate) fn parse_key(value: RespArray, name: &'static str) -> Result<String, CommandError> {
+    validate_command(&value, &[name])?;
+    match extract_args(value, 1)?.into_iter().next() {
+        Some(key @ RespFrame::BulkString(_)) => key.try_into(),
+        _ => Err(CommandError::InvalidArgument("invalid key".into())),
+    }
+}
+
+/// Strict conversion of a command argument into a string: only bulk and simple strings
+/// are accepted.
+impl TryFrom<RespFrame> for String {
+    type Error = CommandError;
+
+    fn try_from(value: RespFrame) -> Result<Self, Self::Error> {
+        match value {
+            RespFrame::BulkString(s) => Ok(String::from_utf8(s.to_vec())?),
+            RespFrame::SimpleString(s) => Ok(s.to_string()),
+            _ => Err(CommandError::InvalidArgument(
+                "expected a string argument".into(),
+            )),
+        }
+    }
 }
 
+/// Strict conversion of a command argument into an integer: accepts integers and bulk
+/// strings holding an integer.
+///
+/// This can't be a `TryFrom<RespFrame> for i64` impl since `enum_dispatch` already
+/// generates `TryInto<i64>` for `RespFrame` (matching only the `Integer` variant).
+fn int_arg(value: RespFrame) -> Result<i64, CommandError> {
+    match value {
+        RespFrame::Integer(n) => Ok(n),
+        RespFrame::BulkString(s) => num::parse_int(&s),
+        _ => Err(CommandError::NotAnInteger),
+    }
+}
+
+/// Execute a parsed command against the backend.
+///
+/// Execution never fails: runtime errors (wrong type, value is not an integer, ...) are
+/// reported to the client as `SimpleError` frames, usually built from a `CommandError`.
 #[enum_dispatch]
 pub trait CommandExecutor {
     fn execute(self, backend: &Backend) -> RespFrame;
 }
 
+/// Execute a command that reads or changes the state of the client connection.
+///
+/// Such commands also implement `CommandExecutor`, which runs them against the state of a
+/// fresh connection (e.g. when replayed from the aof).
+pub trait ConnectionExecutor {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame;
+}
+
 #[derive(Debug)]
 #[enum_dispatch(CommandExecutor)]
 pub enum Command {
@@ -38,11 +1003,353 @@ pub enum Command {
     HGet(HGet),
     HSet(HSet),
     HGetAll(HGetAll),
+    Copy(Copy),
+    Expire(Expire),
+    ExpireAt(ExpireAt),
+    PExpire(PExpire),
+    Persist(Persist),
+    Ttl(Ttl),
+    PTtl(PTtl),
+    CommandInfo(CommandInfo),
+    Info(Info),
+    Hello(Hello),
+    Save(Save),
+    Config(Config),
+    Object(Object),
+    #[cfg(debug_assertions)]
+    Debug(DebugCommand),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    Publish(Publish),
+    PSubscribe(PSubscribe),
+    PUnsubscribe(PUnsubscribe),
+    Reset(Reset),
+    Client(Client),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    Incr(Incr),
+    Decr(Decr),
+    IncrBy(IncrBy),
+    DecrBy(DecrBy),
+    IncrByFloat(IncrByFloat),
+    Touch(Touch),
+    Dump(Dump),
+    Restore(Restore),
+    SAdd(SAdd),
+    SIsMember(SIsMember),
+    SMIsMember(SMIsMember),
+    ZAdd(ZAdd),
+    ZScore(ZScore),
+    ZRange(ZRange),
+    ZCard(ZCard),
+    PExpireAt(PExpireAt),
+    ZIncrBy(ZIncrBy),
+    ZRank(ZRank),
+    GetEx(GetEx),
+    LPush(LPush),
+    RPush(RPush),
+    LPop(LPop),
+    RPop(RPop),
+    BLPop(BLPop),
+    BRPop(BRPop),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
+    Sort(Sort),
+    LSet(LSet),
+    LInsert(LInsert),
+    LRem(LRem),
+    LTrim(LTrim),
+    Monitor(Monitor),
+    Quit(Quit),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    Lolwut(Lolwut),
+    Ping(Ping),
+    LPos(LPos),
+    HRandField(HRandField),
+    SRem(SRem),
+    SRandMember(SRandMember),
+    SPop(SPop),
+    MSet(MSet),
+    FlushAll(FlushAll),
+    FlushDb(FlushDb),
+    Move(Move),
+    Unlink(Unlink),
+    Wait(Wait),
+    Shutdown(Shutdown),
+    HSetNx(HSetNx),
 
     // unrecognized command
     Unrecognized(Unrecognized),
 }
 
+impl Command {
+    /// name of the command as listed in `COMMAND_TABLE`, `None` for unrecognized commands
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self {
+            Command::Get(_) => "get",
+            Command::Set(_) => "set",
+            Command::HGet(_) => "hget",
+            Command::HSet(_) => "hset",
+            Command::HGetAll(_) => "hgetall",
+            Command::Copy(_) => "copy",
+            Command::Expire(_) => "expire",
+            Command::ExpireAt(_) => "expireat",
+            Command::PExpire(_) => "pexpire",
+            Command::Persist(_) => "persist",
+            Command::Ttl(_) => "ttl",
+            Command::PTtl(_) => "pttl",
+            Command::CommandInfo(_) => "command",
+            Command::Info(_) => "info",
+            Command::Hello(_) => "hello",
+            Command::Save(_) => "save",
+            Command::Config(_) => "config",
+            Command::Object(_) => "object",
+            #[cfg(debug_assertions)]
+            Command::Debug(_) => "debug",
+            Command::Subscribe(_) => "subscribe",
+            Command::Unsubscribe(_) => "unsubscribe",
+            Command::Publish(_) => "publish",
+            Command::PSubscribe(_) => "psubscribe",
+            Command::PUnsubscribe(_) => "punsubscribe",
+            Command::Reset(_) => "reset",
+            Command::Client(_) => "client",
+            Command::GetRange(_) => "getrange",
+            Command::SetRange(_) => "setrange",
+            Command::Incr(_) => "incr",
+            Command::Decr(_) => "decr",
+            Command::IncrBy(_) => "incrby",
+            Command::DecrBy(_) => "decrby",
+            Command::IncrByFloat(_) => "incrbyfloat",
+            Command::Touch(_) => "touch",
+            Command::Dump(_) => "dump",
+            Command::Restore(_) => "restore",
+            Command::SAdd(_) => "sadd",
+            Command::SIsMember(_) => "sismember",
+            Command::SMIsMember(_) => "smismember",
+            Command::ZAdd(_) => "zadd",
+            Command::ZScore(_) => "zscore",
+            Command::ZRange(_) => "zrange",
+            Command::ZCard(_) => "zcard",
+            Command::PExpireAt(_) => "pexpireat",
+            Command::ZIncrBy(_) => "zincrby",
+            Command::ZRank(_) => "zrank",
+            Command::GetEx(_) => "getex",
+            Command::LPush(_) => "lpush",
+            Command::RPush(_) => "rpush",
+            Command::LPop(_) => "lpop",
+            Command::RPop(_) => "rpop",
+            Command::BLPop(_) => "blpop",
+            Command::BRPop(_) => "brpop",
+            Command::Multi(_) => "multi",
+            Command::Exec(_) => "exec",
+            Command::Discard(_) => "discard",
+            Command::Watch(_) => "watch",
+            Command::Unwatch(_) => "unwatch",
+            Command::Sort(_) => "sort",
+            Command::LSet(_) => "lset",
+            Command::LInsert(_) => "linsert",
+            Command::LRem(_) => "lrem",
+            Command::LTrim(_) => "ltrim",
+            Command::Monitor(_) => "monitor",
+            Command::Quit(_) => "quit",
+            Command::SetBit(_) => "setbit",
+            Command::GetBit(_) => "getbit",
+            Command::BitCount(_) => "bitcount",
+            Command::Lolwut(_) => "lolwut",
+            Command::Ping(_) => "ping",
+            Command::LPos(_) => "lpos",
+            Command::HRandField(_) => "hrandfield",
+            Command::SRem(_) => "srem",
+            Command::SRandMember(_) => "srandmember",
+            Command::SPop(_) => "spop",
+            Command::MSet(_) => "mset",
+            Command::FlushAll(_) => "flushall",
+            Command::FlushDb(_) => "flushdb",
+            Command::Move(_) => "move",
+            Command::Unlink(_) => "unlink",
+            Command::Wait(_) => "wait",
+            Command::Shutdown(_) => "shutdown",
+            Command::HSetNx(_) => "hsetnx",
+            Command::Unrecognized(_) => return None,
+        };
+        Some(name)
+    }
+
+    /// execute the command on behalf of the connection with the given state
+    pub async fn execute_in(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        match self {
+            #[cfg(debug_assertions)]
+            Command::Debug(cmd) => cmd.execute_async(backend).await,
+            Command::Hello(cmd) => cmd.execute_with(backend, conn),
+            Command::Subscribe(cmd) => cmd.execute_with(backend, conn),
+            Command::Unsubscribe(cmd) => cmd.execute_with(backend, conn),
+            Command::PSubscribe(cmd) => cmd.execute_with(backend, conn),
+            Command::PUnsubscribe(cmd) => cmd.execute_with(backend, conn),
+            Command::Reset(cmd) => cmd.execute_with(backend, conn),
+            Command::Client(cmd) => cmd.execute_with(backend, conn),
+            Command::Watch(cmd) => cmd.execute_async(backend, conn).await,
+            Command::Multi(cmd) => cmd.execute_with(backend, conn),
+            Command::Discard(cmd) => cmd.execute_with(backend, conn),
+            Command::Unwatch(cmd) => cmd.execute_with(backend, conn),
+            Command::BLPop(cmd) => cmd.execute_async(backend).await,
+            Command::BRPop(cmd) => cmd.execute_async(backend).await,
+            Command::Monitor(cmd) => cmd.execute_with(backend, conn),
+            Command::Quit(cmd) => cmd.execute_with(backend, conn),
+            Command::Ping(cmd) => cmd.execute_with(backend, conn),
+            Command::FlushDb(cmd) => cmd.execute_with(backend, conn),
+            Command::Shutdown(cmd) => cmd.execute_with(backend, conn),
+            cmd => cmd.execute(backend),
+        }
+    }
+
+    /// Frames to append to the aof for the command `raw` was parsed into, once executed.
+    /// Times to live relative to now are rewritten as an absolute `PEXPIREAT`, so replaying
+    /// the aof later doesn't bring back keys that have expired since.
+    pub fn aof_frames(&self, raw: RespFrame) -> Vec<RespFrame> {
+        let now = expire::unix_millis();
+        match self {
+            Command::Expire(cmd) => vec![expire::pexpireat_frame(
+                &cmd.key,
+                now.saturating_add(cmd.seconds.saturating_mul(1000)),
+            )],
+            Command::PExpire(cmd) => vec![expire::pexpireat_frame(
+                &cmd.key,
+                now.saturating_add(cmd.milliseconds),
+            )],
+            Command::Set(cmd) if matches!(cmd.expiry, Some(SetExpiry::After(_))) => {
+                vec![cmd.aof_frame(now).into()]
+            }
+            // a plain GETEX doesn't modify anything
+            Command::GetEx(cmd) => match (cmd.expiry, cmd.persist) {
+                (Some(expiry), _) => {
+                    vec![expire::pexpireat_frame(&cmd.key, expiry.unix_millis(now))]
+                }
+                (None, true) => vec![RespArray::new([
+                    BulkString::from("persist").into(),
+                    BulkString::from(cmd.key.as_str()).into(),
+                ])
+                .into()],
+                (None, false) => vec![],
+            },
+            Command::Restore(cmd) if cmd.ttl.is_some() => {
+                let ttl = cmd.ttl.unwrap_or_default().as_millis() as i64;
+                let mut restore = vec![
+                    BulkString::from("restore").into(),
+                    BulkString::from(cmd.key.as_str()).into(),
+                    BulkString::from("0").into(),
+                    BulkString::new(cmd.payload.clone()).into(),
+                ];
+                if cmd.replace {
+                    restore.push(BulkString::from("replace").into());
+                }
+                vec![
+                    RespArray::new(restore).into(),
+                    expire::pexpireat_frame(&cmd.key, now.saturating_add(ttl)),
+                ]
+            }
+            // the members popped are only known once executed, SPOP appends them as SREM
+            Command::SPop(_) => vec![],
+            _ => vec![raw],
+        }
+    }
+
+    /// whether the command modifies data, i.e. has the `write` flag in `COMMAND_TABLE`
+    pub fn is_write(&self) -> bool {
+        self.has_flag("write")
+    }
+
+    /// whether the command may wait for other clients, i.e. has the `blocking` flag in
+    /// `COMMAND_TABLE`
+    pub fn is_blocking(&self) -> bool {
+        self.has_flag("blocking")
+    }
+
+    /// whether the request names a command with the `blocking` flag, without parsing it
+    pub fn is_blocking_request(frame: &RespFrame) -> bool {
+        request_meta(frame).is_some_and(|meta| meta.flags.contains(&"blocking"))
+    }
+
+    /// Keys of the request, at the positions given by its command in `COMMAND_TABLE`.
+    pub fn key_args(frame: &RespFrame) -> Vec<String> {
+        let (Some(meta), RespFrame::Array(args)) = (request_meta(frame), frame) else {
+            return vec![];
+        };
+        if meta.first_key == 0 {
+            return vec![];
+        }
+        let last_key = match meta.last_key {
+            last if last < 0 => args.len() as i64 + last,
+            last => last,
+        };
+        (meta.first_key..=last_key)
+            .step_by(meta.step.max(1) as usize)
+            .filter_map(|i| match args.get(i as usize) {
+                Some(RespFrame::BulkString(key)) => Some(String::from_utf8_lossy(key).into_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// whether the command is queued when sent after `MULTI`, the commands controlling the
+    /// transaction run right away
+    pub fn is_queued_in_transaction(&self) -> bool {
+        !matches!(
+            self,
+            Command::Multi(_)
+                | Command::Exec(_)
+                | Command::Discard(_)
+                | Command::Watch(_)
+                | Command::Reset(_)
+                | Command::Quit(_)
+        )
+    }
+
+    /// Whether a RESP2 connection subscribed to channels or patterns may run the command,
+    /// other commands are rejected like redis does until the last subscription ends.
+    pub fn is_allowed_while_subscribed(&self) -> bool {
+        matches!(
+            self,
+            Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::Reset(_)
+                | Command::Quit(_)
+                | Command::Ping(_)
+        )
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.name().is_some_and(|name| has_flag(name, flag))
+    }
+}
+
+// whether the command `name` has the `flag` in `COMMAND_TABLE`
+fn has_flag(name: &str, flag: &str) -> bool {
+    COMMAND_TABLE
+        .iter()
+        .find(|meta| meta.name == name)
+        .is_some_and(|meta| meta.flags.contains(&flag))
+}
+
+// entry in `COMMAND_TABLE` of the command named by the request
+fn request_meta(frame: &RespFrame) -> Option<&'static CommandMeta> {
+    let RespFrame::Array(args) = frame else {
+        return None;
+    };
+    let Some(RespFrame::BulkString(name)) = args.first() else {
+        return None;
+    };
+    COMMAND_TABLE
+        .iter()
+        .find(|meta| meta.name.as_bytes().eq_ignore_ascii_case(name))
+}
+
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
 
@@ -60,18 +1367,107 @@ impl TryFrom<RespArray> for Command {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        match value.first() {
-            Some(RespFrame::BulkString(ref cmd)) => match cmd.as_ref() {
-                b"get" => Get::try_from(value).map(|x| x.into()),
-                b"set" => Set::try_from(value).map(|x| x.into()),
-                b"hget" => HGet::try_from(value).map(|x| x.into()),
-                b"hset" => HSet::try_from(value).map(|x| x.into()),
-                b"hgetall" => HGetAll::try_from(value).map(|x| x.into()),
-                _ => Ok(Unrecognized.into()),
-            },
-            _ => Err(CommandError::InvalidCommand(
-                "Command must have a BulkString as the first argument".into(),
-            )),
+        // command names are case insensitive
+        let name = match value.first() {
+            Some(RespFrame::BulkString(cmd)) => cmd.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidCommand(
+                    "Command must have a BulkString as the first argument".into(),
+                ))
+            }
+        };
+        if let Some(meta) = COMMAND_TABLE
+            .iter()
+            .find(|meta| meta.name.as_bytes() == name)
+        {
+            validate_arity(&value, meta)?;
+        }
+        match name.as_slice() {
+            b"get" => Get::try_from(value).map(|x| x.into()),
+            b"set" => Set::try_from(value).map(|x| x.into()),
+            b"hget" => HGet::try_from(value).map(|x| x.into()),
+            b"hset" => HSet::try_from(value).map(|x| x.into()),
+            b"hgetall" => HGetAll::try_from(value).map(|x| x.into()),
+            b"copy" => Copy::try_from(value).map(|x| x.into()),
+            b"expire" => Expire::try_from(value).map(|x| x.into()),
+            b"expireat" => ExpireAt::try_from(value).map(|x| x.into()),
+            b"pexpire" => PExpire::try_from(value).map(|x| x.into()),
+            b"persist" => Persist::try_from(value).map(|x| x.into()),
+            b"ttl" => Ttl::try_from(value).map(|x| x.into()),
+            b"pttl" => PTtl::try_from(value).map(|x| x.into()),
+            b"command" => CommandInfo::try_from(value).map(|x| x.into()),
+            b"info" => Info::try_from(value).map(|x| x.into()),
+            b"hello" => Hello::try_from(value).map(|x| x.into()),
+            b"save" => Save::try_from(value).map(|x| x.into()),
+            b"config" => Config::try_from(value).map(|x| x.into()),
+            b"object" => Object::try_from(value).map(|x| x.into()),
+            #[cfg(debug_assertions)]
+            b"debug" => DebugCommand::try_from(value).map(|x| x.into()),
+            b"subscribe" => Subscribe::try_from(value).map(|x| x.into()),
+            b"unsubscribe" => Unsubscribe::try_from(value).map(|x| x.into()),
+            b"publish" => Publish::try_from(value).map(|x| x.into()),
+            b"psubscribe" => PSubscribe::try_from(value).map(|x| x.into()),
+            b"punsubscribe" => PUnsubscribe::try_from(value).map(|x| x.into()),
+            b"reset" => Reset::try_from(value).map(|x| x.into()),
+            b"client" => Client::try_from(value).map(|x| x.into()),
+            b"getrange" => GetRange::try_from(value).map(|x| x.into()),
+            b"setrange" => SetRange::try_from(value).map(|x| x.into()),
+            b"incr" => Incr::try_from(value).map(|x| x.into()),
+            b"decr" => Decr::try_from(value).map(|x| x.into()),
+            b"incrby" => IncrBy::try_from(value).map(|x| x.into()),
+            b"decrby" => DecrBy::try_from(value).map(|x| x.into()),
+            b"incrbyfloat" => IncrByFloat::try_from(value).map(|x| x.into()),
+            b"touch" => Touch::try_from(value).map(|x| x.into()),
+            b"dump" => Dump::try_from(value).map(|x| x.into()),
+            b"restore" => Restore::try_from(value).map(|x| x.into()),
+            b"sadd" => SAdd::try_from(value).map(|x| x.into()),
+            b"sismember" => SIsMember::try_from(value).map(|x| x.into()),
+            b"smismember" => SMIsMember::try_from(value).map(|x| x.into()),
+            b"zadd" => ZAdd::try_from(value).map(|x| x.into()),
+            b"zscore" => ZScore::try_from(value).map(|x| x.into()),
+            b"zrange" => ZRange::try_from(value).map(|x| x.into()),
+            b"zcard" => ZCard::try_from(value).map(|x| x.into()),
+            b"pexpireat" => PExpireAt::try_from(value).map(|x| x.into()),
+            b"zincrby" => ZIncrBy::try_from(value).map(|x| x.into()),
+            b"zrank" => ZRank::try_from(value).map(|x| x.into()),
+            b"getex" => GetEx::try_from(value).map(|x| x.into()),
+            b"lpush" => LPush::try_from(value).map(|x| x.into()),
+            b"rpush" => RPush::try_from(value).map(|x| x.into()),
+            b"lpop" => LPop::try_from(value).map(|x| x.into()),
+            b"rpop" => RPop::try_from(value).map(|x| x.into()),
+            b"blpop" => BLPop::try_from(value).map(|x| x.into()),
+            b"brpop" => BRPop::try_from(value).map(|x| x.into()),
+            b"multi" => Multi::try_from(value).map(|x| x.into()),
+            b"exec" => Exec::try_from(value).map(|x| x.into()),
+            b"discard" => Discard::try_from(value).map(|x| x.into()),
+            b"watch" => Watch::try_from(value).map(|x| x.into()),
+            b"unwatch" => Unwatch::try_from(value).map(|x| x.into()),
+            b"sort" => Sort::try_from(value).map(|x| x.into()),
+            b"lset" => LSet::try_from(value).map(|x| x.into()),
+            b"linsert" => LInsert::try_from(value).map(|x| x.into()),
+            b"lrem" => LRem::try_from(value).map(|x| x.into()),
+            b"ltrim" => LTrim::try_from(value).map(|x| x.into()),
+            b"monitor" => Monitor::try_from(value).map(|x| x.into()),
+            b"quit" => Quit::try_from(value).map(|x| x.into()),
+            b"setbit" => SetBit::try_from(value).map(|x| x.into()),
+            b"getbit" => GetBit::try_from(value).map(|x| x.into()),
+            b"bitcount" => BitCount::try_from(value).map(|x| x.into()),
+            b"lolwut" => Lolwut::try_from(value).map(|x| x.into()),
+            b"ping" => Ping::try_from(value).map(|x| x.into()),
+            b"lpos" => LPos::try_from(value).map(|x| x.into()),
+            b"hrandfield" => HRandField::try_from(value).map(|x| x.into()),
+            b"srem" => SRem::try_from(value).map(|x| x.into()),
+            b"srandmember" => SRandMember::try_from(value).map(|x| x.into()),
+            b"spop" => SPop::try_from(value).map(|x| x.into()),
+            b"mset" => MSet::try_from(value).map(|x| x.into()),
+            b"flushall" => FlushAll::try_from(value).map(|x| x.into()),
+            b"flushdb" => FlushDb::try_from(value).map(|x| x.into()),
+            b"move" => Move::try_from(value).map(|x| x.into()),
+            b"unlink" => Unlink::try_from(value).map(|x| x.into()),
+            b"wait" => Wait::try_from(value).map(|x| x.into()),
+            b"shutdown" => Shutdown::try_from(value).map(|x| x.into()),
+            b"hsetnx" => HSetNx::try_from(value).map(|x| x.into()),
+            _ => Ok(Unrecognized(String::from_utf8_lossy(&name).into_owned()).into()),
         }
     }
 }
@@ -85,6 +1481,36 @@ pub struct Get {
 pub struct Set {
     key: String,
     value: RespFrame,
+    condition: Option<SetCondition>,
+    expiry: Option<SetExpiry>,
+    get: bool,
+}
+
+/// Expiry option of `SET` and `GETEX`, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetExpiry {
+    /// EX and PX, relative to now
+    After(i64),
+    /// EXAT and PXAT, as a unix time
+    At(i64),
+}
+
+impl SetExpiry {
+    /// the expiry as a unix time in milliseconds, given the current one
+    fn unix_millis(self, now: i64) -> i64 {
+        match self {
+            SetExpiry::After(millis) => now.saturating_add(millis),
+            SetExpiry::At(timestamp) => timestamp,
+        }
+    }
+
+    /// milliseconds left until the expiry, given the current unix time
+    fn millis_from(self, now: i64) -> i64 {
+        match self {
+            SetExpiry::After(millis) => millis,
+            SetExpiry::At(timestamp) => timestamp.saturating_sub(now),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -103,35 +1529,522 @@ pub struct HSet {
 #[derive(Debug)]
 pub struct HGetAll {
     key: String,
-    sort: bool,
 }
 
 #[derive(Debug)]
-pub struct Unrecognized;
+pub struct Copy {
+    source: String,
+    destination: String,
+    replace: bool,
+}
+
+#[derive(Debug)]
+pub struct Expire {
+    key: String,
+    seconds: i64,
+}
+
+#[derive(Debug)]
+pub struct ExpireAt {
+    key: String,
+    timestamp: i64,
+}
+
+#[derive(Debug)]
+pub struct PExpire {
+    key: String,
+    milliseconds: i64,
+}
+
+#[derive(Debug)]
+pub struct Persist {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Ttl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct PTtl {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct CommandInfo {
+    subcommand: CommandSubcommand,
+}
+
+#[derive(Debug, PartialEq)]
+enum CommandSubcommand {
+    List,
+    Count,
+    /// docs of the named commands, of every command if none is named
+    Docs(Vec<String>),
+    /// the command, name and arguments, to find the keys of
+    GetKeys(RespArray),
+}
+
+#[derive(Debug)]
+pub struct Info {
+    section: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Hello {
+    protocol: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct Save;
+
+#[derive(Debug)]
+pub enum Config {
+    Get(String),
+    Set(String, String),
+}
+
+#[derive(Debug)]
+pub enum Object {
+    Encoding(String),
+    IdleTime(String),
+    RefCount(String),
+    Freq(String),
+}
+
+/// `DEBUG` subcommands for testing, only available in debug builds
+#[cfg(debug_assertions)]
+#[derive(Debug)]
+pub enum DebugCommand {
+    Sleep(std::time::Duration),
+    Object(String),
+}
+
+#[derive(Debug)]
+pub struct Subscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unsubscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Publish {
+    channel: String,
+    message: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PUnsubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Reset;
+
+#[derive(Debug)]
+pub enum Client {
+    Id,
+    GetName,
+    SetName(String),
+    List,
+}
+
+#[derive(Debug)]
+pub struct GetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct SetRange {
+    key: String,
+    offset: usize,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Incr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Decr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct IncrBy {
+    key: String,
+    increment: i64,
+}
+
+#[derive(Debug)]
+pub struct DecrBy {
+    key: String,
+    decrement: i64,
+}
+
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: String,
+    increment: f64,
+}
+
+#[derive(Debug)]
+pub struct Touch {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Dump {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Restore {
+    key: String,
+    ttl: Option<std::time::Duration>,
+    payload: Vec<u8>,
+    replace: bool,
+}
+
+#[derive(Debug)]
+pub struct SAdd {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SIsMember {
+    key: String,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct SMIsMember {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ZAdd {
+    key: String,
+    members: Vec<(f64, String)>,
+}
+
+#[derive(Debug)]
+pub struct ZScore {
+    key: String,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct ZRange {
+    key: String,
+    start: i64,
+    stop: i64,
+    withscores: bool,
+}
+
+#[derive(Debug)]
+pub struct ZCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct PExpireAt {
+    key: String,
+    timestamp: i64,
+}
+
+#[derive(Debug)]
+pub struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct ZRank {
+    key: String,
+    member: String,
+}
+
+#[derive(Debug)]
+pub struct GetEx {
+    key: String,
+    expiry: Option<SetExpiry>,
+    persist: bool,
+}
+
+#[derive(Debug)]
+pub struct LPush {
+    key: String,
+    values: Vec<RespFrame>,
+}
+
+#[derive(Debug)]
+pub struct RPush {
+    key: String,
+    values: Vec<RespFrame>,
+}
+
+#[derive(Debug)]
+pub struct LPop {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct RPop {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct BLPop {
+    keys: Vec<String>,
+    timeout: Option<std::time::Duration>,
+}
+
+#[derive(Debug)]
+pub struct BRPop {
+    keys: Vec<String>,
+    timeout: Option<std::time::Duration>,
+}
+
+#[derive(Debug)]
+pub struct Multi;
+
+#[derive(Debug)]
+pub struct Exec;
+
+#[derive(Debug)]
+pub struct Discard;
+
+#[derive(Debug)]
+pub struct Watch {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unwatch;
+
+#[derive(Debug)]
+pub struct Sort {
+    key: String,
+    alpha: bool,
+    desc: bool,
+    /// offset and count of the elements to return, a negative count returns the rest
+    limit: Option<(i64, i64)>,
+}
+
+#[derive(Debug)]
+pub struct LSet {
+    key: String,
+    index: i64,
+    value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct LInsert {
+    key: String,
+    /// insert before the pivot, otherwise after it
+    before: bool,
+    pivot: RespFrame,
+    value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct LRem {
+    key: String,
+    count: i64,
+    value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct LTrim {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Debug)]
+pub struct Monitor;
+
+#[derive(Debug)]
+pub struct Quit;
+
+#[derive(Debug)]
+pub struct SetBit {
+    key: String,
+    offset: usize,
+    bit: bool,
+}
+
+#[derive(Debug)]
+pub struct GetBit {
+    key: String,
+    offset: usize,
+}
+
+#[derive(Debug)]
+pub struct BitCount {
+    key: String,
+    range: Option<(i64, i64)>,
+}
+
+#[derive(Debug)]
+pub struct Lolwut;
+
+#[derive(Debug)]
+pub struct Ping {
+    message: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct LPos {
+    key: String,
+    value: RespFrame,
+    /// the match to start from, the first one being 1, counted from the tail if negative
+    rank: i64,
+    /// reply with the positions of up to `count` matches, of all of them if 0
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct HRandField {
+    key: String,
+    // `None` for a single field, replied as a bulk string rather than an array
+    count: Option<i64>,
+    with_values: bool,
+}
+
+#[derive(Debug)]
+pub struct SRem {
+    key: String,
+    members: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SRandMember {
+    key: String,
+    // `None` for a single member, replied as a bulk string rather than an array
+    count: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct SPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct MSet {
+    pairs: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct FlushAll;
+
+#[derive(Debug)]
+pub struct FlushDb;
+
+/// The key, checked while parsing, isn't kept: with database 0 alone it can never move.
+#[derive(Debug)]
+pub struct Move {
+    db: i64,
+}
+
+#[derive(Debug)]
+pub struct Unlink {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Wait;
+
+#[derive(Debug)]
+pub struct Shutdown {
+    // SAVE, a snapshot is written before shutting down
+    save: bool,
+}
+
+#[derive(Debug)]
+pub struct HSetNx {
+    key: String,
+    field: String,
+    value: RespFrame,
+}
+
+/// A command this server doesn't implement, by its lowercase name.
+#[derive(Debug)]
+pub struct Unrecognized(String);
+
+/// Redis commands recognized but not implemented, which are reported as such rather than
+/// as unknown commands.
+pub static UNIMPLEMENTED_COMMANDS: &[&str] = &[
+    "append",
+    "bgrewriteaof",
+    "bgsave",
+    "bitop",
+    "bitpos",
+    "dbsize",
+    "del",
+    "echo",
+    "exists",
+    "getdel",
+    "getset",
+    "hdel",
+    "hexists",
+    "hincrby",
+    "hkeys",
+    "hlen",
+    "hmget",
+    "hvals",
+    "keys",
+    "lindex",
+    "llen",
+    "lrange",
+    "mget",
+    "randomkey",
+    "rename",
+    "scan",
+    "scard",
+    "select",
+    "setex",
+    "setnx",
+    "smembers",
+    "strlen",
+    "type",
+    "zcount",
+    "zrem",
+    "zrevrange",
+];
 
 impl CommandExecutor for Unrecognized {
     fn execute(self, _backend: &Backend) -> RespFrame {
-        RESP_OK.clone()
+        if UNIMPLEMENTED_COMMANDS.contains(&self.0.as_str()) {
+            return SimpleError::new(format!("ERR '{}' command is not implemented", self.0)).into();
+        }
+        SimpleError::new(format!("ERR unknown command '{}'", self.0)).into()
     }
 }
 
-fn validate_command(
-    value: &RespArray,
-    names: &[&'static str],
-    n_args: usize,
-) -> Result<(), CommandError> {
-    // test if the array has 2 elements
-    if value.len() != n_args + 1 {
-        return Err(CommandError::InvalidArgument(format!(
-            "{} command must have exactly {} arguments",
-            names.join(" "),
-            n_args
-        )));
-    }
-
+// the number of arguments is checked against `COMMAND_TABLE` by `Command::try_from`, this
+// only checks the command name, and subcommand name if any
+fn validate_command(value: &RespArray, names: &[&'static str]) -> Result<(), CommandError> {
     for (i, name) in names.iter().enumerate() {
-        match value[i] {
-            RespFrame::BulkString(ref cmd) => {
+        match value.get(i) {
+            Some(RespFrame::BulkString(cmd)) => {
                 if cmd.to_ascii_lowercase() != name.as_bytes() {
                     return Err(CommandError::InvalidCommand(format!(
                         "Invalid command: expected {}, got {}",
@@ -151,6 +2064,224 @@ fn validate_command(
     Ok(())
 }
 
+// like `validate_command` for a subcommand taking exactly `n_args` arguments, subcommands
+// are not covered by `COMMAND_TABLE`
+fn validate_subcommand(
+    value: &RespArray,
+    names: &[&'static str],
+    n_args: usize,
+) -> Result<(), CommandError> {
+    if value.len() != n_args + 1 {
+        return Err(CommandError::InvalidArgument(format!(
+            "{} command must have exactly {} arguments",
+            names.join(" "),
+            n_args
+        )));
+    }
+    validate_command(value, names)
+}
+
+// check the number of arguments, command name included, against the arity in `COMMAND_TABLE`
+fn validate_arity(value: &RespArray, meta: &CommandMeta) -> Result<(), CommandError> {
+    let len = value.len() as i64;
+    let valid = if meta.arity >= 0 {
+        len == meta.arity
+    } else {
+        len >= -meta.arity
+    };
+    if !valid {
+        return Err(CommandError::InvalidArgument(format!(
+            "wrong number of arguments for '{}' command",
+            meta.name
+        )));
+    }
+    Ok(())
+}
+
 fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, CommandError> {
     Ok(value.into_iter().skip(start).collect::<Vec<_>>())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RespEncode, SimpleString};
+
+    #[test]
+    fn test_aof_frames_use_absolute_ttl() {
+        let frame = |args: &[&str]| -> RespFrame {
+            let args = args
+                .iter()
+                .map(|arg| BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>();
+            RespArray::new(args).into()
+        };
+        // the deadline in the aof frame, in ms from now
+        let deadline = |frame: &RespFrame| -> i64 {
+            let RespFrame::Array(args) = frame else {
+                panic!("expected an array");
+            };
+            let RespFrame::BulkString(ts) = &args[2] else {
+                panic!("expected a bulk string timestamp");
+            };
+            num::parse_int(ts).unwrap() - expire::unix_millis()
+        };
+
+        let raw = frame(&["set", "k", "v"]);
+        let cmd = Command::try_from(raw.clone()).unwrap();
+        assert_eq!(cmd.aof_frames(raw.clone()), vec![raw]);
+
+        let raw = frame(&["expire", "k", "10"]);
+        let frames = Command::try_from(raw.clone()).unwrap().aof_frames(raw);
+        assert_eq!(frames.len(), 1);
+        assert!((9_000..=10_000).contains(&deadline(&frames[0])));
+
+        let raw = frame(&["restore", "k", "5000", "payload", "REPLACE"]);
+        let frames = Command::try_from(raw.clone()).unwrap().aof_frames(raw);
+        assert_eq!(
+            frames[0],
+            frame(&["restore", "k", "0", "payload", "replace"])
+        );
+        assert!((4_000..=5_000).contains(&deadline(&frames[1])));
+
+        let raw = frame(&["getex", "k", "ex", "10"]);
+        let frames = Command::try_from(raw.clone()).unwrap().aof_frames(raw);
+        assert!((9_000..=10_000).contains(&deadline(&frames[0])));
+        let raw = frame(&["getex", "k", "persist"]);
+        let frames = Command::try_from(raw.clone()).unwrap().aof_frames(raw);
+        assert_eq!(frames, vec![frame(&["persist", "k"])]);
+        let raw = frame(&["getex", "k"]);
+        assert!(Command::try_from(raw.clone())
+            .unwrap()
+            .aof_frames(raw)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_command_error_to_frame() {
+        let frame: RespFrame = CommandError::InvalidArgument("invalid key".into()).into();
+        assert_eq!(frame.encode(), b"-ERR Invalid arguments: invalid key\r\n");
+
+        let frame: RespFrame = CommandError::WrongType.into();
+        assert_eq!(
+            frame.encode(),
+            b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    }
+
+    #[test]
+    fn test_frame_try_into_string() -> Result<()> {
+        let s: String = RespFrame::from("hello").try_into()?;
+        assert_eq!(s, "hello");
+        let s: String = RespFrame::from(SimpleString::new("OK")).try_into()?;
+        assert_eq!(s, "OK");
+        assert!(String::try_from(RespFrame::Integer(1)).is_err());
+        assert!(String::try_from(RespFrame::from(vec![0xff, 0xfe])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_int_arg() -> Result<()> {
+        assert_eq!(int_arg(RespFrame::Integer(42))?, 42);
+        assert_eq!(int_arg(RespFrame::from("-7"))?, -7);
+        assert!(int_arg(RespFrame::from("1.5")).is_err());
+        assert!(int_arg(RespFrame::from("abc")).is_err());
+        assert!(int_arg(RespFrame::from(SimpleString::new("1"))).is_err());
+        assert!(int_arg(RespFrame::Double(1.0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_command_becomes_error_frame() {
+        let frame = RespArray::new([crate::BulkString::from("get").into()]);
+        let err = Command::try_from(frame).unwrap_err();
+        let frame: RespFrame = err.into();
+        assert!(matches!(frame, RespFrame::Error(_)));
+    }
+
+    #[test]
+    fn test_arity_is_checked_against_command_table() {
+        let command = |args: &[&str]| {
+            let args = args
+                .iter()
+                .map(|arg| crate::BulkString::from(*arg).into())
+                .collect::<Vec<RespFrame>>();
+            Command::try_from(RespArray::new(args))
+        };
+        // exact arity
+        assert!(command(&["get", "a"]).is_ok());
+        let err: RespFrame = command(&["get", "a", "b"]).unwrap_err().into();
+        assert_eq!(
+            err.encode(),
+            b"-ERR Invalid arguments: wrong number of arguments for 'get' command\r\n"
+        );
+        // "at least" arity
+        assert!(command(&["touch"]).is_err());
+        assert!(command(&["touch", "a", "b", "c"]).is_ok());
+        // unknown commands are not rejected
+        assert!(matches!(
+            command(&["nope", "a"]),
+            Ok(Command::Unrecognized(_))
+        ));
+    }
+
+    #[test]
+    fn test_unrecognized_command_reply() -> anyhow::Result<()> {
+        let command = |args: &[&str]| {
+            let args = args.iter().map(|arg| crate::BulkString::from(*arg).into());
+            Command::try_from(RespArray::new(args.collect::<Vec<_>>()))
+        };
+        let backend = Backend::new();
+        assert_eq!(
+            command(&["NOPE", "a"])?.execute(&backend),
+            SimpleError::new("ERR unknown command 'nope'").into()
+        );
+        assert_eq!(
+            command(&["mget", "a"])?.execute(&backend),
+            SimpleError::new("ERR 'mget' command is not implemented").into()
+        );
+        for name in UNIMPLEMENTED_COMMANDS {
+            let implemented = COMMAND_TABLE.iter().any(|meta| meta.name == *name);
+            assert!(!implemented, "{} is implemented", name);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_uppercase_command_is_dispatched() -> anyhow::Result<()> {
+        use crate::RespDecode;
+
+        let mut buf = bytes::BytesMut::from("*3\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+        let cmd = Command::try_from(RespArray::decode(&mut buf)?)?;
+        assert!(matches!(cmd, Command::Set(_)));
+
+        let mut buf = bytes::BytesMut::from("*2\r\n$3\r\nGet\r\n$5\r\nhello\r\n");
+        let cmd = Command::try_from(RespArray::decode(&mut buf)?)?;
+        assert!(matches!(cmd, Command::Get(_)));
+
+        // the arity of mixed case commands is checked too
+        let mut buf = bytes::BytesMut::from("*1\r\n$3\r\nGET\r\n");
+        assert!(Command::try_from(RespArray::decode(&mut buf)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_args() {
+        let request = |args: &[&str]| -> RespFrame {
+            RespArray::new(
+                args.iter()
+                    .map(|arg| BulkString::from(*arg).into())
+                    .collect::<Vec<_>>(),
+            )
+            .into()
+        };
+        assert_eq!(Command::key_args(&request(&["SET", "k", "v"])), ["k"]);
+        assert_eq!(Command::key_args(&request(&["copy", "a", "b"])), ["a", "b"]);
+        assert_eq!(
+            Command::key_args(&request(&["blpop", "a", "b", "0"])),
+            ["a", "b"]
+        );
+        assert!(Command::key_args(&request(&["publish", "c", "m"])).is_empty());
+        assert!(Command::key_args(&request(&["nope", "a"])).is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+// Parsing and formatting of numbers stored in, or passed as, strings, following redis.
+
+use super::CommandError;
+
+/// Parse a decimal integer like redis: an optional `-` sign, no `+` sign nor surrounding
+/// spaces, and within the range of an `i64`.
+pub(crate) fn parse_int(data: &[u8]) -> Result<i64, CommandError> {
+    if data.first() == Some(&b'+') {
+        return Err(CommandError::NotAnInteger);
+    }
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(CommandError::NotAnInteger)
+}
+
+/// Parse a float like redis, rejecting NaN and infinity.
+pub(crate) fn parse_float(data: &[u8]) -> Result<f64, CommandError> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .ok_or(CommandError::NotAFloat)
+}
+
+/// Format a float like redis replies to `INCRBYFLOAT`: no exponent and no trailing zeros,
+/// e.g. `3.0` is `3`.
+pub(crate) fn format_float(f: f64) -> String {
+    // `Display` never uses an exponent and prints the shortest digits reading back as `f`
+    f.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_int() {
+        assert_eq!(parse_int(b"42").unwrap(), 42);
+        assert_eq!(parse_int(b"-42").unwrap(), -42);
+        assert_eq!(
+            parse_int(i64::MIN.to_string().as_bytes()).unwrap(),
+            i64::MIN
+        );
+        for invalid in [
+            &b"+1"[..],
+            b" 1",
+            b"1.0",
+            b"",
+            b"abc",
+            b"9223372036854775808",
+        ] {
+            assert!(matches!(
+                parse_int(invalid),
+                Err(CommandError::NotAnInteger)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_float() {
+        assert_eq!(parse_float(b"10.5").unwrap(), 10.5);
+        assert_eq!(parse_float(b"5.0e3").unwrap(), 5000.0);
+        assert_eq!(parse_float(b"-1").unwrap(), -1.0);
+        for invalid in [&b"abc"[..], b"", b"inf", b"nan"] {
+            assert!(matches!(parse_float(invalid), Err(CommandError::NotAFloat)));
+        }
+    }
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(3.0), "3");
+        assert_eq!(format_float(10.5 + 0.1), "10.6");
+        assert_eq!(format_float(5.0e3 + 2.0e2), "5200");
+        assert_eq!(format_float(-0.25), "-0.25");
+        assert_eq!(format_float(1e20), "100000000000000000000");
+        assert_eq!(format_float(1.5e-7), "0.00000015");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    backend::{Backend, EvictionPolicy},
+    RespArray, RespFrame, SimpleError, SimpleString,
+};
+
+use super::{extract_args, validate_subcommand, CommandError, CommandExecutor, Object};
+
+impl CommandExecutor for Object {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            Object::Encoding(key) => match backend.object_encoding(&key) {
+                Some(encoding) => SimpleString::new(encoding).into(),
+                None => no_such_key(),
+            },
+            Object::IdleTime(key) => match backend.idle_time(&key) {
+                Some(idle) => RespFrame::integer(idle.as_secs() as i64),
+                None => no_such_key(),
+            },
+            Object::Freq(_) if backend.eviction_policy() != EvictionPolicy::AllKeysLfu => {
+                SimpleError::new(
+                    "ERR An LFU maxmemory policy is not selected, access frequency not tracked.",
+                )
+                .into()
+            }
+            Object::Freq(key) => match backend.access_frequency(&key) {
+                Some(frequency) => RespFrame::integer(frequency as i64),
+                None => no_such_key(),
+            },
+            // values are never shared between keys
+            Object::RefCount(key) => match backend.key_type(&key) {
+                Some(_) => RespFrame::integer(1),
+                None => no_such_key(),
+            },
+        }
+    }
+}
+
+fn no_such_key() -> RespFrame {
+    SimpleError::new("ERR no such key").into()
+}
+
+impl TryFrom<RespArray> for Object {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let subcommand = match value.get(1) {
+            Some(RespFrame::BulkString(s)) => s.to_ascii_lowercase(),
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "object command needs a subcommand".into(),
+                ))
+            }
+        };
+        let (name, object): (_, fn(String) -> Object) = match subcommand.as_slice() {
+            b"encoding" => ("encoding", Object::Encoding),
+            b"idletime" => ("idletime", Object::IdleTime),
+            b"refcount" => ("refcount", Object::RefCount),
+            b"freq" => ("freq", Object::Freq),
+            _ => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'",
+                    String::from_utf8_lossy(&subcommand)
+                )))
+            }
+        };
+        validate_subcommand(&value, &["object", name], 2)?;
+        match extract_args(value, 2)?.into_iter().next() {
+            Some(key @ RespFrame::BulkString(_)) => Ok(object(key.try_into()?)),
+            _ => Err(CommandError::InvalidArgument("invalid key".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_object_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nobject\r\n$8\r\nENCODING\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Object::try_from(frame)?;
+        assert!(matches!(cmd, Object::Encoding(key) if key == "hello"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_encoding_command() {
+        let backend = Backend::new();
+        backend.set("int", BulkString::new("12345").into());
+        backend.set("short", BulkString::new("hello").into());
+        backend.set("long", BulkString::new("x".repeat(100)).into());
+        backend.hset("small", "field", BulkString::new("value").into());
+        backend.hset("big", "field", BulkString::new("x".repeat(100)).into());
+
+        let encoding = |key: &str| Object::Encoding(key.into()).execute(&backend);
+        assert_eq!(encoding("int"), SimpleString::new("int").into());
+        assert_eq!(encoding("short"), SimpleString::new("embstr").into());
+        assert_eq!(encoding("long"), SimpleString::new("raw").into());
+        assert_eq!(encoding("small"), SimpleString::new("listpack").into());
+        assert_eq!(encoding("big"), SimpleString::new("hashtable").into());
+        assert_eq!(encoding("missing"), no_such_key());
+    }
+
+    #[test]
+    fn test_object_idletime_refcount_command() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nobject\r\n$8\r\nidletime\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(Object::try_from(frame)?, Object::IdleTime(key) if key == "hello"));
+
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        assert_eq!(
+            Object::IdleTime("hello".into()).execute(&backend),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            Object::RefCount("hello".into()).execute(&backend),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            Object::IdleTime("missing".into()).execute(&backend),
+            no_such_key()
+        );
+        assert_eq!(
+            Object::RefCount("missing".into()).execute(&backend),
+            no_such_key()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_freq_command() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nobject\r\n$4\r\nFREQ\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(Object::try_from(frame)?, Object::Freq(key) if key == "hello"));
+
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        assert!(matches!(
+            Object::Freq("hello".into()).execute(&backend),
+            RespFrame::Error(_)
+        ));
+
+        let backend = Backend::with_eviction(10, EvictionPolicy::AllKeysLfu);
+        backend.set("hello", BulkString::new("world").into());
+        // new keys start at 5, like redis
+        assert_eq!(
+            Object::Freq("hello".into()).execute(&backend),
+            RespFrame::Integer(5)
+        );
+        backend.get("hello");
+        assert_eq!(
+            Object::Freq("hello".into()).execute(&backend),
+            RespFrame::Integer(6)
+        );
+        assert_eq!(
+            Object::Freq("missing".into()).execute(&backend),
+            no_such_key()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, RespNull};
+
+use super::{
+    extract_args, validate_command, CommandError, CommandExecutor, ConnectionExecutor,
+    ConnectionState, PSubscribe, PUnsubscribe, Publish, Subscribe, Unsubscribe,
+};
+
+impl ConnectionExecutor for Subscribe {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        reply_each("subscribe", self.channels, conn, |conn, channel| {
+            conn.subscribe(backend, channel)
+        })
+    }
+}
+
+impl ConnectionExecutor for Unsubscribe {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        let channels = match self.channels.is_empty() {
+            true => conn.channels(),
+            false => self.channels,
+        };
+        reply_each("unsubscribe", channels, conn, |conn, channel| {
+            conn.unsubscribe(backend, channel)
+        })
+    }
+}
+
+impl ConnectionExecutor for PSubscribe {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        reply_each("psubscribe", self.patterns, conn, |conn, pattern| {
+            conn.psubscribe(backend, pattern)
+        })
+    }
+}
+
+impl ConnectionExecutor for PUnsubscribe {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        let patterns = match self.patterns.is_empty() {
+            true => conn.patterns(),
+            false => self.patterns,
+        };
+        reply_each("punsubscribe", patterns, conn, |conn, pattern| {
+            conn.punsubscribe(backend, pattern)
+        })
+    }
+}
+
+impl CommandExecutor for Subscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for Unsubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for PSubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for PUnsubscribe {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl CommandExecutor for Publish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::integer(backend.publish(&self.channel, self.message) as i64)
+    }
+}
+
+// `[kind, channel, count]`, the confirmation redis sends for each (un)subscribed channel
+fn subscription_reply(kind: &str, channel: RespFrame, count: usize) -> RespFrame {
+    RespArray::new([
+        BulkString::from(kind).into(),
+        channel,
+        RespFrame::integer(count as i64),
+    ])
+    .into()
+}
+
+// (Un)subscribe to each name and reply with a confirmation per name: the first one is the
+// reply of the command, the others are sent after it. Without any name (unsubscribing while
+// not subscribed) a single confirmation with a null name is sent.
+fn reply_each(
+    kind: &str,
+    names: Vec<String>,
+    conn: &mut ConnectionState,
+    mut f: impl FnMut(&mut ConnectionState, &str) -> usize,
+) -> RespFrame {
+    if names.is_empty() {
+        return subscription_reply(kind, RespNull.into(), conn.subscription_count());
+    }
+    let mut replies = names
+        .into_iter()
+        .map(|name| {
+            let count = f(conn, &name);
+            subscription_reply(kind, BulkString::from(name).into(), count)
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+    let first = replies.next().expect("names is not empty");
+    replies.for_each(|frame| conn.push_reply(frame));
+    first
+}
+
+fn parse_channels(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|channel| match channel {
+            RespFrame::BulkString(_) => channel.try_into(),
+            _ => Err(CommandError::InvalidArgument("invalid channel".into())),
+        })
+        .collect()
+}
+
+impl TryFrom<RespArray> for Subscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["subscribe"])?;
+        Ok(Subscribe {
+            channels: parse_channels(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Unsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unsubscribe"])?;
+        Ok(Unsubscribe {
+            channels: parse_channels(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PSubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["psubscribe"])?;
+        Ok(PSubscribe {
+            patterns: parse_channels(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PUnsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["punsubscribe"])?;
+        Ok(PUnsubscribe {
+            patterns: parse_channels(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Publish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["publish"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(channel @ RespFrame::BulkString(_)), Some(message)) => Ok(Publish {
+                channel: channel.try_into()?,
+                message,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid channel or message".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::Command;
+    use crate::{RespDecode, RespEncode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_subscribe_try_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Subscribe::try_from(frame)?;
+        assert_eq!(cmd.channels, ["a", "b"]);
+
+        let mut buf = BytesMut::from("*1\r\n$9\r\nsubscribe\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Command::try_from(frame).is_err());
+
+        let mut buf = BytesMut::from("*1\r\n$11\r\nunsubscribe\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Unsubscribe::try_from(frame)?.channels.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_replies_per_channel() {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        let cmd = Subscribe {
+            channels: vec!["a".into(), "b".into()],
+        };
+        let reply = cmd.execute_with(&backend, &mut conn);
+        assert_eq!(
+            reply.encode(),
+            b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n"
+        );
+        let replies: Vec<_> = conn.take_replies().collect();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(
+            replies[0].clone().encode(),
+            b"*3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n"
+        );
+
+        let cmd = Unsubscribe { channels: vec![] };
+        let reply = cmd.execute_with(&backend, &mut conn);
+        assert_eq!(
+            reply.encode(),
+            b"*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n"
+        );
+        assert_eq!(
+            conn.take_replies().next().unwrap().encode(),
+            b"*3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:0\r\n"
+        );
+
+        let cmd = Unsubscribe { channels: vec![] };
+        let reply = cmd.execute_with(&backend, &mut conn).into_resp2();
+        assert_eq!(reply.encode(), b"*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_publish_delivers_message() -> Result<()> {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        conn.subscribe(&backend, "news");
+
+        let cmd = Publish {
+            channel: "news".into(),
+            message: BulkString::from("hello").into(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let message = conn.next_message().await.unwrap();
+        assert_eq!(
+            message.encode(),
+            b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_psubscribe_receives_pmessage() -> Result<()> {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        conn.subscribe(&backend, "news.tech");
+        let cmd = PSubscribe {
+            patterns: vec!["news.*".into()],
+        };
+        let reply = cmd.execute_with(&backend, &mut conn);
+        assert_eq!(
+            reply.encode(),
+            b"*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:2\r\n"
+        );
+
+        let cmd = Publish {
+            channel: "news.tech".into(),
+            message: BulkString::from("hi").into(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(
+            conn.next_message().await.unwrap().encode(),
+            b"*3\r\n$7\r\nmessage\r\n$9\r\nnews.tech\r\n$2\r\nhi\r\n"
+        );
+        assert_eq!(
+            conn.next_message().await.unwrap().encode(),
+            b"*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$9\r\nnews.tech\r\n$2\r\nhi\r\n"
+        );
+
+        let cmd = PUnsubscribe { patterns: vec![] };
+        let reply = cmd.execute_with(&backend, &mut conn);
+        assert_eq!(
+            reply.encode(),
+            b"*3\r\n$12\r\npunsubscribe\r\n$6\r\nnews.*\r\n:1\r\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use tracing::warn;
+
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, RespMap, SimpleError};
+
+use super::{
+    extract_args, glob_match, int_arg, validate_command, CommandError, CommandExecutor, Config,
+    ConnectionExecutor, ConnectionState, Lolwut, Save, Shutdown, Wait, RESP_OK,
+};
+
+impl CommandExecutor for Save {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.snapshot_to(backend.snapshot_path()) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
+}
+
+impl CommandExecutor for Config {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self {
+            // a map, RESP2 connections get it as a flat name/value array
+            Config::Get(pattern) => {
+                let mut ret = RespMap::new();
+                for (name, value) in backend.config_params() {
+                    if glob_match(pattern.as_bytes(), name.as_bytes()) {
+                        ret.insert(name.to_string(), BulkString::new(value).into());
+                    }
+                }
+                ret.into()
+            }
+            Config::Set(name, value) => match backend.config_set(&name, &value) {
+                Ok(()) => RESP_OK.clone(),
+                Err(e) => SimpleError::new(format!("ERR {}", e)).into(),
+            },
+        }
+    }
+}
+
+/// Unlike redis there is no art, only the version line, so the reply is always the same.
+impl CommandExecutor for Lolwut {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        BulkString::from(format!("simple-redis ver. {}\n", env!("CARGO_PKG_VERSION"))).into()
+    }
+}
+
+/// Like redis, a successful `SHUTDOWN` isn't replied to: the connection is closed and the
+/// server stops as on a termination signal. Only failing to save is replied, the server
+/// then keeps running. There are no save points, so without SAVE nothing is written.
+impl ConnectionExecutor for Shutdown {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        if self.save {
+            if let Err(e) = backend.snapshot_to(backend.snapshot_path()) {
+                warn!("shutdown: failed to save the snapshot: {:?}", e);
+                return SimpleError::new("ERR Errors trying to SHUTDOWN. Check logs.").into();
+            }
+        }
+        backend.request_shutdown();
+        conn.close_without_reply();
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for Shutdown {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+/// A stub: the server is standalone, so no replica ever acknowledges the writes and the
+/// reply is 0 right away, whatever the timeout.
+impl CommandExecutor for Wait {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RespFrame::integer(0)
+    }
+}
+
+impl TryFrom<RespArray> for Save {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["save"])?;
+        Ok(Save)
+    }
+}
+
+impl TryFrom<RespArray> for Config {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = match value.len() {
+            3 => {
+                validate_command(&value, &["config", "get"])?;
+                extract_args(value, 2)?
+            }
+            4 => {
+                validate_command(&value, &["config", "set"])?;
+                extract_args(value, 2)?
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "config supports GET <pattern> or SET <parameter> <value>".into(),
+                ))
+            }
+        };
+        let mut args = args.into_iter().map(|arg| match arg {
+            RespFrame::BulkString(_) => String::try_from(arg),
+            _ => Err(CommandError::InvalidArgument("invalid parameter".into())),
+        });
+        // parameter names are case insensitive, values are kept as is
+        match (args.next(), args.next()) {
+            (Some(pattern), None) => Ok(Config::Get(pattern?.to_ascii_lowercase())),
+            (Some(name), Some(value)) => Ok(Config::Set(name?.to_ascii_lowercase(), value?)),
+            _ => unreachable!("config arguments are validated above"),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Lolwut {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // like redis, options such as VERSION are accepted and ignored
+        validate_command(&value, &["lolwut"])?;
+        Ok(Lolwut)
+    }
+}
+
+impl TryFrom<RespArray> for Wait {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["wait"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let (Some(replicas), Some(timeout), None) = (args.next(), args.next(), args.next()) else {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'wait' command".into(),
+            ));
+        };
+        int_arg(replicas)?;
+        if int_arg(timeout)? < 0 {
+            return Err(CommandError::InvalidArgument("timeout is negative".into()));
+        }
+        Ok(Wait)
+    }
+}
+
+impl TryFrom<RespArray> for Shutdown {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["shutdown"])?;
+        let save = match &extract_args(value, 1)?[..] {
+            [] => false,
+            [RespFrame::BulkString(mode)] if mode.eq_ignore_ascii_case(b"nosave") => false,
+            [RespFrame::BulkString(mode)] if mode.eq_ignore_ascii_case(b"save") => true,
+            _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+        };
+        Ok(Shutdown { save })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_wait_command() -> Result<()> {
+        let wait = |args: &[&str]| {
+            let args = ["wait"].iter().chain(args);
+            let args = args.map(|arg| BulkString::from(*arg).into());
+            Wait::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+        };
+        assert_eq!(
+            wait(&["1", "100"])?.execute(&Backend::new()),
+            RespFrame::Integer(0)
+        );
+        assert!(wait(&["1", "-1"]).is_err());
+        assert!(wait(&["one", "0"]).is_err());
+        assert!(wait(&["1"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lolwut_command() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nLOLWUT\r\n$7\r\nversion\r\n$1\r\n5\r\n");
+        let cmd = Lolwut::try_from(RespArray::decode(&mut buf)?)?;
+        assert_eq!(
+            cmd.execute(&Backend::new()),
+            BulkString::from(format!("simple-redis ver. {}\n", env!("CARGO_PKG_VERSION"))).into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_command() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("dump.rdb");
+        let backend = Backend::new();
+        backend.set_snapshot_path(&path);
+        backend.set("hello", BulkString::new("world").into());
+
+        assert_eq!(Save.execute(&backend), RESP_OK.clone());
+
+        let restored = Backend::new();
+        restored.load_from(&path)?;
+        assert_eq!(restored.get("hello"), Some(BulkString::new("world").into()));
+        Ok(())
+    }
+
+    fn shutdown(args: &[&str]) -> Result<Shutdown, CommandError> {
+        let args = ["shutdown"].iter().chain(args);
+        let args = args.map(|arg| BulkString::from(*arg).into());
+        Shutdown::try_from(RespArray::new(args.collect::<Vec<RespFrame>>()))
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_command() -> Result<()> {
+        assert!(shutdown(&["nosave", "save"]).is_err());
+        assert!(shutdown(&["now"]).is_err());
+
+        let dir = tempfile::tempdir()?;
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        // the snapshot can't be written, the server goes on
+        backend.set_snapshot_path(dir.path().join("missing").join("dump.rdb"));
+        let mut conn = ConnectionState::new(1);
+        assert_eq!(
+            shutdown(&["SAVE"])?.execute_with(&backend, &mut conn),
+            SimpleError::new("ERR Errors trying to SHUTDOWN. Check logs.").into()
+        );
+        assert!(!conn.is_closing_without_reply());
+
+        let path = dir.path().join("dump.rdb");
+        backend.set_snapshot_path(&path);
+        shutdown(&["save"])?.execute_with(&backend, &mut conn);
+        assert!(conn.is_closing_without_reply());
+        backend.shutdown_requested().await;
+        assert!(path.exists());
+
+        let backend = Backend::new();
+        backend.set_snapshot_path(dir.path().join("nosave.rdb"));
+        shutdown(&[])?.execute_with(&backend, &mut ConnectionState::new(1));
+        backend.shutdown_requested().await;
+        assert!(!dir.path().join("nosave.rdb").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nCONFIG\r\n$3\r\nGET\r\n$4\r\nsave\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Config::try_from(frame)?;
+        assert!(matches!(cmd, Config::Get(pattern) if pattern == "save"));
+
+        let mut buf = BytesMut::from(
+            "*4\r\n$6\r\nconfig\r\n$3\r\nset\r\n$10\r\nDBFILENAME\r\n$8\r\nDump.RDB\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = Config::try_from(frame)?;
+        assert!(
+            matches!(cmd, Config::Set(name, value) if name == "dbfilename" && value == "Dump.RDB")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_command() {
+        let backend = Backend::new();
+        let cmd = Config::Set("timeout".into(), "30".into());
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        let cmd = Config::Get("time*".into());
+        let mut expected = RespMap::new();
+        expected.insert("timeout".into(), BulkString::from("30").into());
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd = Config::Get("no-such-param".into());
+        assert_eq!(cmd.execute(&backend), RespMap::new().into());
+
+        let cmd = Config::Set("maxmemory".into(), "100".into());
+        assert!(matches!(cmd.execute(&backend), RespFrame::Error(_)));
+    }
+}
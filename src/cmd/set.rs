@@ -0,0 +1,304 @@
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, RespNull};
+
+use super::{
+    extract_args, int_arg, validate_command, wrongtype, CommandError, CommandExecutor, SAdd,
+    SIsMember, SMIsMember, SPop, SRandMember, SRem,
+};
+
+impl CommandExecutor for SAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        RespFrame::integer(backend.sadd(&self.key, self.members) as i64)
+    }
+}
+
+impl CommandExecutor for SIsMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        RespFrame::integer(backend.sismember(&self.key, &self.member) as i64)
+    }
+}
+
+impl CommandExecutor for SMIsMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        let members = backend.smismember(&self.key, self.members.iter().map(String::as_str));
+        let ret = members
+            .into_iter()
+            .map(|found| RespFrame::integer(found as i64))
+            .collect::<Vec<_>>();
+        RespArray::new(ret).into()
+    }
+}
+
+impl CommandExecutor for SRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        let removed = backend.srem(&self.key, self.members.iter().map(String::as_str));
+        RespFrame::integer(removed as i64)
+    }
+}
+
+impl CommandExecutor for SRandMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        match self.count {
+            Some(count) => members_reply(backend.srandmember(&self.key, count)),
+            None => member_reply(backend.srandmember(&self.key, 1)),
+        }
+    }
+}
+
+/// The members are random, so the pop is appended to the aof as the `SREM` of the members
+/// popped, for the aof to replay to the same set.
+impl CommandExecutor for SPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_set(backend, &self.key) {
+            return wrongtype();
+        }
+        let popped = backend.spop(&self.key, self.count.unwrap_or(1));
+        if !popped.is_empty() {
+            let srem = ["srem", &self.key]
+                .into_iter()
+                .chain(popped.iter().map(String::as_str))
+                .map(|arg| BulkString::from(arg).into())
+                .collect::<Vec<RespFrame>>();
+            backend.append_aof(RespArray::new(srem).into());
+        }
+        match self.count {
+            Some(_) => members_reply(popped),
+            None => member_reply(popped),
+        }
+    }
+}
+
+// the only member as a bulk string, null if there is none
+fn member_reply(mut members: Vec<String>) -> RespFrame {
+    match members.pop() {
+        Some(member) => BulkString::from(member).into(),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+fn members_reply(members: Vec<String>) -> RespFrame {
+    let members = members
+        .into_iter()
+        .map(|member| BulkString::from(member).into())
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(members).into()
+}
+
+impl TryFrom<RespArray> for SAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["sadd"])?;
+        let (key, members) = parse_key_and_members(value)?;
+        Ok(SAdd { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SIsMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["sismember"])?;
+        let (key, mut members) = parse_key_and_members(value)?;
+        Ok(SIsMember {
+            key,
+            member: members.remove(0),
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SMIsMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["smismember"])?;
+        let (key, members) = parse_key_and_members(value)?;
+        Ok(SMIsMember { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SRem {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["srem"])?;
+        let (key, members) = parse_key_and_members(value)?;
+        Ok(SRem { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SRandMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["srandmember"])?;
+        let (key, count) = parse_key_and_count(value)?;
+        Ok(SRandMember { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for SPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["spop"])?;
+        let (key, count) = parse_key_and_count(value)?;
+        let count = count.map(usize::try_from).transpose().map_err(|_| {
+            CommandError::InvalidArgument("value is out of range, must be positive".into())
+        })?;
+        Ok(SPop { key, count })
+    }
+}
+
+// the key holds a value of another type than set
+fn is_not_set(backend: &Backend, key: &str) -> bool {
+    backend.key_type(key).is_some_and(|t| t != "set")
+}
+
+fn parse_key_and_members(value: RespArray) -> Result<(String, Vec<String>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter().map(|arg| match arg {
+        RespFrame::BulkString(_) => arg.try_into(),
+        _ => Err(CommandError::InvalidArgument(
+            "invalid key or member".into(),
+        )),
+    });
+    let key = args
+        .next()
+        .ok_or_else(|| CommandError::InvalidArgument("missing key".into()))??;
+    Ok((key, args.collect::<Result<_, _>>()?))
+}
+
+// `key [count]`
+fn parse_key_and_count(value: RespArray) -> Result<(String, Option<i64>), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = match args.next() {
+        Some(key @ RespFrame::BulkString(_)) => key.try_into()?,
+        _ => return Err(CommandError::InvalidArgument("invalid key".into())),
+    };
+    let count = args.next().map(int_arg).transpose()?;
+    if args.next().is_some() {
+        return Err(CommandError::InvalidArgument("syntax error".into()));
+    }
+    Ok((key, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::Command;
+    use crate::{BulkString, RespDecode};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_smismember_from_resp_array() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$10\r\nsmismember\r\n$1\r\ns\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = SMIsMember::try_from(frame)?;
+        assert_eq!(cmd.key, "s");
+        assert_eq!(cmd.members, ["a", "b"]);
+
+        let mut buf = BytesMut::from("*2\r\n$10\r\nsmismember\r\n$1\r\ns\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Command::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_smismember_command() {
+        let backend = Backend::new();
+        let cmd = SAdd {
+            key: "s".into(),
+            members: vec!["a".into(), "c".into()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        let smismember = |key: &str| {
+            SMIsMember {
+                key: key.into(),
+                members: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            }
+            .execute(&backend)
+        };
+        let expected: RespFrame = RespArray::new([
+            RespFrame::Integer(1),
+            RespFrame::Integer(0),
+            RespFrame::Integer(1),
+            RespFrame::Integer(0),
+        ])
+        .into();
+        assert_eq!(smismember("s"), expected);
+
+        let expected: RespFrame = RespArray::new(vec![RespFrame::Integer(0); 4]).into();
+        assert_eq!(smismember("missing"), expected);
+
+        backend.set("str", BulkString::new("value").into());
+        assert_eq!(smismember("str"), wrongtype());
+    }
+
+    fn command(args: &[&str]) -> Result<Command> {
+        let args = args.iter().map(|arg| BulkString::from(*arg).into());
+        Ok(Command::try_from(RespArray::new(args.collect::<Vec<_>>()))?)
+    }
+
+    #[test]
+    fn test_srandmember_and_spop_commands() -> Result<()> {
+        let backend = Backend::new();
+        let null = RespFrame::Null(RespNull);
+        assert_eq!(command(&["srandmember", "s"])?.execute(&backend), null);
+        assert_eq!(command(&["spop", "s"])?.execute(&backend), null);
+        assert_eq!(
+            command(&["spop", "s", "2"])?.execute(&backend),
+            RespArray::new([]).into()
+        );
+
+        backend.sadd("s", ["a".into()]);
+        let a: RespFrame = BulkString::from("a").into();
+        // SRANDMEMBER leaves the member, SPOP removes it
+        assert_eq!(command(&["srandmember", "s"])?.execute(&backend), a);
+        assert_eq!(
+            command(&["srandmember", "s", "-2"])?.execute(&backend),
+            RespArray::new([a.clone(), a.clone()]).into()
+        );
+        assert!(backend.sismember("s", "a"));
+        assert_eq!(command(&["spop", "s"])?.execute(&backend), a);
+        assert!(!backend.sismember("s", "a"));
+        assert_eq!(backend.key_type("s"), None);
+
+        assert!(command(&["spop", "s", "-1"]).is_err());
+        assert!(command(&["srandmember", "s", "1", "2"]).is_err());
+        backend.set("str", BulkString::new("value").into());
+        assert_eq!(command(&["spop", "str"])?.execute(&backend), wrongtype());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sismember_command() {
+        let backend = Backend::new();
+        backend.sadd("s", ["a".into()]);
+        let sismember = |member: &str| {
+            SIsMember {
+                key: "s".into(),
+                member: member.into(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(sismember("a"), RespFrame::Integer(1));
+        assert_eq!(sismember("b"), RespFrame::Integer(0));
+    }
+}
@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, SimpleError};
+
+use super::{
+    extract_args, int_arg, validate_command, wrongtype, CommandError, CommandExecutor, Sort,
+};
+
+impl CommandExecutor for Sort {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let mut elements: Vec<Vec<u8>> = match backend.key_type(&self.key) {
+            None => vec![],
+            Some("list") => backend
+                .list_elements(&self.key)
+                .unwrap_or_default()
+                .into_iter()
+                .map(element_bytes)
+                .collect(),
+            Some("set") => backend
+                .smembers(&self.key)
+                .unwrap_or_default()
+                .into_iter()
+                .map(String::into_bytes)
+                .collect(),
+            Some("zset") => backend
+                .zrange(&self.key, 0, -1)
+                .into_iter()
+                .map(|(member, _)| member.into_bytes())
+                .collect(),
+            Some(_) => return wrongtype(),
+        };
+
+        if self.alpha {
+            elements.sort_by(|a, b| order(a.cmp(b), self.desc));
+        } else {
+            let Some(mut scored) = elements
+                .into_iter()
+                .map(|element| Some((parse_score(&element)?, element)))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return SimpleError::new("ERR One or more scores can't be converted into double")
+                    .into();
+            };
+            // equal scores are ordered by the elements, so the reply is deterministic
+            scored
+                .sort_by(|(a, x), (b, y)| order(a.total_cmp(b).then_with(|| x.cmp(y)), self.desc));
+            elements = scored.into_iter().map(|(_, element)| element).collect();
+        }
+
+        let (offset, count) = match self.limit {
+            Some((offset, count)) => (
+                offset.max(0) as usize,
+                usize::try_from(count).unwrap_or(usize::MAX),
+            ),
+            None => (0, usize::MAX),
+        };
+        let ret = elements
+            .into_iter()
+            .skip(offset)
+            .take(count)
+            .map(|element| BulkString::new(element).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
+    }
+}
+
+fn order(ordering: Ordering, desc: bool) -> Ordering {
+    if desc {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+// the bytes of a list element, which is pushed as a bulk string
+fn element_bytes(element: RespFrame) -> Vec<u8> {
+    match element {
+        RespFrame::BulkString(s) => s.to_vec(),
+        RespFrame::SimpleString(s) => s.as_bytes().to_vec(),
+        RespFrame::Integer(n) => n.to_string().into_bytes(),
+        _ => vec![],
+    }
+}
+
+// the value of an element sorted numerically: like redis, any float but NaN, e.g. `inf`
+fn parse_score(element: &[u8]) -> Option<f64> {
+    std::str::from_utf8(element)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|score| !score.is_nan())
+}
+
+impl TryFrom<RespArray> for Sort {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["sort"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut sort = match args.next() {
+            Some(key @ RespFrame::BulkString(_)) => Sort {
+                key: key.try_into()?,
+                alpha: false,
+                desc: false,
+                limit: None,
+            },
+            _ => return Err(CommandError::InvalidArgument("invalid key".into())),
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".into());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(arg) = arg else {
+                return Err(syntax_error());
+            };
+            match arg.to_ascii_lowercase().as_slice() {
+                b"alpha" => sort.alpha = true,
+                b"asc" => sort.desc = false,
+                b"desc" => sort.desc = true,
+                b"limit" => match (args.next(), args.next()) {
+                    (Some(offset), Some(count)) => {
+                        sort.limit = Some((int_arg(offset)?, int_arg(count)?))
+                    }
+                    _ => return Err(syntax_error()),
+                },
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(sort)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::ListEnd, cmd::Command};
+    use anyhow::Result;
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    fn sort(backend: &Backend, args: &[&str]) -> Result<RespFrame> {
+        let args = ["sort"].iter().chain(args).map(|arg| bulk(arg));
+        Ok(Command::try_from(RespArray::new(args.collect::<Vec<_>>()))?.execute(backend))
+    }
+
+    fn array(elements: &[&str]) -> RespFrame {
+        RespArray::new(elements.iter().map(|e| bulk(e)).collect::<Vec<_>>()).into()
+    }
+
+    #[test]
+    fn test_sort_numeric() -> Result<()> {
+        let backend = Backend::new();
+        let values = ["3", "-1.5", "10", "2"].map(bulk);
+        backend.push("l", ListEnd::Right, values);
+        assert_eq!(sort(&backend, &["l"])?, array(&["-1.5", "2", "3", "10"]));
+        assert_eq!(
+            sort(&backend, &["l", "DESC"])?,
+            array(&["10", "3", "2", "-1.5"])
+        );
+
+        backend.sadd("s", ["5".into(), "1".into(), "3".into()]);
+        assert_eq!(sort(&backend, &["s"])?, array(&["1", "3", "5"]));
+        assert_eq!(sort(&backend, &["missing"])?, array(&[]));
+
+        backend.push("words", ListEnd::Right, [bulk("1"), bulk("b")]);
+        assert_eq!(
+            sort(&backend, &["words"])?,
+            SimpleError::new("ERR One or more scores can't be converted into double").into()
+        );
+
+        backend.set("str", bulk("1"));
+        assert_eq!(sort(&backend, &["str"])?, wrongtype());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_alpha() -> Result<()> {
+        let backend = Backend::new();
+        backend.sadd("s", ["b".into(), "10".into(), "a".into(), "9".into()]);
+        assert_eq!(
+            sort(&backend, &["s", "alpha"])?,
+            array(&["10", "9", "a", "b"])
+        );
+        assert_eq!(
+            sort(&backend, &["s", "ALPHA", "desc"])?,
+            array(&["b", "a", "9", "10"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_limit() -> Result<()> {
+        let backend = Backend::new();
+        let values = ["5", "4", "3", "2", "1"].map(bulk);
+        backend.push("l", ListEnd::Right, values);
+        assert_eq!(
+            sort(&backend, &["l", "LIMIT", "1", "2"])?,
+            array(&["2", "3"])
+        );
+        assert_eq!(
+            sort(&backend, &["l", "limit", "3", "-1", "desc"])?,
+            array(&["2", "1"])
+        );
+        assert_eq!(sort(&backend, &["l", "limit", "-2", "1"])?, array(&["1"]));
+        assert_eq!(sort(&backend, &["l", "limit", "9", "1"])?, array(&[]));
+
+        assert!(sort(&backend, &["l", "limit", "1"]).is_err());
+        assert!(sort(&backend, &["l", "by"]).is_err());
+        Ok(())
+    }
+}
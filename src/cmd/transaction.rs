@@ -0,0 +1,194 @@
+use crate::{backend::Backend, RespArray, RespFrame, SimpleError};
+
+use super::{
+    extract_args, validate_command, Command, CommandError, CommandExecutor, ConnectionExecutor,
+    ConnectionState, Discard, Exec, Multi, Unwatch, Watch, RESP_OK,
+};
+
+/// Commands queued by a connection between `MULTI` and `EXEC`, with the requests they were
+/// parsed from.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    pub commands: Vec<(Command, RespFrame)>,
+    /// a command was rejected while queuing, `EXEC` discards the transaction
+    pub failed: bool,
+}
+
+impl ConnectionExecutor for Multi {
+    fn execute_with(self, _backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        if !conn.begin_transaction() {
+            return SimpleError::new("ERR MULTI calls can not be nested").into();
+        }
+        RESP_OK.clone()
+    }
+}
+
+impl ConnectionExecutor for Discard {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        if conn.take_transaction().is_none() {
+            return SimpleError::new("ERR DISCARD without MULTI").into();
+        }
+        conn.unwatch(backend);
+        RESP_OK.clone()
+    }
+}
+
+impl ConnectionExecutor for Unwatch {
+    fn execute_with(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        conn.unwatch(backend);
+        RESP_OK.clone()
+    }
+}
+
+impl Watch {
+    /// Watch the keys for the next `EXEC` of the connection. No write is in progress while
+    /// the watch starts, so a write either happened before or is signalled.
+    pub async fn execute_async(self, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+        if conn.in_transaction() {
+            return SimpleError::new("ERR WATCH inside MULTI is not allowed").into();
+        }
+        let _writes = backend.lock_writes_exclusive().await;
+        for key in self.keys {
+            conn.watch(backend, key);
+        }
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for Multi {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+/// Connections run `EXEC` with the queue of their transaction, without one there is
+/// nothing to execute.
+impl CommandExecutor for Exec {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        SimpleError::new("ERR EXEC without MULTI").into()
+    }
+}
+
+impl CommandExecutor for Discard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+/// A fresh connection never executes a transaction, so there is nothing to watch for.
+impl CommandExecutor for Watch {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for Unwatch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.execute_with(backend, &mut ConnectionState::new(0))
+    }
+}
+
+impl TryFrom<RespArray> for Multi {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["multi"])?;
+        Ok(Multi)
+    }
+}
+
+impl TryFrom<RespArray> for Exec {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["exec"])?;
+        Ok(Exec)
+    }
+}
+
+impl TryFrom<RespArray> for Discard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["discard"])?;
+        Ok(Discard)
+    }
+}
+
+impl TryFrom<RespArray> for Watch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["watch"])?;
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|key| match key {
+                RespFrame::BulkString(_) => key.try_into(),
+                _ => Err(CommandError::InvalidArgument("invalid key".into())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Watch { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Unwatch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unwatch"])?;
+        Ok(Unwatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_multi_and_discard() {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        assert_eq!(
+            Discard.execute_with(&backend, &mut conn),
+            SimpleError::new("ERR DISCARD without MULTI").into()
+        );
+        assert_eq!(Multi.execute_with(&backend, &mut conn), RESP_OK.clone());
+        assert!(conn.in_transaction());
+        assert_eq!(
+            Multi.execute_with(&backend, &mut conn),
+            SimpleError::new("ERR MULTI calls can not be nested").into()
+        );
+        assert_eq!(Discard.execute_with(&backend, &mut conn), RESP_OK.clone());
+        assert!(!conn.in_transaction());
+    }
+
+    #[tokio::test]
+    async fn test_watch_is_ended_by_unwatch() {
+        let backend = Backend::new();
+        let mut conn = ConnectionState::new(1);
+        let cmd = Watch {
+            keys: vec!["a".into(), "a".into(), "b".into()],
+        };
+        assert_eq!(
+            cmd.execute_async(&backend, &mut conn).await,
+            RESP_OK.clone()
+        );
+        backend.set("a", BulkString::from("1").into());
+        backend.signal_modified(Some(&["a".to_string()]));
+
+        // the write is reported once, then the keys are no longer watched
+        assert!(conn.unwatch(&backend));
+        assert!(!conn.unwatch(&backend));
+        assert!(!backend.has_watchers());
+
+        conn.begin_transaction();
+        let cmd = Watch {
+            keys: vec!["a".into()],
+        };
+        assert_eq!(
+            cmd.execute_async(&backend, &mut conn).await,
+            SimpleError::new("ERR WATCH inside MULTI is not allowed").into()
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{backend::Backend, BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+use super::{
+    extract_args, int_arg,
+    num::{format_float, parse_float},
+    parse_key, validate_command, wrongtype, CommandError, CommandExecutor, ZAdd, ZCard, ZIncrBy,
+    ZRange, ZRank, ZScore,
+};
+
+impl CommandExecutor for ZAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        RespFrame::integer(backend.zadd(&self.key, self.members) as i64)
+    }
+}
+
+impl CommandExecutor for ZScore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        match backend.zscore(&self.key, &self.member) {
+            Some(score) => BulkString::from(format_float(score)).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for ZIncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        match backend.zincrby(&self.key, self.increment, &self.member) {
+            Some(score) => BulkString::from(format_float(score)).into(),
+            None => SimpleError::new("ERR resulting score is not a number (NaN)").into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZRank {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        match backend.zrank(&self.key, &self.member) {
+            Some(rank) => RespFrame::integer(rank as i64),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for ZRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        let ret = backend
+            .zrange(&self.key, self.start, self.stop)
+            .into_iter()
+            .flat_map(|(member, score)| {
+                let score = self
+                    .withscores
+                    .then(|| BulkString::from(format_float(score)).into());
+                std::iter::once(BulkString::from(member).into()).chain(score)
+            })
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(ret).into()
+    }
+}
+
+impl CommandExecutor for ZCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if is_not_zset(backend, &self.key) {
+            return wrongtype();
+        }
+        RespFrame::integer(backend.zcard(&self.key) as i64)
+    }
+}
+
+impl TryFrom<RespArray> for ZAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zadd"])?;
+        if !value.len().is_multiple_of(2) {
+            return Err(CommandError::InvalidArgument("syntax error".into()));
+        }
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let mut members = Vec::new();
+        while let Some(score) = args.next() {
+            let score = match score {
+                RespFrame::BulkString(score) => parse_score(&score)?,
+                _ => return Err(CommandError::NotAFloat),
+            };
+            members.push((score, parse_string(args.next())?));
+        }
+        Ok(ZAdd { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for ZScore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zscore"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZScore {
+            key: parse_string(args.next())?,
+            member: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZIncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zincrby"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let increment = match args.next() {
+            Some(RespFrame::BulkString(increment)) => parse_score(&increment)?,
+            _ => return Err(CommandError::NotAFloat),
+        };
+        Ok(ZIncrBy {
+            key,
+            increment,
+            member: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZRank {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zrank"])?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZRank {
+            key: parse_string(args.next())?,
+            member: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let withscores = match value.len() {
+            4 => {
+                validate_command(&value, &["zrange"])?;
+                false
+            }
+            5 => {
+                validate_command(&value, &["zrange"])?;
+                match value[4] {
+                    RespFrame::BulkString(ref flag) if flag.eq_ignore_ascii_case(b"withscores") => {
+                        true
+                    }
+                    _ => return Err(CommandError::InvalidArgument("syntax error".into())),
+                }
+            }
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "zrange command must have 3 or 4 arguments".into(),
+                ))
+            }
+        };
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        match (args.next(), args.next()) {
+            (Some(start), Some(stop)) => Ok(ZRange {
+                key,
+                start: int_arg(start)?,
+                stop: int_arg(stop)?,
+                withscores,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "invalid start or stop".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ZCard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ZCard {
+            key: parse_key(value, "zcard")?,
+        })
+    }
+}
+
+// the key holds a value of another type than sorted set
+fn is_not_zset(backend: &Backend, key: &str) -> bool {
+    backend.key_type(key).is_some_and(|t| t != "zset")
+}
+
+// a score is a float, infinities included like redis
+fn parse_score(data: &[u8]) -> Result<f64, CommandError> {
+    match data.to_ascii_lowercase().as_slice() {
+        b"inf" | b"+inf" => Ok(f64::INFINITY),
+        b"-inf" => Ok(f64::NEG_INFINITY),
+        _ => parse_float(data),
+    }
+}
+
+fn parse_string(arg: Option<RespFrame>) -> Result<String, CommandError> {
+    match arg {
+        Some(arg @ RespFrame::BulkString(_)) => arg.try_into(),
+        _ => Err(CommandError::InvalidArgument(
+            "invalid key or member".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespDecode;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_zadd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from(
+            "*6\r\n$4\r\nzadd\r\n$1\r\nz\r\n$3\r\n1.5\r\n$1\r\na\r\n$4\r\n-inf\r\n$1\r\nb\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = ZAdd::try_from(frame)?;
+        assert_eq!(cmd.key, "z");
+        assert_eq!(
+            cmd.members,
+            vec![(1.5, "a".to_string()), (f64::NEG_INFINITY, "b".to_string())]
+        );
+
+        // a score without its member
+        let mut buf =
+            BytesMut::from("*5\r\n$4\r\nzadd\r\n$1\r\nz\r\n$1\r\n1\r\n$1\r\na\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(ZAdd::try_from(frame).is_err());
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\nzadd\r\n$1\r\nz\r\n$3\r\nabc\r\n$1\r\na\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(matches!(
+            ZAdd::try_from(frame),
+            Err(CommandError::NotAFloat)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrange_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::from(
+            "*5\r\n$6\r\nzrange\r\n$1\r\nz\r\n$2\r\n-2\r\n$2\r\n-1\r\n$10\r\nWITHSCORES\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd = ZRange::try_from(frame)?;
+        assert_eq!((cmd.start, cmd.stop), (-2, -1));
+        assert!(cmd.withscores);
+        Ok(())
+    }
+
+    fn leaderboard() -> Backend {
+        let backend = Backend::new();
+        let cmd = ZAdd {
+            key: "board".into(),
+            members: vec![
+                (10.0, "alice".into()),
+                (5.0, "bob".into()),
+                (7.5, "carol".into()),
+                (5.0, "adam".into()),
+            ],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        backend
+    }
+
+    fn zrange(backend: &Backend, start: i64, stop: i64, withscores: bool) -> RespFrame {
+        ZRange {
+            key: "board".into(),
+            start,
+            stop,
+            withscores,
+        }
+        .execute(backend)
+    }
+
+    fn bulk_strings(values: &[&str]) -> RespFrame {
+        let values = values
+            .iter()
+            .map(|v| BulkString::from(*v).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(values).into()
+    }
+
+    #[test]
+    fn test_zrange_negative_indices() {
+        let backend = leaderboard();
+        // ties are ordered by member
+        assert_eq!(
+            zrange(&backend, 0, -1, false),
+            bulk_strings(&["adam", "bob", "carol", "alice"])
+        );
+        assert_eq!(
+            zrange(&backend, -2, -1, false),
+            bulk_strings(&["carol", "alice"])
+        );
+        assert_eq!(
+            zrange(&backend, -100, 1, false),
+            bulk_strings(&["adam", "bob"])
+        );
+        assert_eq!(zrange(&backend, 3, 1, false), bulk_strings(&[]));
+    }
+
+    #[test]
+    fn test_zrange_withscores() {
+        let backend = leaderboard();
+        assert_eq!(
+            zrange(&backend, 1, 2, true),
+            bulk_strings(&["bob", "5", "carol", "7.5"])
+        );
+    }
+
+    #[test]
+    fn test_zscore_and_zcard() {
+        let backend = leaderboard();
+        let zscore = |member: &str| {
+            ZScore {
+                key: "board".into(),
+                member: member.into(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(zscore("carol"), BulkString::from("7.5").into());
+        assert_eq!(zscore("dave"), RespFrame::Null(RespNull));
+
+        let zcard = |key: &str| ZCard { key: key.into() }.execute(&backend);
+        assert_eq!(zcard("board"), RespFrame::Integer(4));
+        assert_eq!(zcard("missing"), RespFrame::Integer(0));
+
+        backend.set("str", BulkString::new("value").into());
+        assert_eq!(zcard("str"), wrongtype());
+    }
+
+    #[test]
+    fn test_zincrby_reorders_zrank() -> Result<()> {
+        let backend = leaderboard();
+        let zrank = |member: &str| {
+            ZRank {
+                key: "board".into(),
+                member: member.into(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(zrank("bob"), RespFrame::Integer(1));
+        assert_eq!(zrank("dave"), RespFrame::Null(RespNull));
+
+        let mut buf =
+            BytesMut::from("*4\r\n$7\r\nzincrby\r\n$5\r\nboard\r\n$3\r\n5.5\r\n$3\r\nbob\r\n");
+        let cmd = ZIncrBy::try_from(RespArray::decode(&mut buf)?)?;
+        assert_eq!(cmd.execute(&backend), BulkString::from("10.5").into());
+        assert_eq!(zrank("bob"), RespFrame::Integer(3));
+        assert_eq!(zrank("alice"), RespFrame::Integer(2));
+        assert_eq!(
+            zrange(&backend, 0, -1, false),
+            bulk_strings(&["adam", "carol", "alice", "bob"])
+        );
+
+        let cmd = ZIncrBy {
+            key: "board".into(),
+            increment: f64::NEG_INFINITY,
+            member: "inf".into(),
+        };
+        cmd.execute(&backend);
+        let cmd = ZIncrBy {
+            key: "board".into(),
+            increment: f64::INFINITY,
+            member: "inf".into(),
+        };
+        assert!(matches!(cmd.execute(&backend), RespFrame::Error(_)));
+        Ok(())
+    }
+}
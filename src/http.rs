@@ -0,0 +1,138 @@
+use crate::backend::Backend;
+use anyhow::Result;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// requests are tiny, anything larger is rejected
+const MAX_REQUEST_LEN: usize = 8 * 1024;
+// a backend that doesn't answer within this delay is reported unhealthy
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+// a client that hasn't sent its request within this delay is disconnected
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+const TEXT_PLAIN: &str = "text/plain";
+
+/// Serve a single HTTP/1.1 request, then close the connection. `GET /health` replies
+/// `200 OK` when the backend answers a ping, `503` otherwise. With the `metrics` feature,
+/// `GET /metrics` replies the metrics in the Prometheus format. The connection is closed
+/// without a reply if the request isn't read within a few seconds.
+pub async fn http_handler<S>(mut stream: S, backend: Backend) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Ok(request_line) = tokio::time::timeout(READ_TIMEOUT, read_request_line(&mut stream)).await
+    else {
+        return Ok(());
+    };
+    let (status, content_type, body): (_, _, String) = match request_line? {
+        Some(line) => match line.split(' ').collect::<Vec<_>>().as_slice() {
+            ["GET", "/health", _] if ping(&backend).await => ("200 OK", TEXT_PLAIN, "OK\n".into()),
+            ["GET", "/health", _] => (
+                "503 Service Unavailable",
+                TEXT_PLAIN,
+                "backend unavailable\n".into(),
+            ),
+            #[cfg(feature = "metrics")]
+            ["GET", "/metrics", _] => (
+                "200 OK",
+                crate::metrics::CONTENT_TYPE,
+                crate::metrics::render(&backend),
+            ),
+            ["GET", _, _] => ("404 Not Found", TEXT_PLAIN, "not found\n".into()),
+            [_, _, _] => (
+                "405 Method Not Allowed",
+                TEXT_PLAIN,
+                "method not allowed\n".into(),
+            ),
+            _ => ("400 Bad Request", TEXT_PLAIN, "bad request\n".into()),
+        },
+        None => ("400 Bad Request", TEXT_PLAIN, "bad request\n".into()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+// the request line once the headers are fully read, `None` for a malformed request
+async fn read_request_line<S>(stream: &mut S) -> Result<Option<String>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::with_capacity(512);
+    let mut chunk = [0u8; 512];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 || buf.len() + n > MAX_REQUEST_LEN {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let end = buf.windows(2).position(|w| w == b"\r\n").unwrap_or(0);
+    Ok(String::from_utf8(buf[..end].to_vec()).ok())
+}
+
+// the backend is healthy if its keyspace can be read in time, the maps being locked
+// while counting the keys
+async fn ping(backend: &Backend) -> bool {
+    let backend = backend.clone();
+    let count = tokio::task::spawn_blocking(move || backend.key_count());
+    matches!(tokio::time::timeout(PING_TIMEOUT, count).await, Ok(Ok(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    async fn request(raw: &[u8]) -> Result<String> {
+        let (mut client, server) = duplex(1024);
+        let handler = tokio::spawn(http_handler(server, Backend::new()));
+        client.write_all(raw).await?;
+        let mut response = String::new();
+        client.read_to_string(&mut response).await?;
+        handler.await??;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_health_check() -> Result<()> {
+        let response = request(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n").await?;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nOK\n"));
+
+        let response = request(b"GET /other HTTP/1.1\r\n\r\n").await?;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let response = request(b"POST /health HTTP/1.1\r\n\r\n").await?;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        let response = request(b"garbage\r\n\r\n").await?;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_client_is_disconnected() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        let handler = tokio::spawn(http_handler(server, Backend::new()));
+        client.write_all(b"GET /health").await?;
+        handler.await??;
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await?;
+        assert!(response.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_metrics() -> Result<()> {
+        let response = request(b"GET /metrics HTTP/1.1\r\n\r\n").await?;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+        assert!(response.contains("\nredis_keys_total 0\n"));
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 mod backend;
 mod cmd;
+pub mod http;
+#[cfg(feature = "metrics")]
+mod metrics;
 pub mod network;
 mod resp;
 
-pub use backend::Backend;
+pub use backend::{Backend, EvictionPolicy, Store};
 pub use resp::*;
@@ -1,25 +1,176 @@
-use anyhow::Result;
-use simple_redis::{network, Backend};
-use tokio::net::TcpListener;
+use std::{
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use simple_redis::{http, network, Backend, EvictionPolicy};
+use tokio::net::{TcpListener, UnixListener};
 use tracing::{info, warn};
 
+#[derive(Debug, Parser)]
+#[command(version, about = "A simple redis server")]
+struct Args {
+    /// TCP address to listen on
+    #[arg(long, default_value = "0.0.0.0:6379")]
+    addr: String,
+    /// Unix socket path to listen on, in addition to TCP
+    #[arg(long)]
+    unixsocket: Option<PathBuf>,
+    /// TCP address of an HTTP listener answering `GET /health` for liveness probes and
+    /// `GET /metrics` for Prometheus, disabled by default
+    #[arg(long)]
+    http_addr: Option<String>,
+    /// Append-only file to persist write commands to, replayed on startup
+    #[arg(long)]
+    aof: Option<PathBuf>,
+    /// Snapshot file written by SAVE, loaded on startup when aof is disabled
+    #[arg(long, default_value = "dump.rdb")]
+    dbfilename: PathBuf,
+    /// Maximum number of keys, keys are evicted beyond it following `maxmemory_policy`
+    #[arg(long)]
+    maxkeys: Option<usize>,
+    /// Keys evicted beyond `maxkeys`: allkeys-lru (least recently used) or allkeys-lfu
+    /// (least frequently used)
+    #[arg(long, default_value = "allkeys-lru")]
+    maxmemory_policy: EvictionPolicy,
+    /// Interval in milliseconds between active expiration cycles
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    active_expire_interval: u64,
+    /// Number of expired keys removed per batch in an active expiration cycle
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+    active_expire_batch: u64,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
+    let args = Args::parse();
+
+    let backend = match args.maxkeys {
+        Some(n) => Backend::with_eviction(n, args.maxmemory_policy),
+        None => Backend::new(),
+    };
+    let backend = match &args.aof {
+        Some(path) => backend.with_aof(path)?,
+        None => {
+            if args.dbfilename.exists() {
+                let n = backend.load_from(&args.dbfilename)?;
+                info!("Loaded {} keys from {}", n, args.dbfilename.display());
+            }
+            backend
+        }
+    };
+    backend.set_snapshot_path(&args.dbfilename);
+    backend.spawn_expiration_reaper(
+        Duration::from_millis(args.active_expire_interval),
+        args.active_expire_batch as usize,
+    );
+
+    info!("Simple-Redis-Server is listening on {}", args.addr);
+    let listener = TcpListener::bind(&args.addr).await?;
+    tokio::spawn(tcp_accept_loop(listener, backend.clone()));
+
+    if let Some(path) = &args.unixsocket {
+        remove_stale_socket(path)?;
+        info!("Simple-Redis-Server is listening on {}", path.display());
+        let listener = UnixListener::bind(path)?;
+        // like redis, unix socket clients are listed as `<path>:0`
+        let addr = format!("{}:0", path.display());
+        tokio::spawn(unix_accept_loop(listener, backend.clone(), addr));
+    }
+
+    if let Some(addr) = &args.http_addr {
+        info!("HTTP endpoints are listening on {}", addr);
+        let listener = TcpListener::bind(addr).await?;
+        tokio::spawn(http_accept_loop(listener, backend.clone()));
+    }
 
-    let addr = "0.0.0.0:6379";
-    info!("Simple-Redis-Server is listening on {}", addr);
-    let listener = TcpListener::bind(addr).await?;
+    // SHUTDOWN ends the server like the signal
+    tokio::select! {
+        signal = tokio::signal::ctrl_c() => signal?,
+        _ = backend.shutdown_requested() => {}
+    }
+    info!("Shutting down");
+    if let Some(path) = &args.unixsocket {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
 
-    let backend = Backend::new();
+// A stale socket file left by a previous run would make bind fail. Anything else at the
+// path, e.g. a regular file given by mistake, is left alone and reported.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => Ok(std::fs::remove_file(path)?),
+        Ok(_) => bail!("{} exists and is not a unix socket", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// pause after a failed accept, errors like EMFILE would otherwise repeat right away
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// accept errors are logged and don't stop the listener, like redis
+async fn tcp_accept_loop(listener: TcpListener, backend: Backend) {
     loop {
-        let (stream, raddr) = listener.accept().await?;
+        let (stream, raddr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("failed to accept tcp connection: {:?}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
         info!("Accepted connection from: {}", raddr);
         let cloned_backend = backend.clone();
         tokio::spawn(async move {
-            if let Err(e) = network::stream_handler(stream, cloned_backend).await {
+            if let Err(e) = network::stream_handler(stream, cloned_backend, raddr.to_string()).await
+            {
                 warn!("handle error for {}: {:?}", raddr, e);
             }
         });
     }
 }
+
+async fn unix_accept_loop(listener: UnixListener, backend: Backend, addr: String) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("failed to accept unix socket connection: {:?}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        info!("Accepted connection from unix socket");
+        let (cloned_backend, addr) = (backend.clone(), addr.clone());
+        tokio::spawn(async move {
+            if let Err(e) = network::stream_handler(stream, cloned_backend, addr).await {
+                warn!("handle error for unix socket: {:?}", e);
+            }
+        });
+    }
+}
+
+async fn http_accept_loop(listener: TcpListener, backend: Backend) {
+    loop {
+        let (stream, raddr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("failed to accept http connection: {:?}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let cloned_backend = backend.clone();
+        tokio::spawn(async move {
+            if let Err(e) = http::http_handler(stream, cloned_backend).await {
+                warn!("http error for {}: {:?}", raddr, e);
+            }
+        });
+    }
+}
@@ -0,0 +1,91 @@
+//! Metrics in the Prometheus text exposition format, served on `GET /metrics` by the HTTP
+//! listener. The names are stable, dashboards can rely on them:
+//!
+//! - `redis_commands_total{cmd="<name>"}` (counter): calls of each command, by lowercase name
+//! - `redis_commands_processed_total` (counter): commands processed, all commands together
+//! - `redis_connections_active` (gauge): clients currently connected
+//! - `redis_keys_total` (gauge): keys in the keyspace, expired keys not yet reaped included
+//! - `redis_uptime_seconds` (gauge): seconds since the server started
+//!
+//! The values are read from the same counters as `INFO`.
+
+use crate::backend::Backend;
+use std::fmt::Write;
+
+/// Content type of the exposition format, for the HTTP response.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Render the current metrics of the backend.
+pub fn render(backend: &Backend) -> String {
+    let mut out = String::new();
+    metric_header(
+        &mut out,
+        "redis_commands_total",
+        "counter",
+        "Calls of each command.",
+    );
+    for (name, calls) in backend.command_stats() {
+        let _ = writeln!(out, "redis_commands_total{{cmd=\"{name}\"}} {calls}");
+    }
+    let metrics = [
+        (
+            "redis_commands_processed_total",
+            "counter",
+            "Commands processed.",
+            backend.commands_processed(),
+        ),
+        (
+            "redis_connections_active",
+            "gauge",
+            "Clients currently connected.",
+            backend.connected_clients(),
+        ),
+        (
+            "redis_keys_total",
+            "gauge",
+            "Keys in the keyspace.",
+            backend.key_count() as u64,
+        ),
+        (
+            "redis_uptime_seconds",
+            "gauge",
+            "Seconds since the server started.",
+            backend.uptime().as_secs(),
+        ),
+    ];
+    for (name, kind, help, value) in metrics {
+        metric_header(&mut out, name, kind, help);
+        let _ = writeln!(out, "{name} {value}");
+    }
+    out
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+
+    #[test]
+    fn test_render() {
+        let backend = Backend::new();
+        backend.set("hello", BulkString::new("world").into());
+        backend.hset("user", "name", BulkString::new("alice").into());
+        backend.record_command("get");
+        backend.record_command("get");
+        backend.record_command("set");
+        backend.client_connected();
+
+        let out = render(&backend);
+        assert!(out.contains("# TYPE redis_commands_total counter\n"));
+        assert!(out.contains("redis_commands_total{cmd=\"get\"} 2\n"));
+        assert!(out.contains("redis_commands_total{cmd=\"set\"} 1\n"));
+        assert!(out.contains("redis_commands_processed_total 3\n"));
+        assert!(out.contains("redis_connections_active 1\n"));
+        assert!(out.contains("# TYPE redis_keys_total gauge\nredis_keys_total 2\n"));
+    }
+}
@@ -1,17 +1,30 @@
 use crate::{
     backend::Backend,
-    cmd::{Command, CommandExecutor},
-    RespDecode, RespEncode, RespError, RespFrame,
+    cmd::{Command, CommandExecutor, ConnectionState},
+    resp::BUF_CAP,
+    RespArray, RespDecode, RespEncode, RespError, RespFrame, RespNullArray, SimpleError,
+    SimpleString,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use futures::SinkExt;
-use tokio::net::TcpStream;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{debug, debug_span, info, warn, Instrument, Level};
 
 #[derive(Debug)]
-struct RespFrameCodec;
+struct RespFrameCodec {
+    // the bulk length limit is read on every decode, `CONFIG SET` may change it
+    backend: Backend,
+}
+
+// a request, or the error of a malformed one: its bytes are dropped, so the connection can
+// go on with the next requests
+type Decoded = Result<RespFrame>;
 
 #[derive(Debug)]
 struct RedisRequest {
@@ -24,32 +37,365 @@ struct RedisResponse {
     frame: RespFrame,
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
-    // how to get a frame
-    let mut framed = Framed::new(stream, RespFrameCodec);
+/// Serve a client connected from `addr` (as shown by `CLIENT LIST`) until it disconnects.
+pub async fn stream_handler<S>(stream: S, backend: Backend, addr: impl Into<String>) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // unregisters the client when dropped, even if the task is cancelled
+    let client = backend.register_client(addr);
+    let mut conn = ConnectionState::new(client.id());
+    let ret = serve(stream, &backend, &mut conn).await;
+    conn.unsubscribe_all(&backend);
+    conn.unwatch(&backend);
+    ret
+}
+
+enum Event {
+    Request(Option<Result<Decoded>>),
+    Message(RespFrame),
+}
+
+async fn serve<S>(stream: S, backend: &Backend, conn: &mut ConnectionState) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // the codec reads into a single buffer per connection, reserving more only when a frame
+    // doesn't fit; bytes of a partial frame stay there until the rest is read
+    let mut framed = Framed::with_capacity(
+        stream,
+        RespFrameCodec {
+            backend: backend.clone(),
+        },
+        BUF_CAP,
+    );
+    // a request read while the previous one was blocked
+    let mut pending = None;
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Received frame: {:?}", frame);
+        // like redis, subscribers and monitors are never considered idle
+        let timeout = backend
+            .idle_timeout()
+            .filter(|_| conn.subscription_count() == 0 && !conn.is_monitoring());
+        let event = match pending.take() {
+            Some(next) => Event::Request(Some(next)),
+            None => tokio::select! {
+                next = next_request(&mut framed, timeout) => match next {
+                    Some(next) => Event::Request(next),
+                    None => {
+                        info!("Closing idle connection");
+                        return Ok(());
+                    }
+                },
+                Some(message) = conn.next_message() => Event::Message(message),
+            },
+        };
+        let next = match event {
+            Event::Request(next) => next,
+            Event::Message(_) if conn.is_lagging() => {
+                warn!("Closing subscriber lagging behind published messages");
+                return Ok(());
+            }
+            Event::Message(message) => {
+                framed.send(for_protocol(message, conn.protocol)).await?;
+                continue;
+            }
+        };
+        match next {
+            Some(Ok(Ok(frame))) => {
+                let blocking = Command::is_blocking_request(&frame);
                 let request = RedisRequest {
                     frame,
                     backend: backend.clone(),
                 };
-                let response = request_handler(request).await?;
-                framed.send(response.frame).await?;
+                let handler = request_handler(request, conn);
+                let response = if blocking {
+                    match watch_connection(handler, &mut framed, &mut pending).await {
+                        Some(response) => response?,
+                        None => return Ok(()),
+                    }
+                } else {
+                    handler.await?
+                };
+                if conn.is_closing_without_reply() {
+                    return Ok(());
+                }
+                framed
+                    .send(for_protocol(response.frame, conn.protocol))
+                    .await?;
+                let replies: Vec<_> = conn.take_replies().collect();
+                for frame in replies {
+                    framed.send(for_protocol(frame, conn.protocol)).await?;
+                }
+                if conn.is_closing() {
+                    return Ok(());
+                }
+            }
+            // the connection is closed past the budget, by default on the first error like redis
+            Some(Ok(Err(e))) => {
+                framed.send(protocol_error(e)).await?;
+                if conn.record_protocol_error() > backend.protocol_error_budget() {
+                    return Ok(());
+                }
+            }
+            Some(Err(e)) => {
+                framed.send(protocol_error(e)).await?;
+                return Ok(());
             }
-            Some(Err(e)) => return Err(e),
             None => return Ok(()),
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
+fn protocol_error(e: anyhow::Error) -> RespFrame {
+    SimpleError::new(format!("ERR Protocol error: {}", e)).into()
+}
+
+// next request of the client, `None` if it was idle for longer than `timeout`
+async fn next_request<S>(
+    framed: &mut Framed<S, RespFrameCodec>,
+    timeout: Option<Duration>,
+) -> Option<Option<Result<Decoded>>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, framed.next()).await.ok(),
+        None => Some(framed.next().await),
+    }
+}
+
+// Run the handler of a blocking request while watching the connection, `None` if the
+// client closed it meanwhile. The handler is dropped then, so a client gone while blocked
+// doesn't consume what is pushed later. A request read meanwhile is kept in `pending`.
+async fn watch_connection<S>(
+    handler: impl Future<Output = Result<RedisResponse>>,
+    framed: &mut Framed<S, RespFrameCodec>,
+    pending: &mut Option<Result<Decoded>>,
+) -> Option<Result<RedisResponse>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    tokio::pin!(handler);
+    loop {
+        tokio::select! {
+            response = &mut handler => return Some(response),
+            // like redis, later requests of a blocked client wait for it to be served
+            next = framed.next(), if pending.is_none() => match next {
+                Some(next) => *pending = Some(next),
+                None => return None,
+            },
+        }
+    }
+}
+
+fn for_protocol(frame: RespFrame, protocol: u8) -> RespFrame {
+    match protocol {
+        2 => frame.into_resp2(),
+        _ => frame,
+    }
+}
+
+// Whether a bulk string header of the buffered request announces more than `max` bytes.
+// Only the headers are looked at, so an oversized bulk string is rejected before its
+// payload is buffered.
+fn exceeds_bulk_len(buf: &[u8], max: u64) -> bool {
+    let mut pos = 0;
+    if buf.first() == Some(&b'*') {
+        match read_header(buf, pos) {
+            Some((_, next)) => pos = next,
+            None => return false,
+        }
+    }
+    while buf.get(pos) == Some(&b'$') {
+        let Some((len, next)) = read_header(buf, pos) else {
+            return false;
+        };
+        if len > 0 && len as u64 > max {
+            return true;
+        }
+        pos = next.saturating_add(len.max(0) as usize + 2);
+    }
+    false
+}
+
+// the integer of the `<prefix><integer>\r\n` line at `pos` and the position following it
+fn read_header(buf: &[u8], pos: usize) -> Option<(i64, usize)> {
+    let end = buf[pos..].windows(2).position(|w| w == b"\r\n")? + pos;
+    let len = std::str::from_utf8(&buf[pos + 1..end]).ok()?.parse().ok()?;
+    Some((len, end + 2))
+}
+
+async fn request_handler(
+    request: RedisRequest,
+    conn: &mut ConnectionState,
+) -> Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
-    let cmd = Command::try_from(frame)?;
-    info!("Executing command: {:?}", cmd);
-    let frame = cmd.execute(&backend);
-    Ok(RedisResponse { frame })
+    // the request is kept for the aof, for the keys it writes while clients watch keys, for
+    // EXEC when queued, and for the connections in MONITOR
+    let raw = (backend.aof_enabled()
+        || backend.has_watchers()
+        || conn.in_transaction()
+        || backend.has_monitors())
+    .then(|| frame.clone());
+    // the key is only copied for the logs when they are enabled
+    let key = tracing::enabled!(Level::DEBUG)
+        .then(|| log_arg(&frame, 1))
+        .flatten();
+    // the name of the command for the error of a rejected command, see below
+    let subscribed = conn.protocol == 2 && conn.subscription_count() > 0;
+    let name = subscribed.then(|| log_arg(&frame, 0)).flatten();
+    let cmd = match Command::try_from(frame) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            // like redis, EXEC discards a transaction once one of its commands is rejected
+            conn.fail_transaction();
+            return Ok(RedisResponse { frame: e.into() });
+        }
+    };
+    // like redis, RESP2 subscribers only manage their subscriptions, replies and messages
+    // could be mixed up otherwise
+    if subscribed && !cmd.is_allowed_while_subscribed() {
+        let name = cmd.name().map(str::to_string).or(name).unwrap_or_default();
+        let frame = SimpleError::new(format!(
+            "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / \
+             RESET are allowed in this context",
+            name.to_lowercase()
+        ));
+        return Ok(RedisResponse {
+            frame: frame.into(),
+        });
+    }
+    if conn.is_monitoring() && !matches!(cmd, Command::Reset(_) | Command::Quit(_)) {
+        let frame = SimpleError::new(
+            "ERR the connection is in MONITOR mode, only RESET and QUIT are allowed",
+        );
+        return Ok(RedisResponse {
+            frame: frame.into(),
+        });
+    }
+    let span = debug_span!("command", name = cmd.name().unwrap_or("unknown"), key);
+    async move {
+        let started = Instant::now();
+        let frame = match (cmd, raw) {
+            (Command::Exec(_), raw) => exec(raw, &backend, conn).await,
+            (cmd, Some(raw)) if conn.in_transaction() && cmd.is_queued_in_transaction() => {
+                conn.queue_command(cmd, raw);
+                SimpleString::new("QUEUED").into()
+            }
+            (cmd, raw) => {
+                // blocking commands lock and append what they pop themselves, writes aren't
+                // locked while they wait
+                let write = cmd.is_write() && !cmd.is_blocking();
+                let _writes = match write {
+                    true => Some(backend.lock_writes().await),
+                    false => None,
+                };
+                let _aof_order = match write {
+                    true => backend.lock_aof().await,
+                    false => None,
+                };
+                execute(cmd, raw, &backend, conn, false).await
+            }
+        };
+        debug!(
+            latency_us = started.elapsed().as_micros() as u64,
+            "executed"
+        );
+        Ok(RedisResponse { frame })
+    }
+    .instrument(span)
+    .await
+}
+
+// Execute the command once the caller holds the locks of its writes, then append it to the
+// aof and signal the keys it wrote to the clients watching them, unless it failed. Within a
+// transaction, blocking commands don't wait and are handled like other writes.
+async fn execute(
+    cmd: Command,
+    raw: Option<RespFrame>,
+    backend: &Backend,
+    conn: &mut ConnectionState,
+    in_transaction: bool,
+) -> RespFrame {
+    if let Some(name) = cmd.name() {
+        backend.record_command(name);
+    }
+    if let Some(raw) = &raw {
+        backend.feed_monitors(conn.id, raw);
+    }
+    let blocking = cmd.is_blocking() && !in_transaction;
+    let write = cmd.is_write() && !blocking;
+    // unknown written keys signal every watching client, a watch may have started since the
+    // request was read
+    let written = raw
+        .as_ref()
+        .filter(|_| write && backend.has_watchers())
+        .map(Command::key_args);
+    let aof_frames = match raw {
+        Some(raw) if write && backend.aof_enabled() => cmd.aof_frames(raw),
+        _ => vec![],
+    };
+    let frame = match cmd.is_blocking() && in_transaction {
+        true => cmd.execute(backend),
+        false => cmd.execute_in(backend, conn).await,
+    };
+    if write && !matches!(frame, RespFrame::Error(_)) {
+        for raw in aof_frames {
+            backend.append_aof(raw);
+        }
+        backend.signal_modified(written.as_deref());
+    }
+    frame
+}
+
+// EXEC: execute the commands queued since MULTI with no other write in between, unless a
+// key watched by the connection was written since WATCH
+async fn exec(raw: Option<RespFrame>, backend: &Backend, conn: &mut ConnectionState) -> RespFrame {
+    backend.record_command("exec");
+    if let Some(raw) = &raw {
+        backend.feed_monitors(conn.id, raw);
+    }
+    let Some(transaction) = conn.take_transaction() else {
+        return SimpleError::new("ERR EXEC without MULTI").into();
+    };
+    let _writes = backend.lock_writes_exclusive().await;
+    // the watch ends with the transaction
+    let dirty = conn.unwatch(backend);
+    if transaction.failed {
+        return SimpleError::new("EXECABORT Transaction discarded because of previous errors.")
+            .into();
+    }
+    if dirty {
+        return RespFrame::NullArray(RespNullArray);
+    }
+    let _aof_order = backend.lock_aof().await;
+    let mut replies = Vec::with_capacity(transaction.commands.len());
+    for (cmd, raw) in transaction.commands {
+        replies.push(execute(cmd, Some(raw), backend, conn, true).await);
+    }
+    RespArray::new(replies).into()
+}
+
+// longest prefix of an argument written to the logs, values may be large or sensitive
+const MAX_LOGGED_ARG_LEN: usize = 32;
+
+// the argument at `index` of the request for the logs, truncated to `MAX_LOGGED_ARG_LEN`
+fn log_arg(frame: &RespFrame, index: usize) -> Option<String> {
+    let RespFrame::Array(args) = frame else {
+        return None;
+    };
+    let RespFrame::BulkString(arg) = args.get(index)? else {
+        return None;
+    };
+    let arg = arg.as_ref();
+    if arg.len() <= MAX_LOGGED_ARG_LEN {
+        return Some(String::from_utf8_lossy(arg).into_owned());
+    }
+    Some(format!(
+        "{}... ({} bytes)",
+        String::from_utf8_lossy(&arg[..MAX_LOGGED_ARG_LEN]),
+        arg.len()
+    ))
 }
 
 impl Encoder<RespFrame> for RespFrameCodec {
@@ -63,14 +409,676 @@ impl Encoder<RespFrame> for RespFrameCodec {
 }
 
 impl Decoder for RespFrameCodec {
-    type Item = RespFrame;
+    type Item = Decoded;
     type Error = anyhow::Error;
 
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>> {
+        match self.decode_frame(src) {
+            Ok(frame) => Ok(frame.map(Ok)),
+            // where the malformed frame ends is unknown, everything buffered goes with it
+            Err(e) => {
+                src.clear();
+                Ok(Some(Err(e)))
+            }
+        }
+    }
+}
+
+impl RespFrameCodec {
+    fn decode_frame(&mut self, src: &mut bytes::BytesMut) -> Result<Option<RespFrame>> {
+        if exceeds_bulk_len(src, self.backend.proto_max_bulk_len()) {
+            return Err(anyhow!("invalid bulk length"));
+        }
         match RespFrame::decode(src) {
             Ok(frame) => Ok(Some(frame)),
+            // the client may never complete the request, it can't grow the buffer forever
+            Err(RespError::NotComplete)
+                if src.len() as u64 > self.backend.client_query_buffer_limit() =>
+            {
+                Err(anyhow!("query buffer exceeds client-query-buffer-limit"))
+            }
             Err(RespError::NotComplete) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::ListEnd, BulkString};
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    #[tokio::test]
+    async fn test_protocol_error_replies_and_closes() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+
+        client.write_all(b"!oops\r\n").await?;
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await?;
+        let reply = String::from_utf8(buf)?;
+        assert!(reply.starts_with("-ERR Protocol error: "));
+        assert!(reply.ends_with("\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_command_replies_error_and_keeps_connection() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+
+        client.write_all(b"+get\r\n").await?;
+        let mut buf = [0u8; 64];
+        let n = client.read(&mut buf).await?;
+        assert!(buf[..n].starts_with(b"-ERR "));
+
+        client
+            .write_all(b"*3\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+        Ok(())
+    }
+
+    fn request(args: &[&str]) -> Vec<u8> {
+        let args = args.iter().map(|arg| BulkString::from(*arg).into());
+        RespFrame::from(RespArray::new(args.collect::<Vec<_>>())).encode()
+    }
+
+    // send the request and read the reply, which must be `expected`
+    async fn assert_reply(client: &mut DuplexStream, args: &[&str], expected: &[u8]) -> Result<()> {
+        client.write_all(&request(args)).await?;
+        let mut buf = vec![0u8; expected.len()];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(
+            String::from_utf8_lossy(&buf),
+            String::from_utf8_lossy(expected)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors_within_budget_are_tolerated() -> Result<()> {
+        let backend = Backend::new();
+        backend.config_set("protocol-error-budget", "2")?;
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        async fn read_line(client: &mut DuplexStream) -> Result<String> {
+            let mut line = Vec::new();
+            while !line.ends_with(b"\r\n") {
+                line.push(client.read_u8().await?);
+            }
+            Ok(String::from_utf8(line)?)
+        }
+        for _ in 0..2 {
+            client.write_all(b"!oops\r\n").await?;
+            assert!(read_line(&mut client)
+                .await?
+                .starts_with("-ERR Protocol error: "));
+            // the connection goes on
+            assert_reply(&mut client, &["ping"], b"+PONG\r\n").await?;
+        }
+
+        // the error past the budget is the last reply
+        client.write_all(b"!oops\r\n").await?;
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await?;
+        assert!(String::from_utf8(buf)?.starts_with("-ERR Protocol error: "));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_closes_without_reply() -> Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        assert_reply(
+            &mut client,
+            &["shutdown", "now"],
+            b"-ERR Invalid arguments: syntax error\r\n",
+        )
+        .await?;
+        let mut requests = request(&["shutdown", "nosave"]);
+        requests.extend(request(&["ping"]));
+        client.write_all(&requests).await?;
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await?;
+        assert!(buf.is_empty());
+        backend.shutdown_requested().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quit_replies_then_closes() -> Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        // the request pipelined after QUIT is never executed
+        let mut requests = request(&["quit"]);
+        requests.extend(request(&["set", "k", "v"]));
+        client.write_all(&requests).await?;
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await?;
+        assert_eq!(buf, b"+OK\r\n");
+        assert_eq!(backend.get("k"), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_exec() -> Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        assert_reply(&mut client, &["multi"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["set", "a", "1"], b"+QUEUED\r\n").await?;
+        assert_reply(&mut client, &["incr", "a"], b"+QUEUED\r\n").await?;
+        assert_eq!(backend.get("a"), None);
+        assert_reply(&mut client, &["exec"], b"*2\r\n+OK\r\n:2\r\n").await?;
+        assert_eq!(backend.get("a"), Some(BulkString::from("2").into()));
+
+        assert_reply(&mut client, &["exec"], b"-ERR EXEC without MULTI\r\n").await?;
+        // a rejected command discards the transaction
+        assert_reply(&mut client, &["multi"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["set", "a"], b"-ERR ").await?;
+        let mut rest = [0u8; 64];
+        let _ = client.read(&mut rest).await?;
+        assert_reply(&mut client, &["set", "a", "3"], b"+QUEUED\r\n").await?;
+        assert_reply(
+            &mut client,
+            &["exec"],
+            b"-EXECABORT Transaction discarded because of previous errors.\r\n",
+        )
+        .await?;
+        assert_eq!(backend.get("a"), Some(BulkString::from("2").into()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watched_key_written_aborts_exec() -> Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+        let (mut other, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        assert_reply(&mut client, &["watch", "a"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["multi"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["set", "a", "mine"], b"+QUEUED\r\n").await?;
+        assert_reply(&mut other, &["set", "a", "theirs"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["exec"], b"*-1\r\n").await?;
+        assert_eq!(backend.get("a"), Some(BulkString::from("theirs").into()));
+        assert!(!backend.has_watchers());
+
+        // writes of other keys don't abort, and the watch ended with the previous EXEC
+        assert_reply(&mut client, &["watch", "a"], b"+OK\r\n").await?;
+        assert_reply(&mut other, &["set", "b", "1"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["multi"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["set", "a", "mine"], b"+QUEUED\r\n").await?;
+        assert_reply(&mut client, &["exec"], b"*1\r\n+OK\r\n").await?;
+        assert_eq!(backend.get("a"), Some(BulkString::from("mine").into()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blpop_of_disconnected_client_pops_nothing() -> Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = duplex(1024);
+        let handler = tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        client
+            .write_all(b"*3\r\n$5\r\nblpop\r\n$5\r\nqueue\r\n$1\r\n0\r\n")
+            .await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(client);
+        handler.await??;
+
+        backend.push("queue", ListEnd::Left, [BulkString::from("job").into()]);
+        assert_eq!(
+            backend.pop("queue", ListEnd::Left),
+            Some(BulkString::from("job").into())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocked_client_keeps_pipelined_requests() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("k", BulkString::from("v").into());
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+
+        client
+            .write_all(b"*3\r\n$5\r\nblpop\r\n$5\r\nqueue\r\n$1\r\n0\r\n")
+            .await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        client.write_all(b"*2\r\n$3\r\nget\r\n$1\r\nk\r\n").await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        backend.push("queue", ListEnd::Left, [BulkString::from("job").into()]);
+
+        let expected = b"*2\r\n$5\r\nqueue\r\n$3\r\njob\r\n$1\r\nv\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        client.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_frame_split_across_reads() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+
+        client.write_all(b"*3\r\n$3\r\nset\r\n$5\r\nhel").await?;
+        client.flush().await?;
+        tokio::task::yield_now().await;
+        client.write_all(b"lo\r\n$5\r\nworld\r\n").await?;
+        let mut buf = [0u8; 64];
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+
+        client
+            .write_all(b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"$5\r\nworld\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_replay_from_aof() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+        let backend = Backend::new_with_aof(&path)?;
+
+        let mut clients = Vec::new();
+        let mut aof_len = 0;
+        for c in 0..4 {
+            let (mut client, server) = duplex(64 * 1024);
+            tokio::spawn(stream_handler(server, backend.clone(), "test"));
+            let mut requests = Vec::new();
+            for i in 0..50 {
+                let value = format!("{c}-{i}");
+                let frame: RespFrame = RespArray::new([
+                    BulkString::from("set").into(),
+                    BulkString::from("key").into(),
+                    BulkString::from(value).into(),
+                ])
+                .into();
+                requests.extend(frame.encode());
+            }
+            aof_len += requests.len() as u64;
+            clients.push(tokio::spawn(async move {
+                client.write_all(&requests).await?;
+                let mut buf = vec![0u8; 50 * b"+OK\r\n".len()];
+                client.read_exact(&mut buf).await?;
+                anyhow::Ok(client)
+            }));
+        }
+        for client in clients {
+            client.await??;
+        }
+        for _ in 0..100 {
+            if std::fs::metadata(&path)?.len() == aof_len {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let restored = Backend::new_with_aof(&path)?;
+        assert!(backend.get("key").is_some());
+        assert_eq!(restored.get("key"), backend.get("key"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spop_replays_from_aof() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("appendonly.aof");
+        let backend = Backend::new_with_aof(&path)?;
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+        assert_reply(&mut client, &["sadd", "s", "a", "b", "c", "d"], b":4\r\n").await?;
+        client.write_all(&request(&["spop", "s", "2"])).await?;
+        let mut buf = [0u8; 64];
+        let _ = client.read(&mut buf).await?;
+        // the random pop is appended as the removal of the members popped
+        for _ in 0..100 {
+            let aof = std::fs::read(&path)?;
+            if aof.windows(4).any(|w| w == b"srem") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let restored = Backend::new_with_aof(&path)?;
+        let members = |backend: &Backend| {
+            let mut members = backend.smembers("s").unwrap();
+            members.sort();
+            members
+        };
+        assert_eq!(members(&backend).len(), 2);
+        assert_eq!(members(&restored), members(&backend));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_monitor_receives_commands_of_other_clients() -> Result<()> {
+        let backend = Backend::new();
+        let (mut monitor, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "monitor"));
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend, "10.0.0.1:6000"));
+
+        assert_reply(&mut monitor, &["monitor"], b"+OK\r\n").await?;
+        assert_reply(&mut client, &["set", "k", "v"], b"+OK\r\n").await?;
+        let mut buf = [0u8; 128];
+        let n = monitor.read(&mut buf).await?;
+        let line = String::from_utf8_lossy(&buf[..n]);
+        assert!(line.starts_with('+'), "{line}");
+        assert!(
+            line.ends_with(" [0 10.0.0.1:6000] \"set\" \"k\" \"v\"\r\n"),
+            "{line}"
+        );
+
+        assert_reply(
+            &mut monitor,
+            &["get", "k"],
+            b"-ERR the connection is in MONITOR mode, only RESET and QUIT are allowed\r\n",
+        )
+        .await?;
+        assert_reply(&mut monitor, &["reset"], b"+RESET\r\n").await?;
+        assert_reply(&mut monitor, &["get", "k"], b"$1\r\nv\r\n").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_only_manages_subscriptions() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+
+        let confirmations = b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n\
+                              *3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n";
+        assert_reply(&mut client, &["subscribe", "a", "b"], confirmations).await?;
+        assert_reply(
+            &mut client,
+            &["GET", "k"],
+            b"-ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / \
+              RESET are allowed in this context\r\n",
+        )
+        .await?;
+
+        let confirmations = b"*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n\
+                              *3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:0\r\n";
+        assert_reply(&mut client, &["unsubscribe"], confirmations).await?;
+        // out of subscribe mode with the last subscription
+        assert_reply(&mut client, &["get", "k"], b"$-1\r\n").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_then_message() -> Result<()> {
+        let backend = Backend::new();
+        let (mut subscriber, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+        let (mut publisher, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        subscriber
+            .write_all(b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n$1\r\nb\r\n")
+            .await?;
+        let expected = b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n\
+                         *3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        subscriber.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+
+        publisher
+            .write_all(b"*3\r\n$7\r\npublish\r\n$1\r\nb\r\n$2\r\nhi\r\n")
+            .await?;
+        let mut buf = [0u8; 64];
+        let n = publisher.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b":1\r\n");
+
+        let expected = b"*3\r\n$7\r\nmessage\r\n$1\r\nb\r\n$2\r\nhi\r\n";
+        let mut buf = vec![0u8; expected.len()];
+        subscriber.read_exact(&mut buf).await?;
+        assert_eq!(buf, expected);
+        Ok(())
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn test_debug_sleep_does_not_block_other_connections() -> Result<()> {
+        let backend = Backend::new();
+        let (mut sleeper, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend.clone(), "test"));
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        let start = std::time::Instant::now();
+        sleeper
+            .write_all(b"*3\r\n$5\r\ndebug\r\n$5\r\nsleep\r\n$3\r\n0.5\r\n")
+            .await?;
+        client
+            .write_all(b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n")
+            .await?;
+        let mut buf = [0u8; 64];
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"$-1\r\n");
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let n = sleeper.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+        assert!(start.elapsed() >= Duration::from_millis(500));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_unregistered_when_connection_fails() -> Result<()> {
+        let backend = Backend::new();
+        // the reply can't be written as the peer stopped reading
+        let (writer, reader) = duplex(1024);
+        drop(reader);
+        let request: &[u8] = b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n";
+        let stream = tokio::io::join(request, writer);
+        let handle = tokio::spawn(stream_handler(stream, backend.clone(), "test"));
+        assert!(handle.await?.is_err());
+        assert!(backend.clients().is_empty());
+        assert_eq!(backend.connected_clients(), 0);
+
+        let (_client, server) = duplex(1024);
+        let handle = tokio::spawn(stream_handler(server, backend.clone(), "test"));
+        while backend.clients().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(backend.clients()[0].addr, "test");
+        handle.abort();
+        assert!(handle.await.unwrap_err().is_cancelled());
+        assert!(backend.clients().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_reply_is_fully_written_to_slow_client() -> Result<()> {
+        // a tiny pipe forces many partial writes of the reply
+        let (mut client, server) = duplex(64);
+        let backend = Backend::new();
+        let fields = (0..1000)
+            .map(|i| (format!("field{i}"), "v".repeat(1000)))
+            .collect::<Vec<_>>();
+        for (field, value) in &fields {
+            backend.hset("big", field, BulkString::from(value.as_str()).into());
+        }
+        let bulk_len = |s: &str| format!("${}\r\n{s}\r\n", s.len()).len();
+        let expected = "*2000\r\n".len()
+            + fields
+                .iter()
+                .map(|(field, value)| bulk_len(field) + bulk_len(value))
+                .sum::<usize>();
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        client
+            .write_all(b"*2\r\n$7\r\nhgetall\r\n$3\r\nbig\r\n")
+            .await?;
+        let mut reply = vec![0u8; expected];
+        let mut read = 0;
+        while read < reply.len() {
+            let n = client.read(&mut reply[read..]).await?;
+            assert!(n > 0, "connection closed after {read} bytes");
+            read += n;
+            if read % 4096 < 64 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+        assert!(reply.starts_with(b"*2000\r\n"));
+        assert!(reply.ends_with(b"\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_arg_is_truncated() {
+        let frame = |key: &str| -> RespFrame {
+            RespArray::new([BulkString::from("get").into(), BulkString::from(key).into()]).into()
+        };
+        assert_eq!(log_arg(&frame("hello"), 1).as_deref(), Some("hello"));
+        assert_eq!(log_arg(&frame("hello"), 2), None);
+        let long = "x".repeat(100);
+        assert_eq!(
+            log_arg(&frame(&long), 1),
+            Some(format!("{}... (100 bytes)", "x".repeat(32)))
+        );
+    }
+
+    #[test]
+    fn test_exceeds_bulk_len() {
+        let request = b"*3\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n";
+        assert!(!exceeds_bulk_len(request, 5));
+        assert!(exceeds_bulk_len(request, 4));
+        // partial requests are checked as far as they go
+        assert!(!exceeds_bulk_len(b"*2\r\n$3\r\nget\r\n$100", 99));
+        assert!(exceeds_bulk_len(b"*2\r\n$3\r\nget\r\n$100\r\nabc", 99));
+        assert!(exceeds_bulk_len(b"$100\r\n", 99));
+        assert!(!exceeds_bulk_len(b"+ping\r\n", 0));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bulk_string_is_rejected() -> Result<()> {
+        let backend = Backend::new();
+        backend.config_set("proto-max-bulk-len", "1048576")?;
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        // rejected on the header, before the payload is sent
+        client
+            .write_all(b"*3\r\n$3\r\nset\r\n$5\r\nhello\r\n$1048577\r\n")
+            .await?;
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await?;
+        assert_eq!(buf, b"-ERR Protocol error: invalid bulk length\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unterminated_request_past_query_buffer_limit() -> Result<()> {
+        let backend = Backend::new();
+        backend.config_set("client-query-buffer-limit", "1048576")?;
+        let (client, server) = duplex(64 * 1024);
+        tokio::spawn(stream_handler(server, backend, "test"));
+
+        let (mut reader, mut writer) = tokio::io::split(client);
+        // the connection is closed before every byte is written
+        tokio::spawn(async move {
+            let line = vec![b'a'; 1024 * 1024 + 1];
+            let _ = writer.write_all(b"+").await;
+            let _ = writer.write_all(&line).await;
+        });
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+        assert_eq!(
+            buf,
+            b"-ERR Protocol error: query buffer exceeds client-query-buffer-limit\r\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replies_follow_negotiated_protocol() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+        let mut buf = [0u8; 256];
+
+        let get = b"*2\r\n$3\r\nget\r\n$7\r\nmissing\r\n";
+        client.write_all(get).await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"$-1\r\n");
+
+        // HGET misses are null bulk strings too, for a missing key or field
+        client
+            .write_all(b"*4\r\n$4\r\nhset\r\n$1\r\nh\r\n$1\r\nf\r\n$1\r\nv\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+        for hget in [
+            b"*3\r\n$4\r\nhget\r\n$1\r\nh\r\n$7\r\nmissing\r\n",
+            b"*3\r\n$4\r\nhget\r\n$7\r\nmissing\r\n$1\r\nf\r\n",
+        ] {
+            client.write_all(hget).await?;
+            let n = client.read(&mut buf).await?;
+            assert_eq!(&buf[..n], b"$-1\r\n");
+        }
+
+        client
+            .write_all(b"*2\r\n$5\r\nhello\r\n$1\r\n3\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert!(buf[..n].starts_with(b"%7\r\n"));
+
+        client.write_all(get).await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"_\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_maps_are_flattened_for_resp2() -> Result<()> {
+        let (mut client, server) = duplex(1024);
+        tokio::spawn(stream_handler(server, Backend::new(), "test"));
+        assert_reply(&mut client, &["hset", "h", "f", "v"], b"+OK\r\n").await?;
+        assert_reply(
+            &mut client,
+            &["hgetall", "h"],
+            b"*2\r\n$1\r\nf\r\n$1\r\nv\r\n",
+        )
+        .await?;
+        assert_reply(
+            &mut client,
+            &["config", "get", "timeout"],
+            b"*2\r\n$7\r\ntimeout\r\n$1\r\n0\r\n",
+        )
+        .await?;
+
+        // the reply of HELLO, a map of its own, is skipped up to the one of HGETALL
+        let mut requests = request(&["hello", "3"]);
+        requests.extend(request(&["hgetall", "h"]));
+        client.write_all(&requests).await?;
+        let expected = b"%1\r\n+f\r\n$1\r\nv\r\n";
+        let mut buf = Vec::new();
+        while !buf.ends_with(expected) {
+            let mut chunk = [0u8; 256];
+            let n = client.read(&mut chunk).await?;
+            assert!(n > 0, "connection closed");
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        assert_reply(
+            &mut client,
+            &["config", "get", "timeout"],
+            b"%1\r\n+timeout\r\n$1\r\n0\r\n",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
-use std::ops::Deref;
-
-use bytes::{Buf, BytesMut};
+use std::{io::Write, ops::Deref};
 
 use crate::{RespDecode, RespEncode, RespError, RespFrame};
 
-use super::{extract_fixed_data, parse_length, BUF_CAP, CRLF_LEN};
+use super::{extract_fixed_data, header_len, parse_length};
+
+// elements reserved up front when decoding, the declared count isn't trusted beyond that
+const PREALLOC_ELEMENTS: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct RespArray(Vec<RespFrame>);
@@ -14,16 +15,16 @@ pub struct RespNullArray;
 
 // - null array: "*-1\r\n"
 impl RespEncode for RespNullArray {
-    fn encode(self) -> Vec<u8> {
-        "*-1\r\n".into()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"*-1\r\n");
     }
 }
 
 // - null array: "*-1\r\n"
 impl RespDecode for RespNullArray {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        extract_fixed_data(buf, "*-1\r\n", "NullArray")?;
-        Ok(RespNullArray)
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let len = extract_fixed_data(buf, "*-1\r\n", "NullArray")?;
+        Ok((RespNullArray, len))
     }
 }
 
@@ -31,34 +32,49 @@ impl RespArray {
     pub fn new(v: impl Into<Vec<RespFrame>>) -> Self {
         RespArray(v.into())
     }
+
+    /// length of the encoded array
+    pub fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(RespFrame::encoded_len).sum::<usize>()
+    }
 }
 
 // - array: "*<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespEncode for RespArray {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("*{}\r\n", self.len()).into_bytes());
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, "*{}\r\n", self.len()).expect("writing to a Vec can't fail");
         for frame in self.0 {
-            buf.extend_from_slice(&frame.encode())
+            frame.encode_into(buf);
         }
+    }
+
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_into(&mut buf);
         buf
     }
 }
 
 // - array: "*<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespDecode for RespArray {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        RespArray::decode_nested(buf, 0)
+    }
+}
+
+impl RespArray {
+    /// Decode the array, itself an element of `depth` enclosing aggregates. A malformed
+    /// element fails the whole frame.
+    pub(super) fn decode_nested(buf: &[u8], depth: usize) -> Result<(Self, usize), RespError> {
         let prefix = "*";
-        let (end, len) = parse_length(buf, prefix)?;
-        let mut newbuf = buf.clone();
-        newbuf.advance(end + CRLF_LEN);
-        let mut frames = Vec::with_capacity(len);
+        let (mut consumed, len) = parse_length(buf, prefix)?;
+        let mut frames = Vec::with_capacity(len.min(PREALLOC_ELEMENTS));
         for _ in 0..len {
-            let res = RespFrame::decode(&mut newbuf).map_err(|_| RespError::NotComplete)?;
+            let (res, n) = RespFrame::decode_nested(&buf[consumed..], depth + 1)?;
             frames.push(res);
+            consumed += n;
         }
-        buf.advance(buf.len() - newbuf.len());
-        Ok(RespArray::new(frames))
+        Ok((RespArray::new(frames), consumed))
     }
 }
 
@@ -85,6 +101,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
+    use bytes::BytesMut;
 
     #[test]
     fn test_array_decode() -> Result<()> {
@@ -124,6 +141,16 @@ mod tests {
             ])
         );
 
+        // the count is only a claim, nothing is allocated for it until the elements arrive
+        let ret = RespArray::decode_with_len(b"*1000000000\r\n:1\r\n");
+        assert_eq!(ret, Err(RespError::NotComplete));
+        let ret = RespArray::decode_with_len(b"*9223372036854775807\r\n");
+        assert_eq!(ret, Err(RespError::NotComplete));
+
+        // a malformed element is an error, not a frame waiting for more bytes
+        let ret = RespArray::decode_with_len(b"*2\r\n:1\r\n!x\r\n");
+        assert!(matches!(ret, Err(RespError::InvalidFrameType(_))));
+
         Ok(())
     }
 
@@ -141,6 +168,18 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_array_encode_into_appends() {
+        let frame: RespFrame = RespArray::new(vec![
+            RespArray::new(vec![BulkString::new("a").into()]).into(),
+            RespFrame::Integer(1),
+        ])
+        .into();
+        let mut buf = b"+OK\r\n".to_vec();
+        frame.encode_into(&mut buf);
+        assert_eq!(buf, b"+OK\r\n*2\r\n*1\r\n$1\r\na\r\n:1\r\n");
+    }
+
     #[test]
     fn test_null_array_encode() {
         let frame: RespFrame = RespNullArray.into();
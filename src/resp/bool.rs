@@ -1,26 +1,25 @@
-use bytes::BytesMut;
-
 use crate::{RespDecode, RespEncode, RespError};
 
 use super::extract_fixed_data;
 
 // - boolean: "#<t|f>\r\n"
 impl RespEncode for bool {
-    fn encode(self) -> Vec<u8> {
-        let flag = if self { "t" } else { "f" };
-        format!("#{}\r\n", flag).into_bytes()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(if self { b"#t\r\n" } else { b"#f\r\n" });
     }
 }
 
 // - boolean: "#<t|f>\r\n"
 impl RespDecode for bool {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        match extract_fixed_data(buf, "#t\r\n", "Bool") {
-            Ok(_) => Ok(true),
-            Err(_) => match extract_fixed_data(buf, "#f\r\n", "Bool") {
-                Ok(_) => Ok(false),
-                Err(e) => Err(e),
-            },
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        // the flag decides which literal to expect, a bad one is reported as such
+        match buf.get(1) {
+            Some(b'f') => extract_fixed_data(buf, "#f\r\n", "Bool").map(|len| (false, len)),
+            Some(flag) if buf[0] == b'#' && *flag != b't' => Err(RespError::InvalidFrame(format!(
+                "expect boolean flag t or f, got: {:?}",
+                *flag as char
+            ))),
+            _ => extract_fixed_data(buf, "#t\r\n", "Bool").map(|len| (true, len)),
         }
     }
 }
@@ -31,7 +30,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
-    use bytes::BufMut;
+    use bytes::{BufMut, BytesMut};
 
     #[test]
     fn test_boolean_encode() {
@@ -64,4 +63,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_boolean_decode_invalid() {
+        let mut buf = BytesMut::from("#x\r\n");
+        let ret = bool::decode(&mut buf);
+        assert_eq!(
+            ret.unwrap_err(),
+            RespError::InvalidFrame("expect boolean flag t or f, got: 'x'".into())
+        );
+        assert_eq!(buf.len(), 4);
+
+        let mut buf = BytesMut::from("#fx\r\n");
+        assert!(matches!(
+            bool::decode(&mut buf),
+            Err(RespError::InvalidFrame(_))
+        ));
+        let mut buf = BytesMut::from("#");
+        assert_eq!(bool::decode(&mut buf).unwrap_err(), RespError::NotComplete);
+        let mut buf = BytesMut::from(":1\r\n");
+        assert!(matches!(
+            bool::decode(&mut buf),
+            Err(RespError::InvalidFrameType(_))
+        ));
+    }
 }
@@ -0,0 +1,97 @@
+use crate::{BulkString, RespArray, RespFrame, RespMap, SimpleString};
+
+/// Builds nested replies without naming every frame type, e.g.
+/// `RespBuilder::array().push_bulk("a").push_int(1).build()`.
+pub struct RespBuilder;
+
+impl RespBuilder {
+    pub fn array() -> ArrayBuilder {
+        ArrayBuilder(Vec::new())
+    }
+
+    pub fn map() -> MapBuilder {
+        MapBuilder(RespMap::new())
+    }
+}
+
+/// Elements of an array built by `RespBuilder::array`, in the order they are pushed.
+#[derive(Debug, Default)]
+pub struct ArrayBuilder(Vec<RespFrame>);
+
+impl ArrayBuilder {
+    pub fn push(mut self, value: impl Into<RespFrame>) -> Self {
+        self.0.push(value.into());
+        self
+    }
+
+    pub fn push_bulk(self, value: impl Into<Vec<u8>>) -> Self {
+        self.push(BulkString::new(value))
+    }
+
+    pub fn push_simple(self, value: impl Into<String>) -> Self {
+        self.push(SimpleString::new(value))
+    }
+
+    pub fn push_int(self, value: i64) -> Self {
+        self.push(value)
+    }
+
+    pub fn build(self) -> RespFrame {
+        RespArray::new(self.0).into()
+    }
+}
+
+/// Entries of a map built by `RespBuilder::map`, a key set twice keeps the last value.
+#[derive(Debug, Default)]
+pub struct MapBuilder(RespMap);
+
+impl MapBuilder {
+    pub fn entry(mut self, key: impl Into<String>, value: impl Into<RespFrame>) -> Self {
+        self.0.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> RespFrame {
+        self.0.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespEncode;
+
+    #[test]
+    fn test_array_builder() {
+        let frame = RespBuilder::array()
+            .push_bulk("a")
+            .push_int(1)
+            .push_simple("OK")
+            .push(RespBuilder::array().push_bulk(vec![0u8]).build())
+            .build();
+        assert_eq!(
+            frame,
+            RespArray::new([
+                BulkString::from("a").into(),
+                RespFrame::Integer(1),
+                SimpleString::new("OK").into(),
+                RespArray::new([BulkString::new(vec![0u8]).into()]).into(),
+            ])
+            .into()
+        );
+        assert_eq!(RespBuilder::array().build().encode(), b"*0\r\n");
+    }
+
+    #[test]
+    fn test_map_builder() {
+        let frame = RespBuilder::map()
+            .entry("proto", 3)
+            .entry("server", "redis")
+            .entry("proto", 2)
+            .build();
+        let mut map = RespMap::new();
+        map.insert("proto".into(), RespFrame::Integer(2));
+        map.insert("server".into(), BulkString::from("redis").into());
+        assert_eq!(frame, map.into());
+    }
+}
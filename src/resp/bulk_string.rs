@@ -1,10 +1,8 @@
-use std::ops::Deref;
-
-use bytes::{Buf, BytesMut};
+use std::{io::Write, ops::Deref};
 
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::{extract_fixed_data, parse_length, CRLF_LEN};
+use super::{extract_fixed_data, header_len, line_terminator, parse_length, CRLF_LEN};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct BulkString(pub(super) Vec<u8>);
@@ -14,43 +12,61 @@ pub struct RespNullBulkString;
 
 // - null bulk string: "$-1\r\n"
 impl RespEncode for RespNullBulkString {
-    fn encode(self) -> Vec<u8> {
-        "$-1\r\n".into()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"$-1\r\n");
     }
 }
 
 // - null bulk string: "$-1\r\n"
 impl RespDecode for RespNullBulkString {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        extract_fixed_data(buf, "$-1\r\n", "NullBulkString")?;
-        Ok(RespNullBulkString)
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let len = extract_fixed_data(buf, "$-1\r\n", "NullBulkString")?;
+        Ok((RespNullBulkString, len))
     }
 }
 
 // - bulk string: "$<length>\r\n<data>\r\n"
 impl RespEncode for BulkString {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(self.len() + 16);
-        buf.extend_from_slice(&format!("${}\r\n", self.len()).into_bytes());
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, "${}\r\n", self.len()).expect("writing to a Vec can't fail");
         buf.extend_from_slice(&self);
         buf.extend_from_slice(b"\r\n");
+    }
+
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_into(&mut buf);
         buf
     }
 }
 
 // - bulk string: "$<length>\r\n<data>\r\n"
 impl RespDecode for BulkString {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
         let prefix = "$";
-        let (end, len) = parse_length(buf, prefix)?;
-        let remained = &buf[end + CRLF_LEN..];
-        if remained.len() < len + CRLF_LEN {
+        let (start, len) = parse_length(buf, prefix)?;
+        let data = &buf[start..];
+        // the declared length is untrusted, it may be close to usize::MAX
+        if len.checked_add(CRLF_LEN).is_none() {
+            // reported saturated, the length doesn't fit an isize
+            return Err(RespError::InvalidFrameLength(isize::MAX));
+        }
+        if data.len() < len {
             return Err(RespError::NotComplete);
         }
-        buf.advance(end + CRLF_LEN);
-
-        let data = buf.split_to(len + CRLF_LEN);
-        Ok(BulkString::new(data[..len].to_vec()))
+        // the length alone decides where the body ends, even an empty one
+        let terminator = match line_terminator(&data[len..]) {
+            Err(RespError::InvalidFrame(_)) => {
+                return Err(RespError::InvalidFrame(
+                    "bulk string is not terminated by CRLF".into(),
+                ))
+            }
+            terminator => terminator?,
+        };
+        Ok((
+            BulkString::new(data[..len].to_vec()),
+            start + len + terminator,
+        ))
     }
 }
 
@@ -58,6 +74,11 @@ impl BulkString {
     pub fn new(s: impl Into<Vec<u8>>) -> Self {
         BulkString(s.into())
     }
+
+    /// length of the encoded bulk string
+    pub fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.len() + CRLF_LEN
+    }
 }
 
 impl Deref for BulkString {
@@ -80,6 +101,18 @@ impl From<&str> for BulkString {
     }
 }
 
+impl From<String> for BulkString {
+    fn from(s: String) -> Self {
+        BulkString::new(s.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for BulkString {
+    fn from(s: Vec<u8>) -> Self {
+        BulkString::new(s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::RespFrame;
@@ -97,4 +130,28 @@ mod tests {
         let frame: RespFrame = RespNullBulkString.into();
         assert_eq!(frame.encode(), b"$-1\r\n");
     }
+
+    #[test]
+    fn test_empty_bulk_string_decode() {
+        let ret = RespFrame::decode_with_len(b"$0\r\n\r\n");
+        assert_eq!(ret, Ok((BulkString::new("").into(), 6)));
+        assert_eq!(
+            BulkString::decode_with_len(b"$0\r\n\r"),
+            Err(RespError::NotComplete)
+        );
+        for data in [&b"$0\r\nab"[..], b"$0\r\n\n\r", b"$2\r\nabc\r\n"] {
+            assert!(matches!(
+                BulkString::decode_with_len(data),
+                Err(RespError::InvalidFrame(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bulk_string_length_overflow() {
+        let ret = BulkString::decode_with_len(b"$18446744073709551615\r\n");
+        assert_eq!(ret, Err(RespError::InvalidFrameLength(isize::MAX)));
+        let ret = RespFrame::decode_with_len(b"$18446744073709551613\r\nabc\r\n");
+        assert_eq!(ret, Err(RespError::NotComplete));
+    }
 }
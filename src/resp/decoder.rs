@@ -0,0 +1,177 @@
+use std::cell::Cell;
+
+use bytes::{Buf, BytesMut};
+
+use crate::{RespDecode, RespError};
+
+// aggregates nested deeper are rejected by default, decoding them recursively could
+// overflow the stack
+pub(super) const DEFAULT_MAX_NESTING: usize = 128;
+
+thread_local! {
+    // set by `RespDecoder` for the duration of a decode, the defaults otherwise
+    static OPTIONS: Cell<RespDecoder> = const { Cell::new(RespDecoder::DEFAULT) };
+}
+
+// whether the frame being decoded may end its lines with a bare LF
+pub(super) fn lenient_newlines() -> bool {
+    OPTIONS.with(|options| options.get().lenient_newlines)
+}
+
+// the number of aggregates the frame being decoded may be nested in
+pub(super) fn max_nesting() -> usize {
+    OPTIONS.with(|options| options.get().max_nesting)
+}
+
+/// Decoder with options, `RespDecode` using the defaults. With lenient newlines a bare
+/// `\n` is accepted wherever the protocol expects `\r\n`, for clients that don't send the
+/// CR. Frames are always encoded with CRLF.
+#[derive(Debug, Clone, Copy)]
+pub struct RespDecoder {
+    lenient_newlines: bool,
+    max_nesting: usize,
+}
+
+impl Default for RespDecoder {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+// restores the options of the enclosing decode, if any, once the frame is decoded
+struct OptionsGuard(RespDecoder);
+
+impl OptionsGuard {
+    fn set(decoder: RespDecoder) -> Self {
+        OptionsGuard(OPTIONS.with(|options| options.replace(decoder)))
+    }
+}
+
+impl Drop for OptionsGuard {
+    fn drop(&mut self) {
+        OPTIONS.with(|options| options.set(self.0));
+    }
+}
+
+impl RespDecoder {
+    const DEFAULT: Self = RespDecoder {
+        lenient_newlines: false,
+        max_nesting: DEFAULT_MAX_NESTING,
+    };
+
+    /// A strict decoder, the same as `RespDecode`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_lenient_newlines(mut self, lenient: bool) -> Self {
+        self.lenient_newlines = lenient;
+        self
+    }
+
+    /// Reject aggregates nested in more than `max` others, 128 by default, with
+    /// `InvalidFrame("max depth exceeded")`. Decoding is recursive, a high limit may
+    /// overflow the stack of the decoding thread.
+    pub fn with_max_nesting(mut self, max: usize) -> Self {
+        self.max_nesting = max;
+        self
+    }
+
+    /// Like `RespDecode::decode_with_len`, with the options of the decoder.
+    pub fn decode_with_len<T: RespDecode>(&self, buf: &[u8]) -> Result<(T, usize), RespError> {
+        let _options = OptionsGuard::set(*self);
+        T::decode_with_len(buf)
+    }
+
+    /// Like `RespDecode::decode`, with the options of the decoder.
+    pub fn decode<T: RespDecode>(&self, buf: &mut BytesMut) -> Result<T, RespError> {
+        let (frame, len) = self.decode_with_len(buf)?;
+        buf.advance(len);
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray, RespFrame, RespNull, SimpleString};
+
+    fn lenient() -> RespDecoder {
+        RespDecoder::new().with_lenient_newlines(true)
+    }
+
+    #[test]
+    fn test_bare_lf_is_strictly_incomplete() {
+        let decoder = RespDecoder::new();
+        // without a CRLF the line hasn't ended yet
+        assert_eq!(
+            decoder.decode_with_len::<SimpleString>(b"+OK\n"),
+            Err(RespError::NotComplete)
+        );
+        assert_eq!(
+            RespFrame::decode_with_len(b"+OK\n"),
+            Err(RespError::NotComplete)
+        );
+    }
+
+    #[test]
+    fn test_lenient_newlines() -> Result<(), RespError> {
+        assert_eq!(
+            lenient().decode_with_len::<SimpleString>(b"+OK\n")?,
+            (SimpleString::new("OK"), 4)
+        );
+
+        let mut buf = BytesMut::from("*2\n$3\nget\n$1\r\nk\r\n_\n:1\r\n");
+        let frame: RespFrame = lenient().decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespArray::new(vec![BulkString::from("get").into(), "k".into()]).into()
+        );
+        assert_eq!(lenient().decode::<RespFrame>(&mut buf)?, RespNull.into());
+        assert_eq!(
+            lenient().decode::<RespFrame>(&mut buf)?,
+            RespFrame::Integer(1)
+        );
+        assert!(buf.is_empty());
+
+        // the bulk string length still decides where the body ends
+        assert_eq!(
+            lenient().decode_with_len::<RespFrame>(b"$3\na\nb\n")?,
+            (BulkString::from("a\nb").into(), 7)
+        );
+        assert_eq!(
+            lenient().decode_with_len::<RespFrame>(b"$3\nabc"),
+            Err(RespError::NotComplete)
+        );
+        assert!(lenient().decode_with_len::<RespFrame>(b"$3\nabcd").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_nesting() {
+        let nested = |depth: usize| {
+            let mut buf = b"*1\r\n".repeat(depth);
+            buf.extend_from_slice(b":1\r\n");
+            buf
+        };
+        let decoder = RespDecoder::new().with_max_nesting(2);
+        assert!(decoder.decode_with_len::<RespFrame>(&nested(2)).is_ok());
+        assert_eq!(
+            decoder.decode_with_len::<RespFrame>(&nested(3)),
+            Err(RespError::InvalidFrame("max depth exceeded".into()))
+        );
+        // the default applies again
+        assert!(RespFrame::decode_with_len(&nested(3)).is_ok());
+    }
+
+    #[test]
+    fn test_mode_ends_with_the_decode() -> Result<(), RespError> {
+        lenient().decode_with_len::<RespFrame>(b"+OK\n")?;
+        assert!(!lenient_newlines());
+        assert_eq!(
+            RespFrame::decode_with_len(b"+OK\n"),
+            Err(RespError::NotComplete)
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use std::io::Write;
 
 use crate::{RespDecode, RespEncode, RespError};
 
@@ -6,25 +6,26 @@ use super::extract_simpe_frame_data;
 
 // - double ",[<+|->]<integral>[.<fractional>][<E|e>[sign]<exponent>]\r\n"
 impl RespEncode for f64 {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(32);
-        let ret = if self.abs() > 1e+8 || self.abs() < 1e-8 {
-            format!(",{:+e}\r\n", self)
-        } else {
-            let sign = if self < 0.0 { "" } else { "+" };
-            format!(",{}{}\r\n", sign, self)
-        };
-        buf.extend_from_slice(&ret.into_bytes());
-        buf
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, ",{}\r\n", format_double(self)).expect("writing to a Vec can't fail");
+    }
+}
+
+// the double between the prefix and CRLF, in scientific notation when very large or small
+pub(super) fn format_double(value: f64) -> String {
+    if value.abs() > 1e+8 || value.abs() < 1e-8 {
+        format!("{:+e}", value)
+    } else {
+        let sign = if value < 0.0 { "" } else { "+" };
+        format!("{}{}", sign, value)
     }
 }
 
 impl RespDecode for f64 {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let end = extract_simpe_frame_data(buf, ",")?;
-        let data = buf.split_to(end + 2);
-        let s = String::from_utf8_lossy(&data[1..end]);
-        Ok(s.parse()?)
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let (end, next) = extract_simpe_frame_data(buf, ",")?;
+        let s = String::from_utf8_lossy(&buf[1..end]);
+        Ok((s.parse()?, next))
     }
 }
 
@@ -34,6 +35,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
+    use bytes::BytesMut;
 
     #[test]
     fn test_double_encode() {
@@ -1,6 +1,8 @@
-use bytes::BytesMut;
+use std::borrow::Cow;
+
 use enum_dispatch::enum_dispatch;
 
+use super::{decimal_len, decoder, double::format_double, CRLF_LEN};
 use crate::{
     BulkString, RespArray, RespDecode, RespError, RespMap, RespNull, RespNullArray,
     RespNullBulkString, RespSet, SimpleError, SimpleString,
@@ -23,57 +25,180 @@ pub enum RespFrame {
     Set(RespSet),
 }
 
+impl RespFrame {
+    /// An integer reply, e.g. a count: `:n`.
+    pub fn integer(n: i64) -> Self {
+        RespFrame::Integer(n)
+    }
+
+    /// The `+OK` reply.
+    pub fn ok() -> Self {
+        SimpleString::new("OK").into()
+    }
+
+    /// Lenient comparison treating `SimpleString` and `BulkString` with the same bytes as
+    /// equal, e.g. `+OK` and `$2 OK`. Other frames are compared with `==`.
+    pub fn content_eq(&self, other: &RespFrame) -> bool {
+        match (self.as_bytes(), other.as_bytes()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+
+    /// Exact number of bytes `encode` produces, e.g. to size a buffer before encoding.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            RespFrame::SimpleString(s) => 1 + s.len() + CRLF_LEN,
+            RespFrame::Error(e) => 1 + e.len() + CRLF_LEN,
+            RespFrame::Integer(i) => {
+                1 + (*i < 0) as usize + decimal_len(i.unsigned_abs()) + CRLF_LEN
+            }
+            RespFrame::BulkString(s) => s.encoded_len(),
+            RespFrame::NullBulkString(_) | RespFrame::NullArray(_) => 5,
+            RespFrame::Array(array) => array.encoded_len(),
+            RespFrame::Null(_) => 3,
+            RespFrame::Boolean(_) => 4,
+            RespFrame::Double(d) => 1 + format_double(*d).len() + CRLF_LEN,
+            RespFrame::Map(map) => map.encoded_len(),
+            RespFrame::Set(set) => set.encoded_len(),
+        }
+    }
+
+    /// Bytes of a bulk or simple string, `None` for the other frames.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            RespFrame::SimpleString(s) => Some(s.as_bytes()),
+            RespFrame::BulkString(s) => Some(s.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Text of a bulk or simple string, invalid utf-8 being replaced with U+FFFD. `None` for
+    /// the other frames.
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        self.as_bytes().map(String::from_utf8_lossy)
+    }
+
+    /// Downgrade the frame to the types available in RESP2: nulls become null bulk strings,
+    /// booleans integers, doubles bulk strings, and maps and sets flat arrays.
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Null(_) => RespNullBulkString.into(),
+            RespFrame::Boolean(b) => RespFrame::Integer(b as i64),
+            RespFrame::Double(d) => BulkString::new(d.to_string()).into(),
+            RespFrame::Array(array) => RespArray::new(
+                array
+                    .into_iter()
+                    .map(|v| v.into_resp2())
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Set(set) => RespArray::new(
+                set.0
+                    .into_iter()
+                    .map(|v| v.into_resp2())
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Map(map) => RespArray::new(
+                map.0
+                    .into_iter()
+                    .flat_map(|(k, v)| [BulkString::new(k).into(), v.into_resp2()])
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            frame => frame,
+        }
+    }
+}
+
+impl From<&str> for RespFrame {
+    fn from(s: &str) -> Self {
+        BulkString::from(s).into()
+    }
+}
+
+impl From<String> for RespFrame {
+    fn from(s: String) -> Self {
+        BulkString::from(s).into()
+    }
+}
+
+impl From<Vec<u8>> for RespFrame {
+    fn from(s: Vec<u8>) -> Self {
+        BulkString::from(s).into()
+    }
+}
+
+impl<T: Into<RespFrame>> From<Option<T>> for RespFrame {
+    fn from(v: Option<T>) -> Self {
+        match v {
+            Some(v) => v.into(),
+            None => RespNull.into(),
+        }
+    }
+}
+
 impl RespDecode for RespFrame {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let mut iter = buf.iter().peekable();
-        match iter.peek() {
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        RespFrame::decode_nested(buf, 0)
+    }
+}
+
+impl RespFrame {
+    /// Decode a frame that is an element of `depth` enclosing aggregates.
+    pub(super) fn decode_nested(buf: &[u8], depth: usize) -> Result<(Self, usize), RespError> {
+        if depth > decoder::max_nesting() {
+            return Err(RespError::InvalidFrame("max depth exceeded".into()));
+        }
+        match buf.first() {
             Some(b'+') => {
-                let frame = SimpleString::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = SimpleString::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
             Some(b'-') => {
-                let frame = SimpleError::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = SimpleError::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
             Some(b':') => {
-                let frame = i64::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = i64::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
-            Some(b'$') => match RespNullBulkString::decode(buf) {
-                Ok(frame) => Ok(frame.into()),
+            Some(b'$') => match RespNullBulkString::decode_with_len(buf) {
+                Ok((frame, len)) => Ok((frame.into(), len)),
                 Err(RespError::NotComplete) => Err(RespError::NotComplete),
                 Err(_) => {
-                    let frame = BulkString::decode(buf)?;
-                    Ok(frame.into())
+                    let (frame, len) = BulkString::decode_with_len(buf)?;
+                    Ok((frame.into(), len))
                 }
             },
-            Some(b'*') => match RespNullArray::decode(buf) {
-                Ok(frame) => Ok(frame.into()),
+            Some(b'*') => match RespNullArray::decode_with_len(buf) {
+                Ok((frame, len)) => Ok((frame.into(), len)),
                 Err(RespError::NotComplete) => Err(RespError::NotComplete),
                 Err(_) => {
-                    let frame = RespArray::decode(buf)?;
-                    Ok(frame.into())
+                    let (frame, len) = RespArray::decode_nested(buf, depth)?;
+                    Ok((frame.into(), len))
                 }
             },
             Some(b'_') => {
-                let frame = RespNull::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = RespNull::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
             Some(b'#') => {
-                let frame = bool::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = bool::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
             Some(b',') => {
-                let frame = f64::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = f64::decode_with_len(buf)?;
+                Ok((frame.into(), len))
             }
             Some(b'%') => {
-                let frame = RespMap::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = RespMap::decode_nested(buf, depth)?;
+                Ok((frame.into(), len))
             }
             Some(b'~') => {
-                let frame = RespSet::decode(buf)?;
-                Ok(frame.into())
+                let (frame, len) = RespSet::decode_nested(buf, depth)?;
+                Ok((frame.into(), len))
             }
             None => Err(RespError::NotComplete),
             _ => Err(RespError::InvalidFrameType(format!(
@@ -83,3 +208,203 @@ impl RespDecode for RespFrame {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespEncode;
+
+    #[test]
+    fn test_from_conversions() {
+        let hello = RespFrame::BulkString(BulkString::new("hello"));
+        assert_eq!(RespFrame::from("hello"), hello);
+        assert_eq!(RespFrame::from("hello".to_string()), hello);
+        assert_eq!(RespFrame::from(b"hello".to_vec()), hello);
+        assert_eq!(RespFrame::from(Some("hello")), hello);
+        assert_eq!(RespFrame::from(None::<String>), RespNull.into());
+        assert_eq!(RespFrame::from(42), RespFrame::Integer(42));
+        assert_eq!(RespFrame::from(Some(1.5)), RespFrame::Double(1.5));
+        assert_eq!(RespFrame::from(true), RespFrame::Boolean(true));
+        assert_eq!(RespFrame::integer(-3).encode(), b":-3\r\n");
+        assert_eq!(RespFrame::ok().encode(), b"+OK\r\n");
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        let mut map = RespMap::new();
+        map.insert("key".to_string(), RespFrame::Double(-1.5));
+        map.insert(
+            "nested".to_string(),
+            RespSet::new([RespFrame::Integer(0)]).into(),
+        );
+        let frames: Vec<RespFrame> = vec![
+            SimpleString::new("OK").into(),
+            SimpleError::new("ERR oops").into(),
+            RespFrame::Integer(0),
+            RespFrame::Integer(-42),
+            RespFrame::Integer(i64::MIN),
+            RespFrame::Integer(i64::MAX),
+            BulkString::new("").into(),
+            BulkString::new(vec![b'x'; 1234]).into(),
+            RespNullBulkString.into(),
+            RespNullArray.into(),
+            RespNull.into(),
+            RespFrame::Boolean(false),
+            RespFrame::Double(123.456),
+            RespFrame::Double(1.23456e+10),
+            RespFrame::Double(-1.5e-9),
+            RespArray::new(vec![]).into(),
+            RespArray::new(
+                (0..12)
+                    .map(|i| RespArray::new([RespFrame::Integer(i), "item".into()]).into())
+                    .collect::<Vec<RespFrame>>(),
+            )
+            .into(),
+            map.into(),
+        ];
+        for frame in frames {
+            assert_eq!(
+                frame.encoded_len(),
+                frame.clone().encode().len(),
+                "{frame:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_with_len() {
+        let data = b"*2\r\n$3\r\nget\r\n%1\r\n+k\r\n#t\r\n:1\r\n";
+        let (frame, len) = RespFrame::decode_with_len(data).unwrap();
+        assert_eq!(len, data.len() - 4);
+        assert_eq!(frame.encode(), &data[..len]);
+        let (frame, len) = RespFrame::decode_with_len(&data[len..]).unwrap();
+        assert_eq!((frame, len), (RespFrame::Integer(1), 4));
+
+        for end in 0..data.len() - 4 {
+            assert_eq!(
+                RespFrame::decode_with_len(&data[..end]),
+                Err(RespError::NotComplete)
+            );
+        }
+    }
+
+    // xorshift, so the random inputs are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    fn assert_decodes_without_panic(input: &[u8]) {
+        if let Ok((_, len)) = RespFrame::decode_with_len(input) {
+            assert!(len <= input.len(), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_arbitrary_bytes() {
+        const ALPHABET: &[u8] = b"+-:$*_#,%~\r\n0123456789-.tfinx";
+        let valid =
+            b"*3\r\n$3\r\nset\r\n%1\r\n+k\r\n~2\r\n#t\r\n,-1.5\r\n:-7\r\n$-1\r\n*-1\r\n_\r\n";
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20_000 {
+            let len = rng.below(48);
+            let input = (0..len)
+                .map(|_| ALPHABET[rng.below(ALPHABET.len())])
+                .collect::<Vec<_>>();
+            assert_decodes_without_panic(&input);
+
+            // a valid frame with a few bytes changed, then truncated
+            let mut input = valid.to_vec();
+            for _ in 0..1 + rng.below(3) {
+                let i = rng.below(input.len());
+                input[i] = ALPHABET[rng.below(ALPHABET.len())];
+            }
+            input.truncate(rng.below(input.len() + 1));
+            assert_decodes_without_panic(&input);
+        }
+    }
+
+    #[test]
+    fn test_decode_deep_nesting() {
+        let nested = |depth: usize| {
+            let mut buf = b"*1\r\n".repeat(depth);
+            buf.extend_from_slice(b":1\r\n");
+            buf
+        };
+        let max = decoder::DEFAULT_MAX_NESTING;
+        let (_, len) = RespFrame::decode_with_len(&nested(max)).unwrap();
+        assert_eq!(len, nested(max).len());
+        assert_eq!(
+            RespFrame::decode_with_len(&nested(max + 1)),
+            Err(RespError::InvalidFrame("max depth exceeded".into()))
+        );
+        // a crafted request can't overflow the stack
+        assert_eq!(
+            RespFrame::decode_with_len(&nested(10_000)),
+            Err(RespError::InvalidFrame("max depth exceeded".into()))
+        );
+        // mixed aggregates, without the end of the frame
+        let buf = b"%1\r\n+k\r\n~1\r\n*1\r\n".repeat(100_000);
+        assert!(matches!(
+            RespFrame::decode_with_len(&buf),
+            Err(RespError::InvalidFrame(_))
+        ));
+    }
+
+    #[test]
+    fn test_as_bytes_and_as_str() {
+        let bulk: RespFrame = BulkString::new(b"a\xffb".to_vec()).into();
+        assert_eq!(bulk.as_bytes(), Some(&b"a\xffb"[..]));
+        assert_eq!(bulk.as_str().as_deref(), Some("a\u{fffd}b"));
+        let simple: RespFrame = SimpleString::new("OK").into();
+        assert_eq!(simple.as_bytes(), Some(&b"OK"[..]));
+        assert!(matches!(simple.as_str(), Some(Cow::Borrowed("OK"))));
+        assert_eq!(RespFrame::Integer(1).as_bytes(), None);
+        assert_eq!(RespFrame::from(RespNull).as_str(), None);
+        assert_eq!(RespFrame::from(SimpleError::new("ERR")).as_bytes(), None);
+    }
+
+    #[test]
+    fn test_content_eq() {
+        let ok: RespFrame = SimpleString::new("OK").into();
+        let bulk_ok: RespFrame = BulkString::new("OK").into();
+        assert_ne!(ok, bulk_ok);
+        assert!(ok.content_eq(&bulk_ok));
+        assert!(bulk_ok.content_eq(&ok));
+        assert!(!ok.content_eq(&BulkString::new("KO").into()));
+        assert!(!ok.content_eq(&SimpleError::new("OK").into()));
+        assert!(RespFrame::Integer(1).content_eq(&1.into()));
+        assert!(!RespFrame::Integer(1).content_eq(&BulkString::new("1").into()));
+    }
+
+    #[test]
+    fn test_into_resp2() {
+        let frame: RespFrame = RespNull.into();
+        assert_eq!(frame.into_resp2().encode(), b"$-1\r\n");
+
+        let frame: RespFrame = true.into();
+        assert_eq!(frame.into_resp2().encode(), b":1\r\n");
+
+        let frame: RespFrame = 1.5.into();
+        assert_eq!(frame.into_resp2().encode(), b"$3\r\n1.5\r\n");
+
+        let mut map = RespMap::new();
+        map.insert("proto".to_string(), 2.into());
+        map.insert("set".to_string(), RespSet::new([RespNull.into()]).into());
+        let frame: RespFrame = map.into();
+        assert_eq!(
+            frame.into_resp2().encode(),
+            b"*4\r\n$5\r\nproto\r\n:2\r\n$3\r\nset\r\n*1\r\n$-1\r\n"
+        );
+    }
+}
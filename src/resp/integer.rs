@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use std::io::Write;
 
 use crate::{RespDecode, RespEncode, RespError};
 
@@ -6,19 +6,18 @@ use super::extract_simpe_frame_data;
 
 // - integer: ":[<+|->]<value>\r\n"
 impl RespEncode for i64 {
-    fn encode(self) -> Vec<u8> {
-        let sign = if self < 0 { "" } else { "+" };
-        format!(":{}{}\r\n", sign, self).into_bytes()
+    // no `+` sign, redis never sends one and clients like hiredis reject it
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, ":{}\r\n", self).expect("writing to a Vec can't fail");
     }
 }
 
 // - integer: ":[<+|->]<value>\r\n"
 impl RespDecode for i64 {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let end = extract_simpe_frame_data(buf, ":")?;
-        let data = buf.split_to(end + 2);
-        let s = String::from_utf8_lossy(&data[1..end]);
-        Ok(s.parse()?)
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let (end, next) = extract_simpe_frame_data(buf, ":")?;
+        let s = String::from_utf8_lossy(&buf[1..end]);
+        Ok((s.parse()?, next))
     }
 }
 
@@ -31,7 +30,7 @@ mod tests {
     #[test]
     fn test_integer_encode() {
         let frame: RespFrame = 42.into();
-        assert_eq!(frame.encode(), b":+42\r\n");
+        assert_eq!(frame.encode(), b":42\r\n");
         let frame: RespFrame = (-42).into();
         assert_eq!(frame.encode(), b":-42\r\n");
     }
@@ -1,48 +1,56 @@
 use std::{
     collections::BTreeMap,
+    io::Write,
     ops::{Deref, DerefMut},
 };
 
-use bytes::{Buf, BytesMut};
-
 use crate::{RespDecode, RespEncode, RespError, RespFrame, SimpleString};
 
-use super::{parse_length, BUF_CAP, CRLF_LEN};
+use super::{header_len, parse_length, CRLF_LEN};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub struct RespMap(BTreeMap<String, RespFrame>);
+pub struct RespMap(pub(super) BTreeMap<String, RespFrame>);
 
 // - map: "%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
 impl RespEncode for RespMap {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("%{}\r\n", self.len()).into_bytes());
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, "%{}\r\n", self.len()).expect("writing to a Vec can't fail");
         for (key, value) in self.0 {
-            buf.extend_from_slice(&SimpleString::new(key).encode());
-            buf.extend_from_slice(&value.encode());
+            SimpleString::new(key).encode_into(buf);
+            value.encode_into(buf);
         }
+    }
+
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_into(&mut buf);
         buf
     }
 }
 
 // - map: "%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>"
 impl RespDecode for RespMap {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        RespMap::decode_nested(buf, 0)
+    }
+}
+
+impl RespMap {
+    /// Decode the map, nested like `RespArray::decode_nested`.
+    pub(super) fn decode_nested(buf: &[u8], depth: usize) -> Result<(Self, usize), RespError> {
         let prefix = "%";
-        let (end, len) = parse_length(buf, prefix)?;
-        let mut newbuf = buf.clone();
-        newbuf.advance(end + CRLF_LEN);
+        let (mut consumed, len) = parse_length(buf, prefix)?;
 
         let mut frames = RespMap::new();
         for _ in 0..len {
-            let key = SimpleString::decode(&mut newbuf).map_err(|_| RespError::NotComplete)?;
-            let value = RespFrame::decode(&mut newbuf).map_err(|_| RespError::NotComplete)?;
+            let (key, n) = SimpleString::decode_with_len(&buf[consumed..])?;
+            consumed += n;
+            let (value, n) = RespFrame::decode_nested(&buf[consumed..], depth + 1)?;
+            consumed += n;
             frames.insert(key.0, value);
         }
 
-        buf.advance(buf.len() - newbuf.len());
-
-        Ok(frames)
+        Ok((frames, consumed))
     }
 }
 
@@ -64,6 +72,15 @@ impl RespMap {
     pub fn new() -> Self {
         RespMap(BTreeMap::new())
     }
+
+    /// length of the encoded map, with keys encoded as simple strings
+    pub fn encoded_len(&self) -> usize {
+        header_len(self.len())
+            + self
+                .iter()
+                .map(|(key, value)| 1 + key.len() + CRLF_LEN + value.encoded_len())
+                .sum::<usize>()
+    }
 }
 impl Default for RespMap {
     fn default() -> Self {
@@ -77,6 +94,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
+    use bytes::BytesMut;
 
     #[test]
     fn test_map_encode() {
@@ -1,6 +1,8 @@
 mod array;
 mod bool;
+mod builder;
 mod bulk_string;
+mod decoder;
 mod double;
 mod frame;
 mod integer;
@@ -9,6 +11,7 @@ mod null;
 mod set;
 mod simple_error;
 mod simple_string;
+mod stream;
 
 use anyhow::Result;
 
@@ -19,17 +22,20 @@ use thiserror::Error;
 
 pub use self::{
     array::{RespArray, RespNullArray},
+    builder::{ArrayBuilder, MapBuilder, RespBuilder},
     bulk_string::BulkString,
     bulk_string::RespNullBulkString,
+    decoder::RespDecoder,
     frame::RespFrame,
     map::RespMap,
     null::RespNull,
     set::RespSet,
     simple_error::SimpleError,
     simple_string::SimpleString,
+    stream::{frame_stream, RespStreamDecoder, RespStreamItem},
 };
 
-const BUF_CAP: usize = 4096;
+pub(crate) const BUF_CAP: usize = 4096;
 const CRLF_LEN: usize = 2;
 
 #[derive(Error, Debug, PartialEq)]
@@ -48,65 +54,211 @@ pub enum RespError {
     Utf8Error(#[from] FromUtf8Error),
     #[error("Parse float error: {0}")]
     ParseFloatError(#[from] std::num::ParseFloatError),
+    // the message only, `io::Error` can't be compared
+    #[error("IO error: {0}")]
+    Io(String),
 }
 
 #[enum_dispatch]
 pub trait RespEncode {
-    fn encode(self) -> Vec<u8>;
+    /// Append the encoded frame to `buf`, nested frames write into the same buffer.
+    fn encode_into(self, buf: &mut Vec<u8>);
+
+    fn encode(self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        let mut buf = Vec::new();
+        self.encode_into(&mut buf);
+        buf
+    }
 }
 
-pub trait RespDecode: Sized {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError>;
+// number of bytes of `n` written in decimal, e.g. in a length header
+fn decimal_len(n: u64) -> usize {
+    n.checked_ilog10().map_or(1, |digits| digits as usize + 1)
 }
 
-fn extract_simpe_frame_data(buf: &mut BytesMut, prefix: &str) -> Result<usize, RespError> {
-    if buf.len() < 3 {
-        return Err(RespError::NotComplete);
+// length of an aggregate header, "<prefix><number-of-elements>\r\n"
+fn header_len(len: usize) -> usize {
+    1 + decimal_len(len as u64) + CRLF_LEN
+}
+
+/// Decode a complete frame from the buffer, `RespError::NotComplete` is returned (and
+/// nothing consumed) until the whole frame is buffered. To consume large bulk strings
+/// without buffering them see `RespStreamDecoder`.
+pub trait RespDecode: Sized {
+    /// Decode a complete frame at the start of `buf`, with the number of bytes it takes.
+    /// Nothing is consumed, e.g. for callers managing their own buffers.
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError>;
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (frame, len) = Self::decode_with_len(buf)?;
+        buf.advance(len);
+        Ok(frame)
     }
+}
+
+// the end of the body of the line that starts with `prefix`, the body may be empty, and the
+// start of what follows the line
+fn extract_simpe_frame_data(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError> {
     if !buf.starts_with(prefix.as_bytes()) {
+        if prefix.as_bytes().starts_with(buf) {
+            return Err(RespError::NotComplete);
+        }
         return Err(RespError::InvalidFrameType(format!(
             "expect: SimpleString({}), got: {:?}",
             prefix, buf
         )));
     }
 
-    buf.windows(2)
-        .position(|pair| pair == [b'\r', b'\n'])
-        .ok_or(RespError::NotComplete)
+    find_line_end(buf, prefix.len()).ok_or(RespError::NotComplete)
 }
 
-fn extract_fixed_data(
-    buf: &mut BytesMut,
-    expect: &str,
-    expect_type: &str,
-) -> Result<(), RespError> {
-    if buf.len() < expect.len() {
-        return Err(RespError::NotComplete);
+// the end of the line body starting at `start` and the end of its terminator, a CRLF or, in
+// lenient mode, a bare LF
+fn find_line_end(buf: &[u8], start: usize) -> Option<(usize, usize)> {
+    if decoder::lenient_newlines() {
+        let lf = start + buf[start..].iter().position(|&b| b == b'\n')?;
+        let end = if lf > start && buf[lf - 1] == b'\r' {
+            lf - 1
+        } else {
+            lf
+        };
+        return Some((end, lf + 1));
+    }
+    buf[start..]
+        .windows(CRLF_LEN)
+        .position(|pair| pair == b"\r\n")
+        .map(|pos| (start + pos, start + pos + CRLF_LEN))
+}
+
+// the length of the line terminator at the start of `buf`, `NotComplete` while it may still
+// be one
+fn line_terminator(buf: &[u8]) -> Result<usize, RespError> {
+    if decoder::lenient_newlines() && buf.first() == Some(&b'\n') {
+        return Ok(1);
     }
-    // let data = buf.split_to(expect.len());
-    let data = &buf[..expect.len()];
-    if data != expect.as_bytes() {
-        return Err(RespError::InvalidFrame(format!("expect {:?}", expect)));
+    match buf.get(..CRLF_LEN) {
+        Some(b"\r\n") => Ok(CRLF_LEN),
+        Some(_) => Err(RespError::InvalidFrame("expect a CRLF".into())),
+        None if b"\r\n".starts_with(buf) => Err(RespError::NotComplete),
+        None => Err(RespError::InvalidFrame("expect a CRLF".into())),
     }
+}
 
-    if !buf.starts_with(expect.as_bytes()) {
-        return Err(RespError::InvalidFrameType(format!(
-            "expect: {}, got: {:?}",
-            expect_type, buf
+// the body of a simple string or error, which can't contain a CR or LF: the frame would
+// end there
+fn check_simple_body(data: &[u8], frame_type: &str) -> Result<(), RespError> {
+    if data.iter().any(|&b| b == b'\r' || b == b'\n') {
+        return Err(RespError::InvalidFrame(format!(
+            "{} contains a CR or LF: {:?}",
+            frame_type,
+            String::from_utf8_lossy(data)
         )));
     }
-
-    buf.advance(expect.len());
     Ok(())
 }
 
-fn parse_length(buf: &mut BytesMut, prefix: &str) -> Result<(usize, usize), RespError> {
-    let end = extract_simpe_frame_data(buf, prefix)?;
+// like redis, CR and LF are replaced with spaces so the text fits a simple frame
+fn single_line(s: String) -> String {
+    if s.contains(['\r', '\n']) {
+        s.replace(['\r', '\n'], " ")
+    } else {
+        s
+    }
+}
+
+// the length of the fixed frame `expect` at the start of `buf`
+fn extract_fixed_data(buf: &[u8], expect: &str, expect_type: &str) -> Result<usize, RespError> {
+    if decoder::lenient_newlines() {
+        if let Some(line) = expect.strip_suffix("\r\n") {
+            let bare = line.len() + 1;
+            if buf.starts_with(line.as_bytes()) && buf.get(line.len()) == Some(&b'\n') {
+                return Ok(bare);
+            }
+        }
+    }
+    // a buffered prefix of `expect` may still complete, any other byte can't
+    let len = buf.len().min(expect.len());
+    if buf[..len] != expect.as_bytes()[..len] {
+        if buf[0] != expect.as_bytes()[0] {
+            return Err(RespError::InvalidFrameType(format!(
+                "expect: {}, got: {:?}",
+                expect_type, buf
+            )));
+        }
+        return Err(RespError::InvalidFrame(format!(
+            "expect {:?}, got: {:?}",
+            expect, buf
+        )));
+    }
+    if buf.len() < expect.len() {
+        return Err(RespError::NotComplete);
+    }
+
+    Ok(expect.len())
+}
+
+// the start of what follows the length line, and the length
+fn parse_length(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError> {
+    let (end, next) = extract_simpe_frame_data(buf, prefix)?;
     let s = String::from_utf8_lossy(&buf[prefix.len()..end]);
-    Ok((end, s.parse()?))
+    Ok((next, s.parse()?))
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO: Add tests
+    use super::*;
+
+    fn extract(data: &[u8]) -> Result<usize, RespError> {
+        extract_fixed_data(data, "#t\r\n", "Bool")
+    }
+
+    #[test]
+    fn test_extract_fixed_data() {
+        assert_eq!(extract(b""), Err(RespError::NotComplete));
+        assert_eq!(extract(b"#"), Err(RespError::NotComplete));
+        assert_eq!(extract(b"#t"), Err(RespError::NotComplete));
+        assert_eq!(extract(b"#t\r\n:1\r\n"), Ok(4));
+        assert!(matches!(extract(b"#x"), Err(RespError::InvalidFrame(_))));
+        assert!(matches!(
+            extract(b"#x\r\n"),
+            Err(RespError::InvalidFrame(_))
+        ));
+        assert!(matches!(
+            extract(b"+t"),
+            Err(RespError::InvalidFrameType(_))
+        ));
+    }
+
+    #[test]
+    fn test_minimal_simple_frames() -> Result<(), RespError> {
+        let mut buf = BytesMut::from("+\r\n-\r\n");
+        assert_eq!(RespFrame::decode(&mut buf)?, SimpleString::new("").into());
+        assert_eq!(RespFrame::decode(&mut buf)?, SimpleError::new("").into());
+        assert!(buf.is_empty());
+
+        for partial in [&b""[..], b"+", b"+\r"] {
+            assert_eq!(
+                SimpleString::decode_with_len(partial),
+                Err(RespError::NotComplete)
+            );
+        }
+        // a wrong prefix is an error however short the buffer
+        assert!(matches!(
+            SimpleString::decode_with_len(b"-"),
+            Err(RespError::InvalidFrameType(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_short_frame_is_not_taken_for_null() -> Result<(), RespError> {
+        // shorter than "*-1\r\n" and "$-1\r\n", but complete
+        let mut buf = BytesMut::from("*0\r\n$0\r\n\r\n");
+        assert_eq!(RespFrame::decode(&mut buf)?, RespArray::new(vec![]).into());
+        assert_eq!(RespFrame::decode(&mut buf)?, BulkString::new("").into());
+        Ok(())
+    }
 }
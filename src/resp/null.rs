@@ -1,5 +1,3 @@
-use bytes::BytesMut;
-
 use crate::{RespDecode, RespEncode, RespError};
 
 use super::extract_fixed_data;
@@ -9,16 +7,16 @@ pub struct RespNull;
 
 // - null: "_\r\n"
 impl RespEncode for RespNull {
-    fn encode(self) -> Vec<u8> {
-        "_\r\n".into()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"_\r\n");
     }
 }
 
 // - null: "_\r\n"
 impl RespDecode for RespNull {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        extract_fixed_data(buf, "_\r\n", "Null")?;
-        Ok(RespNull)
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let len = extract_fixed_data(buf, "_\r\n", "Null")?;
+        Ok((RespNull, len))
     }
 }
 
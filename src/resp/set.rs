@@ -1,41 +1,49 @@
-use std::ops::Deref;
-
-use bytes::{Buf, BytesMut};
+use std::{io::Write, ops::Deref};
 
 use crate::{RespDecode, RespEncode, RespError, RespFrame};
 
-use super::{parse_length, BUF_CAP, CRLF_LEN};
+use super::{header_len, parse_length};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct RespSet(pub(super) Vec<RespFrame>);
 
 // - set: "~<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespEncode for RespSet {
-    fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("~{}\r\n", self.len()).into_bytes());
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        write!(buf, "~{}\r\n", self.len()).expect("writing to a Vec can't fail");
         for value in self.0 {
-            buf.extend_from_slice(&value.encode());
+            value.encode_into(buf);
         }
+    }
+
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_into(&mut buf);
         buf
     }
 }
 
 // - set: "~<number-of-elements>\r\n<element-1>...<element-n>"
 impl RespDecode for RespSet {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        RespSet::decode_nested(buf, 0)
+    }
+}
+
+impl RespSet {
+    /// Decode the set, nested like `RespArray::decode_nested`.
+    pub(super) fn decode_nested(buf: &[u8], depth: usize) -> Result<(Self, usize), RespError> {
         let prefix = "~";
-        let (end, len) = parse_length(buf, prefix)?;
+        let (mut consumed, len) = parse_length(buf, prefix)?;
 
-        let mut newbuf = buf.clone();
-        newbuf.advance(end + CRLF_LEN);
         let mut frames = Vec::new();
         for _ in 0..len {
-            frames.push(RespFrame::decode(&mut newbuf).map_err(|_| RespError::NotComplete)?);
+            let (frame, n) = RespFrame::decode_nested(&buf[consumed..], depth + 1)?;
+            frames.push(frame);
+            consumed += n;
         }
 
-        buf.advance(buf.len() - newbuf.len());
-        Ok(RespSet::new(frames))
+        Ok((RespSet::new(frames), consumed))
     }
 }
 
@@ -51,6 +59,11 @@ impl RespSet {
     pub fn new(v: impl Into<Vec<RespFrame>>) -> Self {
         RespSet(v.into())
     }
+
+    /// length of the encoded set
+    pub fn encoded_len(&self) -> usize {
+        header_len(self.len()) + self.iter().map(RespFrame::encoded_len).sum::<usize>()
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +72,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
+    use bytes::BytesMut;
 
     #[test]
     fn test_set_encode() {
@@ -69,7 +83,7 @@ mod tests {
         let frame: RespFrame = set.into();
         assert_eq!(
             frame.encode(),
-            b"~2\r\n*2\r\n:+1234\r\n#t\r\n$5\r\nworld\r\n"
+            b"~2\r\n*2\r\n:1234\r\n#t\r\n$5\r\nworld\r\n"
         )
     }
 
@@ -1,34 +1,35 @@
 use std::ops::Deref;
 
-use bytes::BytesMut;
-
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::extract_simpe_frame_data;
+use super::{check_simple_body, extract_simpe_frame_data, single_line};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct SimpleError(String);
 
 impl SimpleError {
+    /// The message on a single line, CR and LF being replaced with spaces.
     pub fn new(s: impl Into<String>) -> Self {
-        SimpleError(s.into())
+        SimpleError(single_line(s.into()))
     }
 }
 
 // - simple error: "-Error message\r\n"
 impl RespEncode for SimpleError {
-    fn encode(self) -> Vec<u8> {
-        format!("-{}\r\n", self.0).into_bytes()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.push(b'-');
+        buf.extend_from_slice(self.0.as_bytes());
+        buf.extend_from_slice(b"\r\n");
     }
 }
 
 // - simple error: "-Error message\r\n"
 impl RespDecode for SimpleError {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let end = extract_simpe_frame_data(buf, "-")?;
-        let data = buf.split_to(end + 2);
-        let s = String::from_utf8_lossy(&data[1..end]);
-        Ok(SimpleError::new(s))
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let (end, next) = extract_simpe_frame_data(buf, "-")?;
+        check_simple_body(&buf[1..end], "simple error")?;
+        let s = String::from_utf8_lossy(&buf[1..end]);
+        Ok((SimpleError::new(s), next))
     }
 }
 
@@ -46,6 +47,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
+    use bytes::BytesMut;
 
     #[test]
     fn test_error_encode() {
@@ -53,6 +55,22 @@ mod tests {
         assert_eq!(frame.encode(), b"-Error message\r\n");
     }
 
+    #[test]
+    fn test_error_with_newline_encode() -> Result<()> {
+        let frame: RespFrame = SimpleError::new("ERR bad\r\nvalue").into();
+        let mut buf = BytesMut::from(&frame.encode()[..]);
+        assert_eq!(&buf[..], b"-ERR bad  value\r\n");
+        assert_eq!(
+            RespFrame::decode(&mut buf)?,
+            SimpleError::new("ERR bad  value").into()
+        );
+        assert!(buf.is_empty());
+
+        let ret = SimpleError::decode_with_len(b"-ERR a\nb\r\n");
+        assert!(matches!(ret, Err(RespError::InvalidFrame(_))));
+        Ok(())
+    }
+
     #[test]
     fn test_simple_error_decode() -> Result<()> {
         let mut buf = BytesMut::new();
@@ -1,34 +1,53 @@
-use std::ops::Deref;
-
-use bytes::BytesMut;
+use std::{
+    ops::Deref,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crate::{RespDecode, RespEncode, RespError};
 
-use super::extract_simpe_frame_data;
+use super::{check_simple_body, extract_simpe_frame_data, single_line};
+
+// see `SimpleString::set_strict_utf8`
+static STRICT_UTF8: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct SimpleString(pub(super) String);
 
 impl SimpleString {
+    /// The string on a single line, CR and LF being replaced with spaces.
     pub fn new(s: impl Into<String>) -> Self {
-        SimpleString(s.into())
+        SimpleString(single_line(s.into()))
+    }
+
+    /// Whether decoding rejects a simple string that isn't valid UTF-8 with
+    /// `RespError::Utf8Error`, rather than replacing the invalid bytes. Off by default, the
+    /// setting applies to every decoder of the process.
+    pub fn set_strict_utf8(strict: bool) {
+        STRICT_UTF8.store(strict, Ordering::Relaxed);
     }
 }
 
-// - simple string: "+OK\r\n"
+// - simple string: "+OK\r\n", which can't contain a CR or LF
 impl RespDecode for SimpleString {
-    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
-        let end = extract_simpe_frame_data(buf, "+")?;
-        let data = buf.split_to(end + 2);
-        let s = String::from_utf8_lossy(&data[1..end]);
-        Ok(SimpleString::new(s))
+    fn decode_with_len(buf: &[u8]) -> Result<(Self, usize), RespError> {
+        let (end, next) = extract_simpe_frame_data(buf, "+")?;
+        let data = &buf[1..end];
+        check_simple_body(data, "simple string")?;
+        let s = if STRICT_UTF8.load(Ordering::Relaxed) {
+            String::from_utf8(data.to_vec())?
+        } else {
+            String::from_utf8_lossy(data).into_owned()
+        };
+        Ok((SimpleString::new(s), next))
     }
 }
 
 // - simple string: "+OK\r\n"
 impl RespEncode for SimpleString {
-    fn encode(self) -> Vec<u8> {
-        format!("+{}\r\n", self.0).into_bytes()
+    fn encode_into(self, buf: &mut Vec<u8>) {
+        buf.push(b'+');
+        buf.extend_from_slice(self.as_bytes());
+        buf.extend_from_slice(b"\r\n");
     }
 }
 
@@ -46,7 +65,7 @@ mod tests {
 
     use super::*;
     use anyhow::Result;
-    use bytes::BufMut;
+    use bytes::{BufMut, BytesMut};
 
     #[test]
     fn test_simple_string_encode() {
@@ -54,6 +73,19 @@ mod tests {
         assert_eq!(frame.encode(), b"+OK\r\n");
     }
 
+    #[test]
+    fn test_simple_string_is_a_single_line() -> Result<()> {
+        let frame: RespFrame = SimpleString::new("a\r\nb\nc").into();
+        let mut buf = BytesMut::from(&frame.encode()[..]);
+        assert_eq!(&buf[..], b"+a  b c\r\n");
+        assert_eq!(
+            RespFrame::decode(&mut buf)?,
+            SimpleString::new("a  b c").into()
+        );
+        assert!(buf.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_simple_string_decode() -> Result<()> {
         let mut buf = BytesMut::new();
@@ -73,4 +105,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_empty_simple_string_decode() -> Result<()> {
+        let (frame, len) = SimpleString::decode_with_len(b"+\r\n+OK\r\n")?;
+        assert_eq!((frame, len), (SimpleString::new(""), 3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simple_string_decode_embedded_newline() {
+        for data in [&b"+hel\nlo\r\n"[..], b"+hel\rlo\r\n", b"+hello\n\r\n"] {
+            let ret = SimpleString::decode_with_len(data);
+            assert!(
+                matches!(ret, Err(RespError::InvalidFrame(_))),
+                "{:?}: {:?}",
+                data,
+                ret
+            );
+        }
+    }
+
+    #[test]
+    fn test_simple_string_decode_invalid_utf8() -> Result<()> {
+        let data = b"+caf\xe9\r\n";
+        let (frame, _) = SimpleString::decode_with_len(data)?;
+        assert_eq!(frame, SimpleString::new("caf\u{fffd}"));
+
+        SimpleString::set_strict_utf8(true);
+        let ret = SimpleString::decode_with_len(data);
+        SimpleString::set_strict_utf8(false);
+        assert!(matches!(ret, Err(RespError::Utf8Error(_))));
+        Ok(())
+    }
 }
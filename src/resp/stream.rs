@@ -0,0 +1,283 @@
+use anyhow::Result;
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{stream, Stream};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{RespDecode, RespError, RespFrame};
+
+use super::{BUF_CAP, CRLF_LEN};
+
+/// An item produced by `RespStreamDecoder`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RespStreamItem {
+    /// header of an array of `len` elements, the elements follow as separate items
+    ArrayStart(usize),
+    /// header of a bulk string of `len` bytes, the body follows as `BulkChunk`s
+    BulkStart(usize),
+    /// a piece of the current bulk string body, at most `BUF_CAP` bytes
+    BulkChunk(Bytes),
+    /// the current bulk string body is complete
+    BulkEnd,
+    /// any other frame, decoded as a whole with `RespFrame::decode`
+    Frame(RespFrame),
+}
+
+/// Incremental RESP decoder reading from an async reader.
+///
+/// Unlike `RespDecode::decode`, which needs the complete frame in the buffer, this yields
+/// array and bulk string headers as soon as they are read and hands out bulk string bodies
+/// in chunks, so a multi-megabyte value never has to be buffered as a whole. Other frames
+/// (simple strings, integers, maps, ...) are small and decoded as a whole.
+pub struct RespStreamDecoder<R> {
+    reader: R,
+    buf: BytesMut,
+    // bytes of the current bulk string body not yet yielded
+    remaining: usize,
+    // the body was yielded, its trailing CRLF wasn't
+    in_bulk: bool,
+}
+
+impl<R: AsyncRead + Unpin> RespStreamDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: BytesMut::with_capacity(BUF_CAP),
+            remaining: 0,
+            in_bulk: false,
+        }
+    }
+
+    /// Read the next item, `None` once the reader is exhausted between frames.
+    pub async fn next(&mut self) -> Result<Option<RespStreamItem>> {
+        loop {
+            if self.in_bulk {
+                if let Some(item) = self.next_bulk_item()? {
+                    return Ok(Some(item));
+                }
+            } else if let Some(item) = self.next_header_or_frame()? {
+                return Ok(Some(item));
+            }
+
+            self.buf.reserve(BUF_CAP);
+            if self.reader.read_buf(&mut self.buf).await? == 0 {
+                if self.buf.is_empty() && !self.in_bulk {
+                    return Ok(None);
+                }
+                return Err(RespError::NotComplete.into());
+            }
+        }
+    }
+
+    fn next_bulk_item(&mut self) -> Result<Option<RespStreamItem>, RespError> {
+        if self.remaining > 0 {
+            if self.buf.is_empty() {
+                return Ok(None);
+            }
+            let n = self.remaining.min(self.buf.len()).min(BUF_CAP);
+            self.remaining -= n;
+            return Ok(Some(RespStreamItem::BulkChunk(
+                self.buf.split_to(n).freeze(),
+            )));
+        }
+
+        if self.buf.len() < CRLF_LEN {
+            return Ok(None);
+        }
+        if &self.buf[..CRLF_LEN] != b"\r\n" {
+            return Err(RespError::InvalidFrame(
+                "bulk string is not terminated by CRLF".into(),
+            ));
+        }
+        self.buf.advance(CRLF_LEN);
+        self.in_bulk = false;
+        Ok(Some(RespStreamItem::BulkEnd))
+    }
+
+    fn next_header_or_frame(&mut self) -> Result<Option<RespStreamItem>, RespError> {
+        match self.buf.first() {
+            None => return Ok(None),
+            // null arrays and null bulk strings are left to `RespFrame::decode`
+            Some(b'*' | b'$') if !self.buf.starts_with(b"*-") && !self.buf.starts_with(b"$-") => {
+                let Some(end) = self.buf.windows(CRLF_LEN).position(|w| w == b"\r\n") else {
+                    return Ok(None);
+                };
+                let len = String::from_utf8_lossy(&self.buf[1..end]).parse()?;
+                let is_array = self.buf[0] == b'*';
+                self.buf.advance(end + CRLF_LEN);
+                if is_array {
+                    return Ok(Some(RespStreamItem::ArrayStart(len)));
+                }
+                self.remaining = len;
+                self.in_bulk = true;
+                return Ok(Some(RespStreamItem::BulkStart(len)));
+            }
+            _ => {}
+        }
+
+        match RespFrame::decode(&mut self.buf) {
+            Ok(frame) => Ok(Some(RespStreamItem::Frame(frame))),
+            Err(RespError::NotComplete) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Decode whole frames from `reader`, reading more whenever the buffered bytes end in the
+/// middle of a frame. The stream ends once the reader is exhausted between frames; a
+/// truncated last frame yields `NotComplete`. Nothing follows an error.
+pub fn frame_stream<R: AsyncRead + Unpin>(
+    reader: R,
+) -> impl Stream<Item = Result<RespFrame, RespError>> {
+    let state = Some((reader, BytesMut::with_capacity(BUF_CAP)));
+    stream::unfold(state, |state| async move {
+        let (mut reader, mut buf) = state?;
+        loop {
+            match RespFrame::decode(&mut buf) {
+                Ok(frame) => return Some((Ok(frame), Some((reader, buf)))),
+                Err(RespError::NotComplete) => {}
+                Err(e) => return Some((Err(e), None)),
+            }
+
+            buf.reserve(BUF_CAP);
+            match reader.read_buf(&mut buf).await {
+                Ok(0) if buf.is_empty() => return None,
+                Ok(0) => return Some((Err(RespError::NotComplete), None)),
+                Ok(_) => {}
+                Err(e) => return Some((Err(RespError::Io(e.to_string())), None)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BulkString, RespArray, RespNullBulkString, SimpleString};
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_stream_decode_command() -> Result<()> {
+        let data: &[u8] = b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n+OK\r\n$-1\r\n";
+        let mut decoder = RespStreamDecoder::new(data);
+
+        let mut items = Vec::new();
+        while let Some(item) = decoder.next().await? {
+            items.push(item);
+        }
+        assert_eq!(
+            items,
+            vec![
+                RespStreamItem::ArrayStart(2),
+                RespStreamItem::BulkStart(3),
+                RespStreamItem::BulkChunk(Bytes::from_static(b"get")),
+                RespStreamItem::BulkEnd,
+                RespStreamItem::BulkStart(5),
+                RespStreamItem::BulkChunk(Bytes::from_static(b"hello")),
+                RespStreamItem::BulkEnd,
+                RespStreamItem::Frame(SimpleString::new("OK").into()),
+                RespStreamItem::Frame(RespNullBulkString.into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_decode_large_bulk_in_chunks() -> Result<()> {
+        let len = BUF_CAP * 4 + 10;
+        let (mut client, server) = tokio::io::duplex(BUF_CAP);
+        let writer = tokio::spawn(async move {
+            client.write_all(format!("${}\r\n", len).as_bytes()).await?;
+            client.write_all(&vec![b'x'; len]).await?;
+            client.write_all(b"\r\n").await?;
+            anyhow::Ok(())
+        });
+
+        let mut decoder = RespStreamDecoder::new(server);
+        assert_eq!(decoder.next().await?, Some(RespStreamItem::BulkStart(len)));
+        let mut total = 0;
+        loop {
+            match decoder.next().await? {
+                Some(RespStreamItem::BulkChunk(chunk)) => {
+                    assert!(chunk.len() <= BUF_CAP);
+                    assert!(chunk.iter().all(|&b| b == b'x'));
+                    total += chunk.len();
+                }
+                Some(RespStreamItem::BulkEnd) => break,
+                item => panic!("unexpected item: {:?}", item),
+            }
+        }
+        assert_eq!(total, len);
+        assert_eq!(decoder.next().await?, None);
+        writer.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_frame_stream() {
+        let data: &[u8] = b"*2\r\n$3\r\nget\r\n$5\r\nhello\r\n+OK\r\n:42\r\n";
+        let frames: Vec<_> = frame_stream(data).collect().await;
+        assert_eq!(
+            frames,
+            vec![
+                Ok(RespArray::new(vec![
+                    BulkString::from("get").into(),
+                    BulkString::from("hello").into()
+                ])
+                .into()),
+                Ok(SimpleString::new("OK").into()),
+                Ok(RespFrame::Integer(42)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frame_stream_reads_split_frames() {
+        let (mut client, server) = tokio::io::duplex(BUF_CAP);
+        let writer = tokio::spawn(async move {
+            for piece in [&b"*1\r\n$3"[..], b"\r\nge", b"t\r\n+O", b"K\r\n"] {
+                client.write_all(piece).await?;
+                tokio::task::yield_now().await;
+            }
+            anyhow::Ok(())
+        });
+
+        let frames: Vec<_> = frame_stream(server).collect().await;
+        assert_eq!(
+            frames,
+            vec![
+                Ok(RespArray::new(vec![BulkString::from("get").into()]).into()),
+                Ok(SimpleString::new("OK").into()),
+            ]
+        );
+        writer.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_frame_stream_truncated() {
+        let data: &[u8] = b"+OK\r\n$5\r\nhel";
+        let frames: Vec<_> = frame_stream(data).collect().await;
+        assert_eq!(
+            frames,
+            vec![
+                Ok(SimpleString::new("OK").into()),
+                Err(RespError::NotComplete)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_decode_truncated_bulk() {
+        let data: &[u8] = b"$5\r\nhel";
+        let mut decoder = RespStreamDecoder::new(data);
+        assert_eq!(
+            decoder.next().await.unwrap(),
+            Some(RespStreamItem::BulkStart(5))
+        );
+        assert!(matches!(
+            decoder.next().await.unwrap(),
+            Some(RespStreamItem::BulkChunk(_))
+        ));
+        assert!(decoder.next().await.is_err());
+    }
+}
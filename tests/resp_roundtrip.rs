@@ -0,0 +1,71 @@
+use proptest::{collection, prelude::*};
+use simple_redis::{
+    BulkString, RespArray, RespDecode, RespEncode, RespFrame, RespMap, RespNull, RespNullArray,
+    RespNullBulkString, RespSet, SimpleError, SimpleString,
+};
+
+// the body of a simple string, error or map key, which can't contain a CR or LF
+const SIMPLE_TEXT: &str = "[^\r\n]{0,16}";
+
+fn leaf() -> impl Strategy<Value = RespFrame> {
+    prop_oneof![
+        SIMPLE_TEXT.prop_map(|s| SimpleString::new(s).into()),
+        SIMPLE_TEXT.prop_map(|s| SimpleError::new(s).into()),
+        any::<i64>().prop_map(RespFrame::Integer),
+        collection::vec(any::<u8>(), 0..32).prop_map(|s| BulkString::new(s).into()),
+        Just(RespNullBulkString.into()),
+        Just(RespNullArray.into()),
+        Just(RespNull.into()),
+        any::<bool>().prop_map(RespFrame::Boolean),
+        // every double, NaN, the infinities and the subnormals included
+        any::<f64>().prop_map(RespFrame::Double),
+    ]
+}
+
+// frames nested at most 4 aggregates deep
+fn frame() -> impl Strategy<Value = RespFrame> {
+    leaf().prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            collection::vec(inner.clone(), 0..8).prop_map(|v| RespArray::new(v).into()),
+            collection::vec(inner.clone(), 0..8).prop_map(|v| RespSet::new(v).into()),
+            // the keys are kept sorted, as decoding them does
+            collection::btree_map(SIMPLE_TEXT, inner, 0..8).prop_map(|entries| {
+                let mut map = RespMap::new();
+                map.extend(entries);
+                map.into()
+            }),
+        ]
+    })
+}
+
+// `==`, but NaN equals NaN: doubles are written in the shortest form parsing back to the
+// same value, so anything else must be equal exactly
+fn same_frame(a: &RespFrame, b: &RespFrame) -> bool {
+    let same_all = |a: &[RespFrame], b: &[RespFrame]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_frame(a, b))
+    };
+    match (a, b) {
+        (RespFrame::Double(a), RespFrame::Double(b)) => a == b || (a.is_nan() && b.is_nan()),
+        (RespFrame::Array(a), RespFrame::Array(b)) => same_all(a, b),
+        (RespFrame::Set(a), RespFrame::Set(b)) => same_all(a, b),
+        (RespFrame::Map(a), RespFrame::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && same_frame(va, vb))
+        }
+        _ => a == b,
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_encode_decode_roundtrip(frame in frame()) {
+        let encoded = frame.clone().encode();
+        prop_assert_eq!(frame.encoded_len(), encoded.len());
+
+        let (decoded, len) = RespFrame::decode_with_len(&encoded)?;
+        prop_assert_eq!(len, encoded.len());
+        prop_assert!(same_frame(&frame, &decoded), "{:?} decoded as {:?}", frame, decoded);
+    }
+}